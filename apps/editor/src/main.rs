@@ -1,40 +1,147 @@
+mod project;
+
+use crate::project::{CameraSettings, EditorSettings, LayoutSettings, Project, RecentFiles};
 use dragonglass::{
-    app::{run_application, App, AppConfig, AppState, MouseOrbit},
+    app::{run_application, App, AppConfig, AppState, FlyCamera, MouseOrbit, OrbitView},
     dependencies::{
         anyhow::{Context, Result},
         egui::{
-            self, global_dark_light_mode_switch, menu, Align, DragValue, Id, LayerId,
-            SelectableLabel, Ui,
+            self, global_dark_light_mode_switch, menu, DragValue, Id, LayerId, SelectableLabel, Ui,
         },
         env_logger,
         legion::IntoQuery,
-        log,
+        log, nalgebra_glm as glm,
         petgraph::{graph::NodeIndex, EdgeDirection::Outgoing},
-        rapier3d::prelude::{InteractionGroups, RigidBodyType},
+        rapier3d::prelude::InteractionGroups,
         rfd::FileDialog,
         serde::{Deserialize, Serialize},
+        serde_json::{self, Value},
         winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode},
     },
+    gui::{
+        asset_browser, import_options_editor, inspect_component, material_editor, Inspect,
+        InspectResponse,
+    },
+    render::{GridSettings, MaterialPreview},
     world::{
-        load_gltf, register_component, Ecs, Entity, EntityStore, MeshRender, Name, RigidBody,
-        SceneGraph, Transform, Viewport,
+        axis_gizmo_handles, import_gltf, pick_axis_gizmo_handle, register_component, AssetId,
+        CameraBookmarks, DespawnEntity, Ecs, Entity, EntityStore, Gizmo, GizmoFrame, GizmoMode,
+        GizmoSpace, GroupDrag, History, ImportColliders, ImportOptions, InstantiatePrefab, Name,
+        PrefabWatcher, SceneGraph, Selection, SelectionMode, SetComponent, SetMaterial,
+        SetRigidBody, SetTransform, SetTransforms, SpawnEntity, Transform, Viewport, World,
+        WorldSnapshot, COMPONENT_REGISTRY,
     },
 };
-use std::path::PathBuf;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
+/// Marked selected entities in worlds saved by older editors.
+/// It stays registered so those worlds still load, and is stripped once they do.
 #[derive(Default, Serialize, Deserialize)]
 #[serde(crate = "dragonglass::dependencies::serde")]
 pub struct Selected;
 
+/// A rubber band selection being dragged in the viewport
+struct BoxSelect {
+    /// The corner the drag started at, in pixels from the top left of the window
+    start: glm::Vec2,
+    mode: SelectionMode,
+}
+
+impl BoxSelect {
+    /// Drags shorter than this many pixels are treated as clicks
+    const CLICK_DISTANCE: f32 = 4.0;
+}
+
+#[derive(Copy, Clone)]
+enum HierarchyAction {
+    Rename(Entity),
+    Delete(Entity),
+    AddChild(Entity),
+    SaveAsPrefab(Entity),
+    InstantiatePrefab(Option<Entity>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CameraMode {
+    Orbit,
+    Fly,
+}
+
+impl Default for CameraMode {
+    fn default() -> Self {
+        Self::Orbit
+    }
+}
+
+/// The panels of the editor's docked layout
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "dragonglass::dependencies::serde")]
+pub enum EditorTab {
+    Hierarchy,
+    Inspector,
+    Assets,
+    Console,
+    Viewport,
+}
+
+impl fmt::Display for EditorTab {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PlayMode {
+    Editing,
+    Playing,
+    Paused,
+}
+
+impl Default for PlayMode {
+    fn default() -> Self {
+        Self::Editing
+    }
+}
+
 #[derive(Default)]
 struct Editor {
     camera: MouseOrbit,
-    moving_selected: bool,
-    selected_entity: Option<Entity>,
+    fly_camera: FlyCamera,
+    camera_mode: CameraMode,
+    bookmark_name: String,
+    gizmo: Gizmo,
+    selection: Selection,
+    group_drag: Option<GroupDrag>,
+    box_select: Option<BoxSelect>,
+    /// The next left drag in the viewport selects a box instead of orbiting
+    box_select_armed: bool,
+    /// Where the world axes are drawn, in points from the top left of the window
+    axis_gizmo_region: Option<Viewport>,
+    hierarchy_action: Option<HierarchyAction>,
+    renaming: Option<(Entity, String)>,
+    prefab_watcher: PrefabWatcher,
+    last_prefab_check: Option<Instant>,
+    history: History,
+    layout: LayoutSettings,
+    selected_material: Option<usize>,
+    import_options: ImportOptions,
+    play_mode: PlayMode,
+    play_snapshot: Option<WorldSnapshot>,
+    /// The edit history is set aside while playing, edits made during play are discarded on stop
+    edit_history: History,
+    project: Option<Project>,
+    /// Where the open level was loaded from or last saved to
+    level_path: Option<PathBuf>,
+    recent_files: RecentFiles,
 }
 
 impl Editor {
-    pub fn deselect_all(&mut self, app_state: &mut AppState) -> Result<()> {
+    /// Removes the selection markers that older editors saved into worlds
+    fn strip_selected_components(&mut self, app_state: &mut AppState) -> Result<()> {
         let mut query = <(Entity, &Selected)>::query();
 
         let entities = query
@@ -48,57 +155,12 @@ impl Editor {
                 .ecs
                 .entry(entity)
                 .context("Failed to find entity!")?;
-            log::info!("Deselecting entity: {:?}", entity);
             entry.remove_component::<Selected>();
         }
 
         Ok(())
     }
 
-    pub fn load_world_from_file(&self, path: &PathBuf, app_state: &mut AppState) -> Result<()> {
-        let raw_path = match path.to_str() {
-            Some(raw_path) => raw_path,
-            None => return Ok(()),
-        };
-
-        if let Some(extension) = path.extension() {
-            match extension.to_str() {
-                Some("glb") | Some("gltf") => {
-                    load_gltf(raw_path, app_state.world)?;
-                }
-                // Some("hdr") => Self::load_hdr(raw_path, application)?,
-                Some("dga") => {
-                    app_state.world.load(raw_path)?;
-                    log::info!("Loaded world!");
-                }
-                _ => log::warn!(
-                    "File extension {:#?} is not a valid '.dga', '.glb', '.gltf', or '.hdr' extension",
-                    extension
-                ),
-            }
-
-            // TODO: Probably don't want this added every time
-            app_state.renderer.load_world(app_state.world)?;
-
-            let mut query = <(Entity, &MeshRender)>::query();
-            let entities = query
-                .iter(&mut app_state.world.ecs)
-                .map(|(e, _)| *e)
-                .collect::<Vec<_>>();
-
-            for entity in entities.into_iter() {
-                app_state
-                    .world
-                    .add_rigid_body(entity, RigidBodyType::Fixed)?;
-                app_state
-                    .world
-                    .add_trimesh_collider(entity, InteractionGroups::all())?;
-            }
-        }
-
-        Ok(())
-    }
-
     fn print_node(&mut self, ecs: &mut Ecs, graph: &SceneGraph, index: NodeIndex, ui: &mut Ui) {
         let entity = graph[index];
         let entry = ecs.entry_ref(entity).expect("Failed to find entity!");
@@ -110,21 +172,32 @@ impl Editor {
             .0
             .to_string();
 
-        let selected = self.selected_entity == Some(entity);
+        let selected = self.selection.contains(entity);
 
+        let mut action = None;
         let context_menu = |ui: &mut Ui| {
             if ui.button("Rename...").clicked() {
-                // UI TODO: Allow renaming entities
+                action = Some(HierarchyAction::Rename(entity));
                 ui.close_menu();
             }
 
             if ui.button("Delete...").clicked() {
-                // UI TODO: Allow deleting entities
+                action = Some(HierarchyAction::Delete(entity));
                 ui.close_menu();
             }
 
             if ui.button("Add Child...").clicked() {
-                // UI TODO: Allow adding child entities
+                action = Some(HierarchyAction::AddChild(entity));
+                ui.close_menu();
+            }
+
+            if ui.button("Save as Prefab...").clicked() {
+                action = Some(HierarchyAction::SaveAsPrefab(entity));
+                ui.close_menu();
+            }
+
+            if ui.button("Instantiate Prefab...").clicked() {
+                action = Some(HierarchyAction::InstantiatePrefab(Some(entity)));
                 ui.close_menu();
             }
         };
@@ -147,226 +220,1483 @@ impl Editor {
         };
 
         if response.clicked() {
-            self.selected_entity = Some(entity);
+            let modifiers = ui.input().modifiers;
+            let mode = selection_mode(modifiers.ctrl, modifiers.shift);
+            self.selection.apply(&[entity], mode);
         }
 
         if response.double_clicked() {
-            // TODO: Allow renaming entity
+            action = Some(HierarchyAction::Rename(entity));
         }
-    }
-}
 
-impl App for Editor {
-    fn gui_active(&mut self) -> bool {
-        true
+        if action.is_some() {
+            self.hierarchy_action = action;
+        }
     }
 
-    fn initialize(&mut self, _app_state: &mut AppState) -> Result<()> {
-        env_logger::init();
-        register_component::<Selected>("selected")?;
+    fn apply_hierarchy_action(&mut self, app_state: &mut AppState) -> Result<()> {
+        let action = match self.hierarchy_action.take() {
+            Some(action) => action,
+            None => return Ok(()),
+        };
+        match action {
+            HierarchyAction::Rename(entity) => {
+                let name = app_state
+                    .world
+                    .ecs
+                    .entry_ref(entity)?
+                    .get_component::<Name>()
+                    .map(|name| name.0.to_string())
+                    .unwrap_or_default();
+                self.renaming = Some((entity, name));
+            }
+            HierarchyAction::Delete(entity) => {
+                let despawned = app_state.world.descendants(entity);
+                self.history
+                    .execute(app_state.world, DespawnEntity::new(entity))?;
+                self.selection
+                    .retain(|selected| !despawned.contains(selected));
+            }
+            HierarchyAction::AddChild(entity) => {
+                self.history.execute(
+                    app_state.world,
+                    SpawnEntity::new(Some(entity), "New Entity"),
+                )?;
+                match app_state.world.children(entity).last() {
+                    Some(child) => self.selection.set(*child),
+                    None => self.selection.clear(),
+                }
+            }
+            HierarchyAction::SaveAsPrefab(entity) => {
+                let path = FileDialog::new()
+                    .add_filter("Dragonglass Prefab", &["prefab"])
+                    .set_directory(self.dialog_directory(Project::asset_path))
+                    .save_file();
+                if let Some(path) = path {
+                    app_state.world.save_prefab(entity, &path)?;
+                    self.prefab_watcher.track(&path.display().to_string());
+                }
+            }
+            HierarchyAction::InstantiatePrefab(parent) => {
+                let path = FileDialog::new()
+                    .add_filter("Dragonglass Prefab", &["prefab"])
+                    .set_directory(self.dialog_directory(Project::asset_path))
+                    .pick_file();
+                if let Some(path) = path {
+                    self.history
+                        .execute_sealed(app_state.world, InstantiatePrefab::new(&path, parent))?;
+                    self.prefab_watcher.track(&path.display().to_string());
+                    if let Some(instance) = self
+                        .history
+                        .last_command::<InstantiatePrefab>()
+                        .and_then(InstantiatePrefab::entity)
+                    {
+                        self.selection.set(self.history.resolve(instance));
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
-    fn update(&mut self, app_state: &mut AppState) -> Result<()> {
-        if app_state.world.active_camera_is_main()? && !self.moving_selected {
-            let camera_entity = app_state.world.active_camera()?;
-            self.camera.update(app_state, camera_entity)?;
-        }
-
-        // Animate entities
-        // if !app_state.world.animations.is_empty() {
-        //     app_state
-        //         .world
-        //         .animate(0, 0.75 * app_state.system.delta_time as f32)?;
-        // }
-
-        if self.moving_selected {
-            let mut query = <(Entity, &Selected)>::query();
-            let entities = query
-                .iter_mut(&mut app_state.world.ecs)
-                .map(|(e, _)| (*e))
-                .collect::<Vec<_>>();
-            for entity in entities.into_iter() {
-                let (right, up) = {
-                    let camera_entity = app_state.world.active_camera()?;
-                    let camera_entry = app_state.world.ecs.entry_ref(camera_entity)?;
-                    let camera_transform = camera_entry.get_component::<Transform>()?;
-                    (camera_transform.right(), camera_transform.up())
-                };
+    /// Updates prefab instances when their prefab file changes on disk
+    fn reload_changed_prefabs(&mut self, app_state: &mut AppState) {
+        let check_due = self
+            .last_prefab_check
+            .map(|last_check| last_check.elapsed() >= Duration::from_secs(1))
+            .unwrap_or(true);
+        if !check_due {
+            return;
+        }
+        self.last_prefab_check = Some(Instant::now());
+        self.prefab_watcher
+            .reload_changed(app_state.world, &mut self.history);
+    }
 
-                let mut entry = app_state.world.ecs.entry_mut(entity)?;
-                let speed = 10.0;
-                let transform = entry.get_component_mut::<Transform>()?;
-                let mouse_delta =
-                    app_state.input.mouse.position_delta * app_state.system.delta_time as f32;
-                if app_state.input.mouse.is_right_clicked {
-                    transform.translation += right * mouse_delta.x * speed;
-                    transform.translation += up * -mouse_delta.y * speed;
+    fn rename_window(&mut self, ctx: &egui::CtxRef, app_state: &mut AppState) -> Result<()> {
+        let (entity, mut name) = match self.renaming.take() {
+            Some(renaming) => renaming,
+            None => return Ok(()),
+        };
+        let (mut confirmed, mut cancelled) = (false, false);
+        egui::Window::new("Rename Entity")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let response = ui.text_edit_singleline(&mut name);
+                if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    confirmed = true;
                 }
-                app_state.world.sync_rigid_body_to_transform(entity)?;
+                ui.horizontal(|ui| {
+                    confirmed |= ui.button("Ok").clicked();
+                    cancelled = ui.button("Cancel").clicked();
+                });
+            });
+        if confirmed {
+            let command =
+                SetComponent::from_component(app_state.world, entity, "name", &Name(name))?;
+            self.history.execute_sealed(app_state.world, command)?;
+        } else if !cancelled {
+            self.renaming = Some((entity, name));
+        }
+        Ok(())
+    }
+}
+
+impl Editor {
+    /// Resets the editing state after the world is replaced
+    fn reset_editing(&mut self, app_state: &mut AppState) -> Result<()> {
+        self.history.clear();
+        self.selection.clear();
+        self.group_drag = None;
+        self.play_mode = PlayMode::Editing;
+        self.play_snapshot = None;
+        app_state.scheduler.simulating = false;
+        app_state.renderer.load_world(app_state.world)
+    }
+
+    fn remember_file(&mut self, path: &Path) {
+        self.recent_files.add(path);
+        if let Err(error) = self.recent_files.save() {
+            log::warn!("{:?}", error);
+        }
+    }
+
+    /// Opens a recent file, forgetting it if it can't be opened anymore
+    fn open_recent_file(&mut self, path: &Path, app_state: &mut AppState) {
+        if let Err(error) = self.open_file(path, app_state) {
+            log::error!("Failed to open recent file {}: {:?}", path.display(), error);
+            self.recent_files.remove(path);
+            if let Err(error) = self.recent_files.save() {
+                log::warn!("{:?}", error);
             }
         }
+    }
 
-        Ok(())
+    /// Where file dialogs start, preferring the given project directory when a project is open
+    fn dialog_directory(&self, directory: impl Fn(&Project) -> PathBuf) -> PathBuf {
+        match self.project.as_ref() {
+            Some(project) => directory(project),
+            None => PathBuf::from("/"),
+        }
     }
 
-    fn update_gui(&mut self, app_state: &mut AppState) -> Result<()> {
-        let ctx = &app_state.gui.context();
+    fn new_level(&mut self, app_state: &mut AppState) -> Result<()> {
+        *app_state.world = World::new()?;
+        self.level_path = None;
+        self.reset_editing(app_state)
+    }
 
-        egui::TopBottomPanel::top("top_panel")
-            .resizable(true)
-            .show(ctx, |ui| {
-                egui::menu::bar(ui, |ui| {
-                    menu::bar(ui, |ui| {
-                        global_dark_light_mode_switch(ui);
-                        ui.menu_button("File", |ui| {
-                            // TODO: Distinguish between loading levels and importing assets
-                            if ui.button("Load Level").clicked() {
-                                let path = FileDialog::new()
-                                    .add_filter("Dragonglass Asset", &["dga"])
-                                    .set_directory("/")
-                                    .pick_file();
-                                if let Some(path) = path {
-                                    self.load_world_from_file(&path, app_state)
-                                        .expect("Failed to load asset!");
-                                }
-                                ui.close_menu();
-                            }
+    /// Replaces the world with a saved level
+    fn open_level(&mut self, path: &Path, app_state: &mut AppState) -> Result<()> {
+        app_state.world.load(path)?;
+        self.strip_selected_components(app_state)?;
+        log::info!("Loaded world!");
+        self.level_path = Some(path.to_path_buf());
+        if let Some(project) = self.project.as_mut() {
+            project.add_level(path);
+        }
+        self.remember_file(path);
+        self.reset_editing(app_state)
+    }
 
-                            if ui.button("Import gltf/glb").clicked() {
-                                let path = FileDialog::new()
-                                    .add_filter("GLTF Asset", &["glb", "gltf"])
-                                    .set_directory("/")
-                                    .pick_file();
-                                if let Some(path) = path {
-                                    self.load_world_from_file(&path, app_state)
-                                        .expect("Failed to load asset!");
-                                }
-                                ui.close_menu();
-                            }
+    fn open_level_dialog(&mut self, app_state: &mut AppState) -> Result<()> {
+        let path = FileDialog::new()
+            .add_filter("Dragonglass Asset", &["dga"])
+            .add_filter("Dragonglass Scene", &["ron"])
+            .set_directory(self.dialog_directory(Project::level_directory))
+            .pick_file();
+        match path {
+            Some(path) => self.open_level(&path, app_state),
+            None => Ok(()),
+        }
+    }
 
-                            if ui.button("Save").clicked() {
-                                let path = FileDialog::new()
-                                    .add_filter("Dragonglass Asset", &["dga"])
-                                    .set_directory("/")
-                                    .save_file();
+    /// Saves the level where it was opened from, asking for a path if it has never been saved
+    fn save_level(&mut self, app_state: &mut AppState) -> Result<()> {
+        match self.level_path.clone() {
+            Some(path) => self.save_level_to(&path, app_state),
+            None => self.save_level_as(app_state),
+        }
+    }
 
-                                if let Some(path) = path {
-                                    let mut query = <(Entity, &Selected)>::query();
+    fn save_level_as(&mut self, app_state: &mut AppState) -> Result<()> {
+        let path = FileDialog::new()
+            .add_filter("Dragonglass Asset", &["dga"])
+            .add_filter("Dragonglass Scene", &["ron"])
+            .set_directory(self.dialog_directory(Project::level_directory))
+            .save_file();
+        match path {
+            Some(path) => self.save_level_to(&path, app_state),
+            None => Ok(()),
+        }
+    }
 
-                                    let entities = query
-                                        .iter(&mut app_state.world.ecs)
-                                        .map(|(e, _)| *e)
-                                        .collect::<Vec<_>>();
+    fn save_level_to(&mut self, path: &Path, app_state: &mut AppState) -> Result<()> {
+        app_state
+            .world
+            .save(path)
+            .context("Failed to save world!")?;
+        self.level_path = Some(path.to_path_buf());
+        self.remember_file(path);
+        if let Some(project) = self.project.as_mut() {
+            project.add_level(path);
+        }
+        self.save_project(app_state)
+    }
 
-                                    for entity in entities.into_iter() {
-                                        app_state
-                                            .world
-                                            .remove_rigid_body(entity)
-                                            .expect("Failed to remove rigid body!");
-                                    }
+    /// Adds a glTF asset's scenes to the world.
+    /// Assets from outside an open project are copied into it first.
+    fn import_asset(&mut self, path: &Path, app_state: &mut AppState) -> Result<()> {
+        let path = match self.project.as_ref() {
+            Some(project) => project.import_asset(path)?,
+            None => path.to_path_buf(),
+        };
+        let raw_path = path.to_str().context("Failed to read asset path!")?;
+        import_gltf(raw_path, app_state.world, &self.import_options)?;
 
-                                    app_state.world.save(&path).expect("Failed to save world!");
-                                }
-                                ui.close_menu();
-                            }
+        // Imports are not recorded as edits, so older edits can't be undone past them
+        self.history.clear();
 
-                            if ui.button("Quit").clicked() {
-                                app_state.system.exit_requested = true;
-                            }
-                        });
-                    });
-                });
+        // Only the newly imported assets are uploaded
+        app_state.renderer.load_world(app_state.world)
+    }
+
+    fn import_asset_dialog(&mut self, app_state: &mut AppState) -> Result<()> {
+        let path = FileDialog::new()
+            .add_filter("GLTF Asset", &["glb", "gltf"])
+            .set_directory(self.dialog_directory(Project::asset_path))
+            .pick_file();
+        match path {
+            Some(path) => self.import_asset(&path, app_state),
+            None => Ok(()),
+        }
+    }
+
+    /// Opens a project, restoring its editor settings and its first level
+    fn open_project(&mut self, path: &Path, app_state: &mut AppState) -> Result<()> {
+        let project = Project::open(path)?;
+        self.apply_settings(&project.settings, app_state);
+        let first_level = project
+            .level_paths()
+            .into_iter()
+            .find(|level| level.exists());
+        self.project = Some(project);
+        self.remember_file(path);
+        match first_level {
+            Some(level) => self.open_level(&level, app_state),
+            None => self.new_level(app_state),
+        }
+    }
+
+    fn open_project_dialog(&mut self, app_state: &mut AppState) -> Result<()> {
+        let path = FileDialog::new()
+            .add_filter("Dragonglass Project", &[Project::EXTENSION])
+            .set_directory("/")
+            .pick_file();
+        match path {
+            Some(path) => self.open_project(&path, app_state),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates a project in a chosen directory, starting it with the current settings
+    fn new_project_dialog(&mut self, app_state: &mut AppState) -> Result<()> {
+        let directory = match FileDialog::new().set_directory("/").pick_folder() {
+            Some(directory) => directory,
+            None => return Ok(()),
+        };
+        let project = Project::create(&directory)?;
+        self.remember_file(&project.path);
+        self.project = Some(project);
+        self.save_project(app_state)?;
+        self.new_level(app_state)
+    }
+
+    /// Saves the project along with the editor's current settings
+    fn save_project(&mut self, app_state: &mut AppState) -> Result<()> {
+        let settings = self.settings(app_state);
+        match self.project.as_mut() {
+            Some(project) => {
+                project.settings = settings;
+                project.save()
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn settings(&self, app_state: &AppState) -> EditorSettings {
+        let orientation = match self.camera_mode {
+            CameraMode::Orbit => self.camera.orientation.clone(),
+            CameraMode::Fly => self.fly_camera.orientation.focus(),
+        };
+        EditorSettings {
+            camera: CameraSettings::new(&orientation, &self.fly_camera),
+            layout: self.layout.clone(),
+            snapping: self.gizmo.snapping,
+            grid: app_state.renderer.grid(),
+        }
+    }
+
+    fn apply_settings(&mut self, settings: &EditorSettings, app_state: &mut AppState) {
+        self.use_orbit_camera();
+        settings
+            .camera
+            .apply(&mut self.camera, &mut self.fly_camera);
+        self.layout = settings.layout.clone();
+        self.gizmo.snapping = settings.snapping;
+        app_state.renderer.set_grid(settings.grid);
+    }
+
+    /// Opens a project or level, or imports an asset, depending on the file's extension
+    fn open_file(&mut self, path: &Path, app_state: &mut AppState) -> Result<()> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        match extension {
+            Project::EXTENSION => self.open_project(path, app_state),
+            "dga" | "ron" => self.open_level(path, app_state),
+            "glb" | "gltf" => self.import_asset(path, app_state),
+            _ => {
+                log::warn!(
+                    "File extension {:#?} is not a valid '.{}', '.dga', '.ron', '.glb', or '.gltf' extension",
+                    extension,
+                    Project::EXTENSION
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn file_menu(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        if ui.button("New Project").clicked() {
+            ui.close_menu();
+            self.new_project_dialog(app_state)?;
+        }
+        if ui.button("Open Project").clicked() {
+            ui.close_menu();
+            self.open_project_dialog(app_state)?;
+        }
+        if ui
+            .add_enabled(self.project.is_some(), egui::Button::new("Save Project"))
+            .clicked()
+        {
+            ui.close_menu();
+            self.save_project(app_state)?;
+        }
+
+        ui.separator();
+        if ui.button("New Level").clicked() {
+            ui.close_menu();
+            self.new_level(app_state)?;
+        }
+        if ui.button("Open Level").clicked() {
+            ui.close_menu();
+            self.open_level_dialog(app_state)?;
+        }
+        let levels = self
+            .project
+            .as_ref()
+            .map(|project| project.levels.clone())
+            .unwrap_or_default();
+        if !levels.is_empty() {
+            ui.menu_button("Project Levels", |ui| -> Result<()> {
+                for level in levels.into_iter() {
+                    if ui.button(level.display().to_string()).clicked() {
+                        ui.close_menu();
+                        let path = self
+                            .project
+                            .as_ref()
+                            .map(|project| project.root().join(&level))
+                            .unwrap_or(level);
+                        self.open_level(&path, app_state)?;
+                    }
+                }
+                Ok(())
+            })
+            .inner
+            .transpose()?;
+        }
+        if ui.button("Save Level (Ctrl+S)").clicked() {
+            ui.close_menu();
+            self.save_level(app_state)?;
+        }
+        if ui.button("Save Level As").clicked() {
+            ui.close_menu();
+            self.save_level_as(app_state)?;
+        }
+
+        ui.separator();
+        if ui.button("Import Asset").clicked() {
+            ui.close_menu();
+            self.import_asset_dialog(app_state)?;
+        }
+        if ui.button("Instantiate Prefab").clicked() {
+            self.hierarchy_action = Some(HierarchyAction::InstantiatePrefab(None));
+            ui.close_menu();
+        }
+
+        ui.separator();
+        let recent = self.recent_files.paths.clone();
+        ui.add_enabled_ui(!recent.is_empty(), |ui| {
+            ui.menu_button("Recent Files", |ui| {
+                for path in recent.into_iter() {
+                    if ui.button(path.display().to_string()).clicked() {
+                        ui.close_menu();
+                        self.open_recent_file(&path, app_state);
+                    }
+                }
             });
+        });
+
+        ui.separator();
+        // The project is saved on exit
+        if ui.button("Quit").clicked() {
+            app_state.system.exit_requested = true;
+        }
 
-        egui::SidePanel::left("scene_explorer")
+        Ok(())
+    }
+}
+
+impl Editor {
+    /// Undoes the last command, reporting failures instead of stopping the editor
+    fn undo(&mut self, app_state: &mut AppState) {
+        if let Err(error) = self.history.undo(app_state.world) {
+            log::error!("{:?}", error);
+        }
+        self.refresh_selection(app_state);
+    }
+
+    /// Redoes the last undone command, reporting failures instead of stopping the editor
+    fn redo(&mut self, app_state: &mut AppState) {
+        if let Err(error) = self.history.redo(app_state.world) {
+            log::error!("{:?}", error);
+        }
+        self.refresh_selection(app_state);
+    }
+
+    /// Follows the selection to entities recreated by undo and redo
+    fn refresh_selection(&mut self, app_state: &mut AppState) {
+        let history = &self.history;
+        self.selection.map(|entity| history.resolve(entity));
+        self.selection
+            .retain(|entity| app_state.world.ecs.contains(*entity));
+    }
+
+    fn history_window(&mut self, ctx: &egui::CtxRef, app_state: &mut AppState) -> Result<()> {
+        if !self.layout.show_history {
+            return Ok(());
+        }
+        let mut jump_to = None;
+        let mut open = self.layout.show_history;
+        egui::Window::new("History")
+            .open(&mut open)
             .resizable(true)
             .show(ctx, |ui| {
-                ui.heading(&app_state.world.scene.name);
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    let scene = &mut app_state.world.scene;
-                    let ecs = &mut app_state.world.ecs;
-                    for graph in scene.graphs.iter_mut() {
-                        self.print_node(ecs, graph, NodeIndex::new(0), ui);
+                    let undo_names = self.history.undo_names();
+                    if ui
+                        .add(SelectableLabel::new(undo_names.is_empty(), "Initial State"))
+                        .clicked()
+                    {
+                        jump_to = Some(0);
+                    }
+                    let current = undo_names.len();
+                    for (index, name) in undo_names.into_iter().enumerate() {
+                        if ui
+                            .add(SelectableLabel::new(index + 1 == current, name))
+                            .clicked()
+                        {
+                            jump_to = Some(index + 1);
+                        }
+                    }
+                    for (index, name) in self.history.redo_names().into_iter().enumerate() {
+                        let label = egui::RichText::new(name).weak();
+                        if ui.add(SelectableLabel::new(false, label)).clicked() {
+                            jump_to = Some(current + index + 1);
+                        }
                     }
-                    ui.allocate_space(ui.available_size());
                 });
             });
+        self.layout.show_history = open;
+        if let Some(undo_len) = jump_to {
+            if let Err(error) = self.history.jump_to(app_state.world, undo_len) {
+                log::error!("{:?}", error);
+            }
+            self.refresh_selection(app_state);
+        }
+        Ok(())
+    }
+
+    fn asset_tab(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        let assets = app_state.world.assets();
+        let mut import = false;
+        egui::CollapsingHeader::new("Import Options").show(ui, |ui| {
+            import_options_editor(ui, &mut self.import_options);
+            if self.import_options.colliders == ImportColliders::None {
+                ui.label("Meshes without colliders can't be picked in the viewport");
+            }
+            import = ui.button("Import gltf/glb").clicked();
+        });
+        ui.separator();
+        let clicked = asset_browser(ui, &assets);
 
-        egui::SidePanel::right("inspector")
+        if let Some(AssetId::Material(index)) = clicked {
+            self.selected_material = Some(index);
+            self.layout.show_materials = true;
+        }
+
+        if import {
+            self.import_asset_dialog(app_state)?;
+        }
+
+        Ok(())
+    }
+
+    fn material_window(&mut self, ctx: &egui::CtxRef, app_state: &mut AppState) -> Result<()> {
+        let material_count = app_state.world.materials.len();
+        self.selected_material = self
+            .selected_material
+            .filter(|index| *index < material_count);
+        if !self.layout.show_materials {
+            app_state.renderer.set_material_preview(None);
+            return Ok(());
+        }
+
+        let mut open = self.layout.show_materials;
+        let mut preview = None;
+        let mut preview_result = Ok(());
+        let mut edited = None;
+        let mut response = InspectResponse::default();
+        egui::Window::new("Materials")
+            .open(&mut open)
             .resizable(true)
             .show(ctx, |ui| {
-                ui.heading("Inspector");
-                if let Some(entity) = self.selected_entity {
-                    ui.heading("Transform");
+                let materials = &app_state.world.materials;
+                let selected_name = self
+                    .selected_material
+                    .map(|index| materials[index].name.to_string())
+                    .unwrap_or_else(|| "None".to_string());
+                egui::ComboBox::from_label("Material")
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        for (index, material) in materials.iter().enumerate() {
+                            ui.selectable_value(
+                                &mut self.selected_material,
+                                Some(index),
+                                format!("{}: {}", index, material.name),
+                            );
+                        }
+                    });
 
-                    let mut should_sync = false;
+                let index = match self.selected_material {
+                    Some(index) => index,
+                    None => return,
+                };
 
-                    let mut entry = app_state
-                        .world
-                        .ecs
-                        .entry(entity)
-                        .expect("Failed to find entity!");
+                // The renderer draws the preview sphere into a texture shown here
+                let size = egui::vec2(160.0, 160.0);
+                let material_preview = MaterialPreview {
+                    material_index: index,
+                    size: (size.x * ui.ctx().pixels_per_point()) as u32,
+                };
+                app_state
+                    .renderer
+                    .set_material_preview(Some(material_preview));
+                preview = Some(material_preview);
+                match app_state.renderer.material_preview_texture() {
+                    Ok(Some(texture_id)) => {
+                        // The texture's rows run from the bottom up
+                        let uv =
+                            egui::Rect::from_min_max(egui::pos2(0.0, 1.0), egui::pos2(1.0, 0.0));
+                        ui.add(egui::Image::new(texture_id, size).uv(uv));
+                    }
+                    Ok(None) => {}
+                    Err(error) => preview_result = Err(error),
+                }
 
-                    ui.with_layout(egui::Layout::top_down(Align::LEFT), |ui| {
-                        let transform = entry
-                            .get_component_mut::<Transform>()
-                            .expect("Entity does not have a transform!");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let mut material = materials[index].clone();
+                    response = material_editor(ui, &mut material, &app_state.world.textures);
+                    if response.changed {
+                        edited = Some((index, material));
+                    }
+                });
+            });
+        self.layout.show_materials = open;
+        app_state
+            .renderer
+            .set_material_preview(preview.filter(|_| open));
+        preview_result?;
 
-                        ui.label("X");
-                        let x_response =
-                            ui.add(DragValue::new(&mut transform.translation.x).speed(0.1));
+        if let Some((index, material)) = edited {
+            let command = SetMaterial::new(app_state.world, index, material)?;
+            self.history.execute(app_state.world, command)?;
+        }
+        if response.finished {
+            self.history.seal();
+        }
+        Ok(())
+    }
+}
 
-                        ui.label("Y");
-                        let y_response =
-                            ui.add(DragValue::new(&mut transform.translation.y).speed(0.1));
+impl Editor {
+    fn play(&mut self, app_state: &mut AppState) -> Result<()> {
+        if self.play_mode == PlayMode::Editing {
+            self.play_snapshot = Some(app_state.world.snapshot()?);
+            self.edit_history = std::mem::take(&mut self.history);
+            self.gizmo.end_drag();
+        }
+        self.play_mode = PlayMode::Playing;
+        app_state.scheduler.simulating = true;
+        Ok(())
+    }
+
+    fn pause(&mut self, app_state: &mut AppState) -> Result<()> {
+        if self.play_mode == PlayMode::Editing {
+            self.play(app_state)?;
+        }
+        self.play_mode = PlayMode::Paused;
+        app_state.scheduler.simulating = false;
+        Ok(())
+    }
 
-                        ui.label("Z");
-                        let z_response =
-                            ui.add(DragValue::new(&mut transform.translation.z).speed(0.1));
+    fn step(&mut self, app_state: &mut AppState) -> Result<()> {
+        self.pause(app_state)?;
+        app_state.scheduler.step();
+        Ok(())
+    }
 
-                        should_sync =
-                            x_response.changed() || y_response.changed() || z_response.changed();
-                    });
+    fn stop(&mut self, app_state: &mut AppState) -> Result<()> {
+        // The snapshot is kept until it has been restored, so a failed stop can be retried
+        if let Some(snapshot) = self.play_snapshot.as_ref() {
+            app_state.world.restore(snapshot)?;
+        }
+        self.play_snapshot = None;
+        self.history = std::mem::take(&mut self.edit_history);
+        self.play_mode = PlayMode::Editing;
+        app_state.scheduler.simulating = false;
+        self.selection
+            .retain(|entity| app_state.world.ecs.contains(*entity));
+        Ok(())
+    }
 
-                    if should_sync && entry.get_component::<RigidBody>().is_ok() {
-                        app_state
-                            .world
-                            .sync_rigid_body_to_transform(entity)
-                            .expect("Failed to sync rigid body to transform!");
-                    }
+    fn play_controls(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        ui.separator();
+        let editing = self.play_mode == PlayMode::Editing;
+        if self.play_mode == PlayMode::Playing {
+            if ui.button("Pause").clicked() {
+                self.pause(app_state)?;
+            }
+        } else if ui.button("Play").clicked() {
+            self.play(app_state)?;
+        }
+        if ui.button("Step").clicked() {
+            self.step(app_state)?;
+        }
+        if ui
+            .add_enabled(!editing, egui::Button::new("Stop"))
+            .clicked()
+        {
+            self.stop(app_state)?;
+        }
+        Ok(())
+    }
+
+    /// The camera the editor controls,
+    /// unless the scene is being viewed through one of its own cameras
+    fn editor_camera(app_state: &AppState) -> Result<Option<Entity>> {
+        if app_state.world.active_camera_is_main()? {
+            Ok(Some(app_state.world.active_camera()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Switches to the orbit camera, orbiting whatever the fly camera was looking at
+    fn use_orbit_camera(&mut self) {
+        if self.camera_mode == CameraMode::Fly {
+            self.camera
+                .set_orientation(self.fly_camera.orientation.focus());
+            self.camera_mode = CameraMode::Orbit;
+        }
+    }
+
+    /// Switches to the fly camera, starting from the orbit camera's viewpoint.
+    /// Flying is always done in perspective.
+    fn use_fly_camera(&mut self, app_state: &mut AppState) -> Result<()> {
+        if self.camera_mode == CameraMode::Fly {
+            return Ok(());
+        }
+        if let Some(camera_entity) = Self::editor_camera(app_state)? {
+            let aspect_ratio = app_state.renderer.viewport().aspect_ratio();
+            self.camera.set_view(
+                app_state.world,
+                camera_entity,
+                OrbitView::Perspective,
+                aspect_ratio,
+            )?;
+        }
+        self.fly_camera
+            .set_orientation(self.camera.orientation.eye());
+        self.camera_mode = CameraMode::Fly;
+        Ok(())
+    }
+
+    fn set_camera_view(&mut self, app_state: &mut AppState, view: OrbitView) -> Result<()> {
+        let camera_entity = match Self::editor_camera(app_state)? {
+            Some(camera_entity) => camera_entity,
+            None => return Ok(()),
+        };
+        self.use_orbit_camera();
+        let aspect_ratio = app_state.renderer.viewport().aspect_ratio();
+        self.camera
+            .set_view(app_state.world, camera_entity, view, aspect_ratio)
+    }
+
+    /// Orbits the selection, zoomed to fit all of it in view
+    fn frame_selected(&mut self, app_state: &mut AppState) -> Result<()> {
+        let camera_entity = match Self::editor_camera(app_state)? {
+            Some(camera_entity) => camera_entity,
+            None => return Ok(()),
+        };
+        let bounding_box = match self.selection.bounding_box(app_state.world)? {
+            Some(bounding_box) => bounding_box,
+            None => return Ok(()),
+        };
+        self.use_orbit_camera();
+        self.camera
+            .frame(app_state.world, camera_entity, &bounding_box)
+    }
+
+    fn camera_menu(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        let camera_entity = match Self::editor_camera(app_state)? {
+            Some(camera_entity) => camera_entity,
+            None => {
+                ui.label("Viewing through a scene camera");
+                return Ok(());
+            }
+        };
+
+        if ui
+            .selectable_label(self.camera_mode == CameraMode::Orbit, "Orbit")
+            .clicked()
+        {
+            self.use_orbit_camera();
+        }
+        if ui
+            .selectable_label(
+                self.camera_mode == CameraMode::Fly,
+                "Fly (Hold Right Mouse)",
+            )
+            .clicked()
+        {
+            self.use_fly_camera(app_state)?;
+        }
+        if ui
+            .add_enabled(
+                !self.selection.is_empty(),
+                egui::Button::new("Frame Selected (F)"),
+            )
+            .clicked()
+        {
+            self.frame_selected(app_state)?;
+            ui.close_menu();
+        }
+
+        ui.separator();
+        for (label, view) in [
+            ("Perspective", OrbitView::Perspective),
+            ("Top", OrbitView::Top),
+            ("Bottom", OrbitView::Bottom),
+            ("Front", OrbitView::Front),
+            ("Back", OrbitView::Back),
+            ("Right", OrbitView::Right),
+            ("Left", OrbitView::Left),
+        ] {
+            if ui.button(label).clicked() {
+                self.set_camera_view(app_state, view)?;
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        ui.label("Controls");
+        let mut controls = self.fly_camera.settings.clone();
+        ui.checkbox(&mut controls.invert_x, "Invert X");
+        ui.checkbox(&mut controls.invert_y, "Invert Y");
+        ui.add(
+            DragValue::new(&mut controls.damping)
+                .speed(0.1)
+                .clamp_range(0.0..=50.0)
+                .prefix("Damping: "),
+        );
+        ui.add(
+            DragValue::new(&mut controls.move_speed)
+                .speed(0.1)
+                .clamp_range(0.1..=500.0)
+                .prefix("Fly Speed: "),
+        );
+        self.camera.settings = controls.clone();
+        self.fly_camera.settings = controls;
+
+        ui.separator();
+        ui.label("Bookmarks");
+        let save = ui
+            .horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.bookmark_name);
+                ui.add_enabled(
+                    !self.bookmark_name.trim().is_empty(),
+                    egui::Button::new("Save"),
+                )
+                .clicked()
+            })
+            .inner;
+        if save {
+            self.use_orbit_camera();
+            let name = self.bookmark_name.trim().to_string();
+            self.camera
+                .save_bookmark(app_state.world, camera_entity, &name)?;
+            self.bookmark_name.clear();
+        }
+
+        let names = app_state
+            .world
+            .ecs
+            .entry_ref(camera_entity)?
+            .get_component::<CameraBookmarks>()
+            .map(|bookmarks| {
+                bookmarks
+                    .0
+                    .iter()
+                    .map(|bookmark| bookmark.name.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for name in names.into_iter() {
+            ui.horizontal(|ui| -> Result<()> {
+                if ui.button(&name).clicked() {
+                    self.use_orbit_camera();
+                    self.camera
+                        .go_to_bookmark(app_state.world, camera_entity, &name)?;
+                    ui.close_menu();
+                }
+                if ui.small_button("x").clicked() {
+                    let mut entry = app_state.world.ecs.entry_mut(camera_entity)?;
+                    let bookmarks = entry.get_component_mut::<CameraBookmarks>()?;
+                    bookmarks.0.retain(|bookmark| bookmark.name != name);
                 }
+                Ok(())
+            })
+            .inner?;
+        }
+
+        Ok(())
+    }
+
+    fn grid_menu(&mut self, ui: &mut Ui, app_state: &mut AppState) {
+        let mut grid = app_state.renderer.grid();
+        ui.checkbox(&mut grid.enabled, "Show Grid");
+        ui.add(
+            DragValue::new(&mut grid.spacing)
+                .speed(0.05)
+                .clamp_range(0.01..=100.0)
+                .prefix("Spacing: "),
+        );
+        ui.add(
+            DragValue::new(&mut grid.major_lines)
+                .clamp_range(1..=100)
+                .prefix("Major Lines: "),
+        );
+        ui.add(
+            DragValue::new(&mut grid.fade_distance)
+                .speed(1.0)
+                .clamp_range(1.0..=1000.0)
+                .prefix("Fade Distance: "),
+        );
+        app_state.renderer.set_grid(grid);
+    }
 
+    fn dock_tab(&mut self, ui: &mut Ui, app_state: &mut AppState, tab: EditorTab) -> Result<()> {
+        match tab {
+            EditorTab::Hierarchy => self.hierarchy_tab(ui, app_state),
+            EditorTab::Inspector => {
+                let mut result = Ok(());
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    result = self.component_inspector(ui, app_state);
+                });
+                result
+            }
+            EditorTab::Assets => self.asset_tab(ui, app_state),
+            EditorTab::Console => {
                 ui.allocate_space(ui.available_size());
-            });
+                Ok(())
+            }
+            EditorTab::Viewport => self.viewport_tab(ui, app_state),
+        }
+    }
+
+    fn hierarchy_tab(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        ui.heading(&app_state.world.scene.name);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let scene = &mut app_state.world.scene;
+            let ecs = &mut app_state.world.ecs;
+            for graph in scene.graphs.iter_mut() {
+                for root in graph.roots() {
+                    self.print_node(ecs, graph, root, ui);
+                }
+            }
+            ui.allocate_space(ui.available_size());
+        });
+        Ok(())
+    }
+
+    /// Shows the scene, which the renderer draws offscreen at exactly the size of this tab,
+    /// so picking and the camera's aspect ratio match what is on screen
+    fn viewport_tab(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        app_state.renderer.set_viewport(Viewport {
+            x: rect.min.x * pixels_per_point,
+            y: rect.min.y * pixels_per_point,
+            width: rect.width() * pixels_per_point,
+            height: rect.height() * pixels_per_point,
+        });
+        app_state.gui.set_scene_region(Some(rect));
+
+        if let Some(texture_id) = app_state.renderer.scene_texture()? {
+            // The texture's rows run from the bottom up
+            let uv = egui::Rect::from_min_max(egui::pos2(0.0, 1.0), egui::pos2(1.0, 0.0));
+            ui.put(rect, egui::Image::new(texture_id, rect.size()).uv(uv));
+        }
 
-        egui::TopBottomPanel::bottom("console")
+        self.place_axis_gizmo(app_state, &rect);
+        Ok(())
+    }
+
+    /// Keeps the world axes in the top right corner of the viewport, given in points
+    fn place_axis_gizmo(&mut self, app_state: &mut AppState, viewport: &egui::Rect) {
+        const SIZE: f32 = 90.0;
+        const MARGIN: f32 = 10.0;
+        let region = Viewport {
+            x: viewport.width() - SIZE - MARGIN,
+            y: MARGIN,
+            width: SIZE,
+            height: SIZE,
+        };
+        app_state.renderer.set_axis_gizmo(Some(region));
+        self.axis_gizmo_region = Some(Viewport {
+            x: viewport.min.x + region.x,
+            y: viewport.min.y + region.y,
+            ..region
+        });
+    }
+
+    /// Labels the ends of the world axes, which are drawn by the renderer
+    fn axis_gizmo_labels(&self, ctx: &egui::CtxRef, app_state: &AppState) -> Result<()> {
+        let region = match self.axis_gizmo_region.as_ref() {
+            Some(region) => region,
+            None => return Ok(()),
+        };
+        let (_, view) = app_state.world.active_camera_matrices(1.0)?;
+        let painter = ctx.layer_painter(LayerId::background());
+        for handle in axis_gizmo_handles(&view, region).into_iter() {
+            let color = handle.axis.color() * if handle.positive { 255.0 } else { 100.0 };
+            let color = egui::Color32::from_rgb(color.x as u8, color.y as u8, color.z as u8);
+            let center = egui::pos2(handle.position.x, handle.position.y);
+            if handle.positive {
+                painter.circle_filled(center, 8.0, color);
+                let label = ["X", "Y", "Z"][handle.axis.index()];
+                painter.text(
+                    center,
+                    egui::Align2::CENTER_CENTER,
+                    label,
+                    egui::TextStyle::Small,
+                    egui::Color32::BLACK,
+                );
+            } else {
+                painter.circle_filled(center, 5.0, color);
+            }
+        }
+        Ok(())
+    }
+
+    /// Aligns the view to the axis clicked on the world axes, returning false if none was clicked
+    fn click_axis_gizmo(&mut self, app_state: &mut AppState) -> Result<bool> {
+        let region = match self.axis_gizmo_region {
+            Some(region) => region,
+            None => return Ok(false),
+        };
+        if Self::editor_camera(app_state)?.is_none() {
+            return Ok(false);
+        }
+        let point = app_state.input.mouse.position / app_state.gui.context().pixels_per_point();
+        let (_, view) = app_state.world.active_camera_matrices(1.0)?;
+        match pick_axis_gizmo_handle(&view, &region, &point) {
+            Some(handle) => {
+                let view = OrbitView::from_axis(handle.axis, handle.positive);
+                self.set_camera_view(app_state, view)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// The primary selected entity, if it has a transform the gizmo can edit
+    fn gizmo_target(&self, app_state: &AppState) -> Option<Entity> {
+        let entity = self.selection.primary()?;
+        let entry = app_state.world.ecs.entry_ref(entity).ok()?;
+        entry.get_component::<Transform>().ok()?;
+        Some(entity)
+    }
+
+    fn gizmo_frame(&self, app_state: &AppState, entity: Entity) -> Result<GizmoFrame> {
+        let global_transform = app_state.world.entity_global_transform(entity)?;
+        let camera_entity = app_state.world.active_camera()?;
+        let camera_position = app_state
+            .world
+            .entity_global_transform(camera_entity)?
+            .translation;
+        Ok(self.gizmo.frame(&global_transform, &camera_position))
+    }
+
+    fn update_gizmo(&mut self, app_state: &mut AppState) -> Result<()> {
+        let entity = match self.gizmo_target(app_state) {
+            Some(entity) => entity,
+            None => {
+                self.gizmo.end_drag();
+                self.group_drag = None;
+                self.gizmo.hovered = None;
+                app_state.renderer.set_overlay_lines(Vec::new());
+                return Ok(());
+            }
+        };
+
+        let frame = self.gizmo_frame(app_state, entity)?;
+        let ray = app_state
+            .world
+            .mouse_ray(&app_state.mouse_ray_configuration()?)?;
+        if self.gizmo.is_dragging() {
+            if let Some(transform) = self.gizmo.drag(&ray) {
+                self.apply_gizmo_transform(app_state, entity, transform)?;
+            }
+        } else {
+            self.gizmo.hover(&frame, &ray);
+        }
+
+        app_state
+            .renderer
+            .set_overlay_lines(self.gizmo.lines(&frame));
+        Ok(())
+    }
+
+    /// Starts a gizmo drag if the mouse is over a handle
+    fn begin_gizmo_drag(&mut self, app_state: &mut AppState) -> Result<bool> {
+        let entity = match self.gizmo_target(app_state) {
+            Some(entity) => entity,
+            None => return Ok(false),
+        };
+        let frame = self.gizmo_frame(app_state, entity)?;
+        let ray = app_state
+            .world
+            .mouse_ray(&app_state.mouse_ray_configuration()?)?;
+        let local_transform = *app_state
+            .world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<Transform>()?;
+        let parent_transform = match app_state.world.parent_entity(entity) {
+            Some(parent) => app_state.world.entity_global_transform_matrix(parent)?,
+            None => glm::Mat4::identity(),
+        };
+        if !self
+            .gizmo
+            .begin_drag(&frame, &ray, local_transform, parent_transform)
+        {
+            return Ok(false);
+        }
+        self.group_drag = Some(GroupDrag::new(app_state.world, entity, &self.selection)?);
+        Ok(true)
+    }
+
+    /// Moves the dragged entity, and the rest of the selection along with it
+    fn apply_gizmo_transform(
+        &mut self,
+        app_state: &mut AppState,
+        entity: Entity,
+        transform: Transform,
+    ) -> Result<()> {
+        let group_drag = match self.group_drag.as_ref() {
+            Some(group_drag) if !group_drag.is_empty() => group_drag,
+            _ => {
+                let command = SetTransform::new(app_state.world, entity, transform)?;
+                return self.history.execute(app_state.world, command);
+            }
+        };
+        let parent_transform = match app_state.world.parent_entity(entity) {
+            Some(parent) => app_state.world.entity_global_transform_matrix(parent)?,
+            None => glm::Mat4::identity(),
+        };
+        let mut transforms = vec![(entity, transform)];
+        transforms.extend(group_drag.transforms(&(parent_transform * transform.matrix())));
+        let command = SetTransforms::new(app_state.world, transforms)?;
+        self.history.execute(app_state.world, command)
+    }
+
+    /// Selects the entities whose bounds overlap the dragged box,
+    /// or picks a single entity if the box is too small to be a drag
+    fn finish_box_select(&mut self, app_state: &mut AppState, box_select: BoxSelect) -> Result<()> {
+        let end = app_state.input.mouse.position;
+        if glm::distance(&box_select.start, &end) < BoxSelect::CLICK_DISTANCE {
+            return self.pick(app_state, box_select.mode);
+        }
+        let configuration = app_state.mouse_ray_configuration()?;
+        // The box is measured from the viewport's top left, like the mouse ray
+        let viewport = app_state.renderer.viewport();
+        let offset = glm::vec2(viewport.x, viewport.y);
+        let entities = app_state.world.entities_in_screen_rect(
+            glm::min2(&box_select.start, &end) - offset,
+            glm::max2(&box_select.start, &end) - offset,
+            configuration.viewport,
+            &configuration.projection_matrix,
+            &configuration.view_matrix,
+        )?;
+        self.selection.apply(&entities, box_select.mode);
+        Ok(())
+    }
+
+    /// Selects the entity under the mouse
+    fn pick(&mut self, app_state: &mut AppState, mode: SelectionMode) -> Result<()> {
+        let interact_distance = f32::MAX;
+        if let Some(entity) = app_state.world.pick_object(
+            &app_state.mouse_ray_configuration()?,
+            interact_distance,
+            InteractionGroups::all(),
+        )? {
+            self.selection.apply(&[entity], mode);
+            log::info!("Selected entity: {:?}", entity);
+        }
+        Ok(())
+    }
+
+    /// Draws the rubber band of a box selection over the viewport
+    fn box_select_overlay(&self, ctx: &egui::CtxRef, app_state: &AppState) {
+        let box_select = match self.box_select.as_ref() {
+            Some(box_select) => box_select,
+            None => return,
+        };
+        let pixels_per_point = ctx.pixels_per_point();
+        let to_point =
+            |pixel: glm::Vec2| egui::pos2(pixel.x / pixels_per_point, pixel.y / pixels_per_point);
+        let rect = egui::Rect::from_two_pos(
+            to_point(box_select.start),
+            to_point(app_state.input.mouse.position),
+        );
+        let painter =
+            ctx.layer_painter(LayerId::new(egui::Order::Foreground, Id::new("box_select")));
+        painter.rect_filled(
+            rect,
+            0.0,
+            egui::Color32::from_rgba_unmultiplied(255, 150, 25, 24),
+        );
+        painter.rect_stroke(rect, 0.0, (1.0, egui::Color32::from_rgb(255, 150, 25)));
+    }
+
+    /// Shows widgets for each registered component of the selected entity
+    fn component_inspector(&mut self, ui: &mut Ui, app_state: &mut AppState) -> Result<()> {
+        let entity = match self
+            .selection
+            .primary()
+            .filter(|entity| app_state.world.ecs.contains(*entity))
+        {
+            Some(entity) => entity,
+            None => {
+                ui.label("No entity selected");
+                return Ok(());
+            }
+        };
+        if self.selection.len() > 1 {
+            ui.label(format!(
+                "{} entities selected, showing the last one selected",
+                self.selection.len()
+            ));
+        }
+
+        // Components that reference entities or physics handles are read only
+        let mut registered = COMPONENT_REGISTRY
+            .read()
+            .expect("Failed to access component registry!")
+            .values
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.prefab))
+            .collect::<Vec<_>>();
+        registered.sort();
+
+        // A component that fails to convert is reported in its own section,
+        // so the entity's other components can still be edited
+        for (key, editable) in registered.into_iter() {
+            // Rigid bodies are edited through their physics settings below
+            if key == "rigid_body" {
+                continue;
+            }
+
+            let mut result = app_state.world.component_value(entity, &key);
+            if let Ok(None) = result {
+                continue;
+            }
+            let mut response = InspectResponse::default();
+            egui::CollapsingHeader::new(&key)
+                .default_open(true)
+                .show(ui, |ui| {
+                    if let Ok(Some(value)) = result.as_mut() {
+                        ui.add_enabled_ui(editable, |ui| {
+                            match inspect_component(&key, value, ui) {
+                                Ok(inspected) => response = inspected,
+                                Err(error) => {
+                                    ui.colored_label(
+                                        egui::Color32::RED,
+                                        format!("Failed to inspect component: {}", error),
+                                    );
+                                }
+                            }
+                        });
+                    }
+                    if let Err(error) = result.as_ref() {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!("Failed to read component: {}", error),
+                        );
+                    }
+                });
+
+            if let (true, Ok(Some(value))) = (response.changed, result) {
+                if let Err(error) = self.set_component(app_state, entity, &key, value) {
+                    log::warn!("Failed to set component '{}': {:?}", key, error);
+                }
+            }
+            if response.finished {
+                self.history.seal();
+            }
+        }
+
+        if let Some(mut settings) = app_state.world.rigid_body_settings(entity)? {
+            let mut response = InspectResponse::default();
+            egui::CollapsingHeader::new("rigid_body")
+                .default_open(true)
+                .show(ui, |ui| response = settings.inspect(ui));
+            if response.changed {
+                let command = SetRigidBody::new(app_state.world, entity, settings)?;
+                self.history.execute(app_state.world, command)?;
+            }
+            if response.finished {
+                self.history.seal();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies an inspector edit through the history, so it can be undone
+    fn set_component(
+        &mut self,
+        app_state: &mut AppState,
+        entity: Entity,
+        key: &str,
+        value: Value,
+    ) -> Result<()> {
+        if key == "transform" {
+            let transform = serde_json::from_value::<Transform>(value)?;
+            let command = SetTransform::new(app_state.world, entity, transform)?;
+            self.history.execute(app_state.world, command)
+        } else {
+            let command = SetComponent::new(app_state.world, entity, key, Some(value))?;
+            self.history.execute(app_state.world, command)
+        }
+    }
+
+    fn gizmo_controls(&mut self, ui: &mut Ui) {
+        ui.separator();
+        ui.selectable_value(&mut self.gizmo.mode, GizmoMode::Translate, "Move (W)");
+        ui.selectable_value(&mut self.gizmo.mode, GizmoMode::Rotate, "Rotate (E)");
+        ui.selectable_value(&mut self.gizmo.mode, GizmoMode::Scale, "Scale (R)");
+
+        ui.separator();
+        ui.add_enabled_ui(self.gizmo.mode != GizmoMode::Scale, |ui| {
+            ui.selectable_value(&mut self.gizmo.space, GizmoSpace::World, "World");
+            ui.selectable_value(&mut self.gizmo.space, GizmoSpace::Local, "Local");
+        });
+
+        ui.separator();
+        let snapping = &mut self.gizmo.snapping;
+        ui.checkbox(&mut snapping.enabled, "Snap");
+        ui.add_enabled_ui(snapping.enabled, |ui| {
+            ui.checkbox(&mut snapping.to_grid, "To Grid");
+            // Snapping to the grid moves in steps of the grid spacing
+            ui.add_enabled(
+                !snapping.to_grid,
+                DragValue::new(&mut snapping.translation)
+                    .speed(0.05)
+                    .clamp_range(0.01..=100.0)
+                    .prefix("Move: "),
+            );
+            ui.add(
+                DragValue::new(&mut snapping.rotation_degrees)
+                    .speed(1.0)
+                    .clamp_range(1.0..=180.0)
+                    .prefix("Rotate: ")
+                    .suffix("°"),
+            );
+            ui.add(
+                DragValue::new(&mut snapping.scale)
+                    .speed(0.01)
+                    .clamp_range(0.01..=10.0)
+                    .prefix("Scale: "),
+            );
+        });
+    }
+}
+
+impl App for Editor {
+    fn gui_active(&mut self) -> bool {
+        true
+    }
+
+    fn initialize(&mut self, app_state: &mut AppState) -> Result<()> {
+        env_logger::init();
+        register_component::<Selected>("selected")?;
+        // Picking in the viewport raycasts against colliders
+        self.import_options.colliders = ImportColliders::Trimesh;
+        // The simulation only runs in play mode
+        app_state.scheduler.simulating = false;
+        app_state.renderer.set_grid(GridSettings {
+            enabled: true,
+            ..Default::default()
+        });
+        // The scene is shown in the viewport tab, so it never renders under the gui
+        app_state.renderer.set_offscreen(true);
+        self.recent_files = RecentFiles::load();
+        self.recent_files.remove_missing();
+        Ok(())
+    }
+
+    fn on_exit(&mut self, app_state: &mut AppState) -> Result<()> {
+        if let Err(error) = self.save_project(app_state) {
+            log::error!("{:?}", error);
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, app_state: &mut AppState) -> Result<()> {
+        self.reload_changed_prefabs(app_state);
+
+        let dragging = self.gizmo.is_dragging() || self.box_select.is_some();
+        if let Some(camera_entity) = Self::editor_camera(app_state)? {
+            match self.camera_mode {
+                CameraMode::Orbit if !dragging => self.camera.update(app_state, camera_entity)?,
+                CameraMode::Fly => self.fly_camera.update(app_state, camera_entity)?,
+                _ => {}
+            }
+        }
+
+        if self.gizmo.snapping.to_grid {
+            self.gizmo.snapping.translation = app_state.renderer.grid().spacing;
+        }
+        self.update_gizmo(app_state)?;
+
+        app_state
+            .renderer
+            .set_selected_entities(self.selection.entities().to_vec());
+
+        Ok(())
+    }
+
+    fn update_gui(&mut self, app_state: &mut AppState) -> Result<()> {
+        let ctx = &app_state.gui.context();
+
+        egui::TopBottomPanel::top("top_panel")
             .resizable(true)
             .show(ctx, |ui| {
-                ui.heading("Console");
-                ui.allocate_space(ui.available_size());
+                egui::menu::bar(ui, |ui| {
+                    menu::bar(ui, |ui| {
+                        global_dark_light_mode_switch(ui);
+                        ui.menu_button("File", |ui| {
+                            if let Err(error) = self.file_menu(ui, app_state) {
+                                log::error!("{:?}", error);
+                            }
+                        });
+
+                        ui.menu_button("Edit", |ui| {
+                            if ui
+                                .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                                .clicked()
+                            {
+                                self.undo(app_state);
+                                ui.close_menu();
+                            }
+                            if ui
+                                .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                                .clicked()
+                            {
+                                self.redo(app_state);
+                                ui.close_menu();
+                            }
+                            ui.checkbox(&mut self.layout.show_history, "History");
+                            ui.checkbox(&mut self.layout.show_materials, "Materials");
+                            if ui.button("Reset Layout").clicked() {
+                                self.layout.dock = LayoutSettings::default().dock;
+                                ui.close_menu();
+                            }
+                        });
+
+                        ui.menu_button("Camera", |ui| {
+                            if let Err(error) = self.camera_menu(ui, app_state) {
+                                log::error!("{:?}", error);
+                            }
+                        });
+
+                        ui.menu_button("Grid", |ui| {
+                            self.grid_menu(ui, app_state);
+                        });
+
+                        ui.menu_button("Debug", |ui| {
+                            let mut settings = app_state.renderer.physics_debug();
+                            ui.checkbox(&mut settings.enabled, "Physics Debug");
+                            ui.add_enabled_ui(settings.enabled, |ui| {
+                                ui.checkbox(&mut settings.colliders, "Colliders");
+                                ui.checkbox(&mut settings.aabbs, "Bounding Boxes");
+                                ui.checkbox(&mut settings.contacts, "Contacts");
+                                ui.checkbox(&mut settings.joints, "Joints");
+                                ui.checkbox(&mut settings.sleep_states, "Sleep States");
+                            });
+                            app_state.renderer.set_physics_debug(settings);
+                        });
+
+                        if let Err(error) = self.play_controls(ui, app_state) {
+                            log::error!("{:?}", error);
+                        }
+
+                        self.gizmo_controls(ui);
+                    });
+                });
             });
 
-        // This is the space leftover on screen after the UI is drawn
-        // We can restrict rendering to this viewport to
-        // prevent drawing the gui over the scene
-        let central_rect = Ui::new(
-            ctx.clone(),
-            LayerId::background(),
-            Id::new("central_panel"),
-            ctx.available_rect(),
-            ctx.input().screen_rect(),
-        )
-        .max_rect();
-
-        // TODO: Don't render underneath the gui
-        let _viewport = Viewport {
-            x: central_rect.min.x,
-            y: central_rect.min.y,
-            width: central_rect.width(),
-            height: central_rect.height(),
-        };
-        // app_state.renderer.set_viewport(viewport);
+        // The viewport tab claims these again if it is shown this frame
+        app_state.gui.set_scene_region(None);
+        self.axis_gizmo_region = None;
+        app_state.renderer.set_axis_gizmo(None);
+
+        let mut dock = self.layout.dock.clone();
+        dock.show(ctx, |ui, tab| self.dock_tab(ui, app_state, tab))?;
+        self.layout.dock = dock;
+
+        self.apply_hierarchy_action(app_state)?;
+        self.rename_window(ctx, app_state)?;
+        self.history_window(ctx, app_state)?;
+        self.material_window(ctx, app_state)?;
+        self.box_select_overlay(ctx, app_state);
+        self.axis_gizmo_labels(ctx, app_state)?;
 
         Ok(())
     }
@@ -376,8 +1706,7 @@ impl App for Editor {
         path: &std::path::PathBuf,
         app_state: &mut AppState,
     ) -> Result<()> {
-        self.load_world_from_file(path, app_state)?;
-        Ok(())
+        self.open_file(path, app_state)
     }
 
     fn on_key(&mut self, input: KeyboardInput, app_state: &mut AppState) -> Result<()> {
@@ -387,9 +1716,38 @@ impl App for Editor {
             app_state.world.clear()?;
         }
 
-        if input.virtual_keycode == Some(VirtualKeyCode::G) && input.state == ElementState::Pressed
+        let control = app_state.input.is_key_pressed(VirtualKeyCode::LControl)
+            || app_state.input.is_key_pressed(VirtualKeyCode::RControl);
+
+        let typing = app_state.gui.context().wants_keyboard_input();
+        // WASD and QE move the fly camera while it is looking around
+        let flying = self.camera_mode == CameraMode::Fly && app_state.input.mouse.is_right_clicked;
+        if !control
+            && !typing
+            && !flying
+            && !self.gizmo.is_dragging()
+            && input.state == ElementState::Pressed
         {
-            self.moving_selected = !self.moving_selected;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::F) => self.frame_selected(app_state)?,
+                Some(VirtualKeyCode::W) => self.gizmo.mode = GizmoMode::Translate,
+                Some(VirtualKeyCode::E) => self.gizmo.mode = GizmoMode::Rotate,
+                Some(VirtualKeyCode::R) => self.gizmo.mode = GizmoMode::Scale,
+                Some(VirtualKeyCode::B) => self.box_select_armed = true,
+                _ => {}
+            }
+        }
+
+        let shift = app_state.input.is_key_pressed(VirtualKeyCode::LShift)
+            || app_state.input.is_key_pressed(VirtualKeyCode::RShift);
+        if control && input.state == ElementState::Pressed {
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::Z) if shift => self.redo(app_state),
+                Some(VirtualKeyCode::Z) => self.undo(app_state),
+                Some(VirtualKeyCode::Y) => self.redo(app_state),
+                Some(VirtualKeyCode::S) => self.save_level(app_state)?,
+                _ => {}
+            }
         }
 
         Ok(())
@@ -401,37 +1759,64 @@ impl App for Editor {
         button_state: &ElementState,
         app_state: &mut AppState,
     ) -> Result<()> {
+        if (MouseButton::Left, ElementState::Released) == (*button, *button_state) {
+            if self.gizmo.is_dragging() {
+                // Each drag becomes a single undoable edit
+                self.gizmo.end_drag();
+                self.group_drag = None;
+                self.history.seal();
+            }
+            if let Some(box_select) = self.box_select.take() {
+                self.finish_box_select(app_state, box_select)?;
+            }
+        }
+
         if (MouseButton::Left, ElementState::Pressed) == (*button, *button_state) {
-            let interact_distance = f32::MAX;
-            if let Some(entity) = app_state.world.pick_object(
-                &app_state.mouse_ray_configuration()?,
-                interact_distance,
-                InteractionGroups::all(),
-            )? {
-                let mut query = <(Entity, &Selected)>::query();
-                let already_selected = query
-                    .iter(&mut app_state.world.ecs)
-                    .map(|(e, _)| *e)
-                    .any(|e| e == entity);
-                if already_selected {
-                    return Ok(());
-                }
+            if !app_state.gui.pointer_over_scene() {
+                return Ok(());
+            }
 
-                self.deselect_all(app_state)?;
-                let mut entry = app_state
-                    .world
-                    .ecs
-                    .entry(entity)
-                    .context("Failed to find entity")?;
-                entry.add_component(Selected::default());
-                self.selected_entity = Some(entity);
-                log::info!("Selected entity: {:?}", entity);
+            if self.click_axis_gizmo(app_state)? {
+                return Ok(());
+            }
+
+            if self.begin_gizmo_drag(app_state)? {
+                return Ok(());
             }
+
+            let control = app_state.input.is_key_pressed(VirtualKeyCode::LControl)
+                || app_state.input.is_key_pressed(VirtualKeyCode::RControl);
+            let shift = app_state.input.is_key_pressed(VirtualKeyCode::LShift)
+                || app_state.input.is_key_pressed(VirtualKeyCode::RShift);
+            let mode = selection_mode(control, shift);
+
+            // Modified clicks start a box, which picks a single entity if it is never dragged out
+            if self.box_select_armed || mode != SelectionMode::Replace {
+                self.box_select_armed = false;
+                self.box_select = Some(BoxSelect {
+                    start: app_state.input.mouse.position,
+                    mode,
+                });
+                return Ok(());
+            }
+
+            self.pick(app_state, mode)?;
         }
         Ok(())
     }
 }
 
+/// Control toggles entities in and out of the selection, shift adds them to it
+fn selection_mode(control: bool, shift: bool) -> SelectionMode {
+    if control {
+        SelectionMode::Toggle
+    } else if shift {
+        SelectionMode::Add
+    } else {
+        SelectionMode::Replace
+    }
+}
+
 fn main() -> Result<()> {
     run_application(
         Editor::default(),
@@ -0,0 +1,331 @@
+use crate::EditorTab;
+use dragonglass::{
+    app::{self, FlyCamera, MouseOrbit, Orientation},
+    dependencies::{
+        anyhow::{Context, Result},
+        log, nalgebra_glm as glm,
+        ron::{self, ser::PrettyConfig},
+        serde::{de::DeserializeOwned, Deserialize, Serialize},
+    },
+    gui::{Dock, DockArea},
+    render::GridSettings,
+    world::GizmoSnapping,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory holding a game's levels and assets,
+/// along with the editor settings used while working on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass::dependencies::serde")]
+pub struct Project {
+    pub name: String,
+    /// Level files, relative to the project root.
+    /// Only levels inside the project are listed.
+    pub levels: Vec<PathBuf>,
+    /// Where imported assets are kept, relative to the project root
+    pub asset_directory: PathBuf,
+    pub settings: EditorSettings,
+    /// Where the project file was opened from, which is not saved so projects can be moved
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl Project {
+    pub const EXTENSION: &'static str = "dgproj";
+    const LEVEL_DIRECTORY: &'static str = "levels";
+    const ASSET_DIRECTORY: &'static str = "assets";
+
+    /// Creates a project in the given directory, named after it
+    pub fn create(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let name = root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("Failed to name the project after its directory!")?
+            .to_string();
+        let path = root.join(&name).with_extension(Self::EXTENSION);
+        let project = Self {
+            name,
+            levels: Vec::new(),
+            asset_directory: PathBuf::from(Self::ASSET_DIRECTORY),
+            settings: EditorSettings::default(),
+            path,
+        };
+        fs::create_dir_all(project.level_directory())
+            .context("Failed to create the project's level directory!")?;
+        fs::create_dir_all(project.asset_path())
+            .context("Failed to create the project's asset directory!")?;
+        project.save()?;
+        Ok(project)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut project: Self = read_ron(path).context("Failed to open project!")?;
+        project.path = path.to_path_buf();
+        Ok(project)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        write_ron(&self.path, self).context("Failed to save project!")
+    }
+
+    /// The directory containing the project file
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new("."))
+    }
+
+    pub fn level_directory(&self) -> PathBuf {
+        self.root().join(Self::LEVEL_DIRECTORY)
+    }
+
+    pub fn asset_path(&self) -> PathBuf {
+        self.root().join(&self.asset_directory)
+    }
+
+    /// The absolute paths of the project's levels
+    pub fn level_paths(&self) -> Vec<PathBuf> {
+        self.levels
+            .iter()
+            .map(|level| self.root().join(level))
+            .collect()
+    }
+
+    /// Adds a level inside the project, storing it relative to the root.
+    /// Levels outside the project are opened and saved without being added.
+    pub fn add_level(&mut self, path: &Path) {
+        let level = match path.strip_prefix(self.root()) {
+            Ok(level) => level.to_path_buf(),
+            Err(_) => return,
+        };
+        if !self.levels.contains(&level) {
+            self.levels.push(level);
+        }
+    }
+
+    /// Copies an asset from outside the project into its asset directory,
+    /// returning the path the asset should be imported from.
+    /// Only self contained `.glb` files are copied,
+    /// since `.gltf` files may reference buffers and images beside them.
+    /// An identical copy already in the project is reused,
+    /// while a different asset with the same name is copied under a numbered name.
+    pub fn import_asset(&self, path: &Path) -> Result<PathBuf> {
+        let is_glb = path
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("glb"));
+        if !is_glb || path.starts_with(self.root()) {
+            return Ok(path.to_path_buf());
+        }
+
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .context("Failed to find asset file name!")?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("glb");
+        let mut destination = self.asset_path().join(format!("{}.{}", stem, extension));
+        let mut suffix = 1;
+        while destination.exists() {
+            if same_contents(path, &destination)? {
+                return Ok(destination);
+            }
+            destination = self
+                .asset_path()
+                .join(format!("{}_{}.{}", stem, suffix, extension));
+            suffix += 1;
+        }
+
+        fs::create_dir_all(self.asset_path())
+            .context("Failed to create the project's asset directory!")?;
+        fs::copy(path, &destination)
+            .with_context(|| format!("Failed to copy asset into {}!", destination.display()))?;
+        log::info!(
+            "Copied {} into the project as {}",
+            path.display(),
+            destination.display()
+        );
+        Ok(destination)
+    }
+}
+
+/// Editor state that is restored when a project is reopened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass::dependencies::serde")]
+#[serde(default)]
+pub struct EditorSettings {
+    pub camera: CameraSettings,
+    pub layout: LayoutSettings,
+    pub snapping: GizmoSnapping,
+    pub grid: GridSettings,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self {
+            camera: CameraSettings::default(),
+            layout: LayoutSettings::default(),
+            snapping: GizmoSnapping::default(),
+            grid: GridSettings {
+                enabled: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Where the editor camera was looking, and how it responds to input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass::dependencies::serde")]
+#[serde(default)]
+pub struct CameraSettings {
+    pub focus: glm::Vec3,
+    pub direction: glm::Vec2,
+    pub radius: f32,
+    /// Shared by the orbit and fly cameras, whose fly speed is kept here too
+    pub controls: app::CameraSettings,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self::new(&MouseOrbit::default().orientation, &FlyCamera::default())
+    }
+}
+
+impl CameraSettings {
+    /// Records an orbit camera's orientation, along with the fly camera's controls
+    pub fn new(orientation: &Orientation, fly_camera: &FlyCamera) -> Self {
+        Self {
+            focus: orientation.offset,
+            direction: orientation.direction,
+            radius: orientation.radius,
+            controls: fly_camera.settings.clone(),
+        }
+    }
+
+    pub fn apply(&self, orbit: &mut MouseOrbit, fly_camera: &mut FlyCamera) {
+        let mut orientation = orbit.orientation.clone();
+        orientation.offset = self.focus;
+        orientation.direction = self.direction;
+        orientation.radius = self.radius;
+        orientation.limit(&self.controls);
+        orbit.set_orientation(orientation);
+        orbit.settings = self.controls.clone();
+        fly_camera.settings = self.controls.clone();
+    }
+}
+
+/// Where the editor's panels are docked, and which of its optional windows are open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass::dependencies::serde")]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub dock: Dock<EditorTab>,
+    pub show_history: bool,
+    pub show_materials: bool,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            dock: Dock::new(vec![
+                (EditorTab::Hierarchy, DockArea::Left),
+                (EditorTab::Inspector, DockArea::Right),
+                (EditorTab::Console, DockArea::Bottom),
+                (EditorTab::Assets, DockArea::Bottom),
+                (EditorTab::Viewport, DockArea::Center),
+            ]),
+            show_history: false,
+            show_materials: false,
+        }
+    }
+}
+
+/// The projects and levels opened most recently, newest first.
+/// These are shared by every project, so they are kept in the user's home directory.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass::dependencies::serde")]
+pub struct RecentFiles {
+    pub paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    const MAX_ENTRIES: usize = 10;
+    const FILE_NAME: &'static str = "recent_files.ron";
+
+    /// Loads the recent files, starting an empty list if there are none yet
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Self::default(),
+        };
+        match read_ron(&path) {
+            Ok(recent_files) => recent_files,
+            Err(error) => {
+                log::warn!("Failed to read recent files: {:?}", error);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("Failed to find the user's home directory!")?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)
+                .context("Failed to create the editor's settings directory!")?;
+        }
+        write_ron(&path, self).context("Failed to save recent files!")
+    }
+
+    /// Moves a path to the front of the list, dropping the oldest entries once it is full
+    pub fn add(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.paths.retain(|existing| *existing != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(Self::MAX_ENTRIES);
+    }
+
+    /// Forgets a file, such as one that could no longer be opened
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|existing| existing != path);
+    }
+
+    /// Forgets files that were moved or deleted since they were opened
+    pub fn remove_missing(&mut self) {
+        self.paths.retain(|path| path.exists());
+    }
+
+    fn path() -> Option<PathBuf> {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        Some(
+            PathBuf::from(home)
+                .join(".dragonglass")
+                .join(Self::FILE_NAME),
+        )
+    }
+}
+
+/// Whether two files hold the same bytes
+fn same_contents(first: &Path, second: &Path) -> Result<bool> {
+    let read =
+        |path: &Path| fs::read(path).with_context(|| format!("Failed to read {}!", path.display()));
+    if fs::metadata(first)?.len() != fs::metadata(second)?.len() {
+        return Ok(false);
+    }
+    Ok(read(first)? == read(second)?)
+}
+
+fn read_ron<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}!", path.display()))?;
+    ron::de::from_str(&text).with_context(|| format!("Failed to parse {}!", path.display()))
+}
+
+fn write_ron<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let text = ron::ser::to_string_pretty(value, PrettyConfig::new())?;
+    fs::write(path, text).with_context(|| format!("Failed to write {}!", path.display()))
+}
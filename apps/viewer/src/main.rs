@@ -1,5 +1,5 @@
 use dragonglass::{
-    app::{run_application, App, AppConfig, AppState, MouseOrbit},
+    app::{run_application, App, AppConfig, AppState, CameraPlugin},
     dependencies::{
         anyhow::Result,
         egui::{self, Id, LayerId, Ui},
@@ -8,13 +8,11 @@ use dragonglass::{
         log,
         winit::event::{ElementState, KeyboardInput, VirtualKeyCode},
     },
-    world::{load_gltf, Camera, Entity, Viewport},
+    world::{load_gltf, AnimationState, Camera, Entity, Viewport},
 };
 
 #[derive(Default)]
-struct Viewer {
-    camera: MouseOrbit,
-}
+struct Viewer;
 
 impl App for Viewer {
     fn gui_active(&mut self) -> bool {
@@ -24,21 +22,11 @@ impl App for Viewer {
     fn initialize(&mut self, app_state: &mut AppState) -> Result<()> {
         env_logger::init();
         app_state.world.add_default_light()?;
-        Ok(())
-    }
-
-    fn update(&mut self, app_state: &mut AppState) -> Result<()> {
-        if app_state.world.active_camera_is_main()? {
-            let camera_entity = app_state.world.active_camera()?;
-            self.camera.update(app_state, camera_entity)?;
-        }
-
-        if !app_state.world.animations.is_empty() {
-            app_state
-                .world
-                .animate(0, 0.75 * app_state.system.delta_time as f32)?;
-        }
-
+        app_state.scheduler.resources.insert(AnimationState {
+            index: Some(0),
+            speed: 0.75,
+        });
+        app_state.scheduler.add_plugin(CameraPlugin)?;
         Ok(())
     }
 
@@ -68,13 +56,13 @@ impl App for Viewer {
         )
         .max_rect();
 
-        let dimensions = app_state.context.window().inner_size();
-        let _offset = dimensions.height as f32 - viewport.max.y;
+        // The gui measures in points, while the renderer measures in pixels
+        let pixels_per_point = ctx.pixels_per_point();
         app_state.renderer.set_viewport(Viewport {
-            x: viewport.min.x,
-            y: viewport.min.y,
-            width: viewport.width(),
-            height: viewport.height(),
+            x: viewport.min.x * pixels_per_point,
+            y: viewport.min.y * pixels_per_point,
+            width: viewport.width() * pixels_per_point,
+            height: viewport.height() * pixels_per_point,
         });
 
         Ok(())
@@ -13,7 +13,7 @@ use dragonglass_dependencies::{
 };
 use dragonglass_gui::{Gui, ScreenDescriptor};
 use dragonglass_render::{create_render_backend, Backend};
-use dragonglass_world::World;
+use dragonglass_world::{DefaultPlugins, Scheduler, World};
 use std::path::PathBuf;
 
 pub trait App {
@@ -32,6 +32,10 @@ pub trait App {
     fn on_file_dropped(&mut self, _path: &PathBuf, _app_state: &mut AppState) -> Result<()> {
         Ok(())
     }
+    /// Called once as the app exits, whether the window was closed or an exit was requested
+    fn on_exit(&mut self, _app_state: &mut AppState) -> Result<()> {
+        Ok(())
+    }
     fn cleanup(&mut self) -> Result<()> {
         Ok(())
     }
@@ -86,6 +90,8 @@ pub fn run_application(mut app: impl App + 'static, config: &AppConfig) -> Resul
 
     let windowed_context = ContextBuilder::new()
         .with_srgb(true)
+        // Selection outlines are masked with the stencil buffer
+        .with_stencil_buffer(8)
         .build_windowed(window_builder, &event_loop)?;
 
     let inner_size = windowed_context.window().inner_size();
@@ -107,15 +113,19 @@ pub fn run_application(mut app: impl App + 'static, config: &AppConfig) -> Resul
     let mut context = unsafe { windowed_context.make_current().unwrap() };
 
     let mut world = World::new()?;
+    let mut scheduler = Scheduler::new();
+    scheduler.add_plugin(DefaultPlugins)?;
 
     let mut renderer = create_render_backend(&Backend::OpenGL, &context, inner_size)?;
 
     let mut input = Input::default();
     let mut system = System::new(inner_size);
+    scheduler.resources.insert(input.clone());
 
     app.initialize(&mut AppState {
         context: &mut context,
         world: &mut world,
+        scheduler: &mut scheduler,
         gui: &mut gui,
         renderer: &mut renderer,
         input: &mut input,
@@ -126,6 +136,7 @@ pub fn run_application(mut app: impl App + 'static, config: &AppConfig) -> Resul
         let state = AppState {
             context: &mut context,
             world: &mut world,
+            scheduler: &mut scheduler,
             gui: &mut gui,
             renderer: &mut renderer,
             input: &mut input,
@@ -182,8 +193,15 @@ fn run_loop(
             _ => (),
         },
         Event::MainEventsCleared => {
-            app_state.world.tick(app_state.system.delta_time as f32)?;
+            app_state
+                .scheduler
+                .resources
+                .insert(app_state.input.clone());
+            app_state
+                .scheduler
+                .update(app_state.world, app_state.system.delta_time as f32)?;
             app.update(&mut app_state)?;
+            app_state.scheduler.post_update(app_state.world)?;
 
             let clipped_shapes = if app.gui_active() {
                 let _frame_data = app_state
@@ -195,6 +213,7 @@ fn run_loop(
                 Vec::new()
             };
 
+            app_state.scheduler.render_prep(app_state.world)?;
             app_state.renderer.render(
                 app_state.context,
                 app_state.world,
@@ -203,6 +222,7 @@ fn run_loop(
             )?;
         }
         Event::LoopDestroyed => {
+            app.on_exit(&mut app_state)?;
             app_state.renderer.cleanup();
             app.cleanup()?;
         }
@@ -1,33 +1,360 @@
-use crate::AppState;
-use dragonglass_dependencies::{anyhow::Result, nalgebra_glm as glm};
-use dragonglass_world::{Entity, EntityStore, Transform};
+use crate::{AppState, Input};
+use dragonglass_dependencies::{
+    anyhow::{Context, Result},
+    nalgebra::Point3,
+    nalgebra_glm as glm,
+    rapier3d::prelude::{QueryFilter, Ray},
+    serde::{Deserialize, Serialize},
+    winit::event::VirtualKeyCode,
+};
+use dragonglass_world::{
+    BoundingBox, Camera, CameraBookmark, CameraBookmarks, Entity, EntityStore, GizmoAxis,
+    OrthographicCamera, PerspectiveCamera, Projection, RigidBody, Transform, World,
+};
 
+/// The axis aligned views an orbit can snap to.
+/// Every view but the perspective one is orthographic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OrbitView {
+    Perspective,
+    Top,
+    Bottom,
+    Front,
+    Back,
+    Right,
+    Left,
+}
+
+impl OrbitView {
+    /// Looking straight down or up leaves no horizontal direction to orient the view by,
+    /// so those views are tilted very slightly towards the front
+    const VERTICAL_PITCH: f32 = 0.0001;
+
+    /// The view looking back along an axis from its positive or negative side
+    pub fn from_axis(axis: GizmoAxis, positive: bool) -> Self {
+        match (axis, positive) {
+            (GizmoAxis::X, true) => Self::Right,
+            (GizmoAxis::X, false) => Self::Left,
+            (GizmoAxis::Y, true) => Self::Top,
+            (GizmoAxis::Y, false) => Self::Bottom,
+            (GizmoAxis::Z, true) => Self::Front,
+            (GizmoAxis::Z, false) => Self::Back,
+        }
+    }
+
+    /// The yaw and pitch the orbit looks from, if the view has a fixed direction
+    fn direction(&self) -> Option<glm::Vec2> {
+        let quarter = 90_f32.to_radians();
+        match self {
+            Self::Perspective => None,
+            Self::Top => Some(glm::vec2(0.0, Self::VERTICAL_PITCH)),
+            Self::Bottom => Some(glm::vec2(0.0, 2.0 * quarter - Self::VERTICAL_PITCH)),
+            Self::Front => Some(glm::vec2(0.0, quarter)),
+            Self::Back => Some(glm::vec2(2.0 * quarter, quarter)),
+            Self::Right => Some(glm::vec2(quarter, quarter)),
+            Self::Left => Some(glm::vec2(-quarter, quarter)),
+        }
+    }
+}
+
+/// Orbits the camera around a focus point, turning with left drags, panning with right drags,
+/// and zooming with the mouse wheel
 #[derive(Default)]
 pub struct MouseOrbit {
+    /// Where input has placed the orbit, which the camera trails when damped
     pub orientation: Orientation,
+    pub settings: CameraSettings,
+    /// The projection to restore when leaving an orthographic view
+    perspective: Option<PerspectiveCamera>,
+    /// Where the camera is
+    current: Option<CameraRig>,
 }
 
 impl MouseOrbit {
+    const DEFAULT_Y_FOV: f32 = 70.0 * std::f32::consts::PI / 180.0;
+    const DEFAULT_Z_FAR: f32 = 1000.0;
+
     pub fn update(&mut self, app_state: &mut AppState, entity: Entity) -> Result<()> {
-        self.orientation
-            .zoom(app_state.input.mouse.wheel_delta.y * 0.3);
+        let input = CameraInput::from_app_state(app_state);
+        let mouse_delta = self.settings.mouse_delta(&input);
+        let aspect_ratio = app_state.renderer.viewport().aspect_ratio();
+
+        if input.rotate {
+            // Orbiting out of an orthographic view continues in perspective
+            if mouse_delta != glm::Vec2::zeros() && self.perspective.is_some() {
+                self.set_view(
+                    app_state.world,
+                    entity,
+                    OrbitView::Perspective,
+                    aspect_ratio,
+                )?;
+            }
+            let turn = mouse_delta.component_mul(&self.settings.rotate_speed);
+            self.orientation.direction.x -= turn.x;
+            self.orientation.direction.y += turn.y;
+        }
+
+        if input.pan {
+            let pan = mouse_delta * self.settings.pan_speed * self.orientation.radius;
+            self.orientation.pan(&pan);
+        }
+
+        self.orientation.radius *= self.settings.zoom_factor.powf(input.wheel_delta);
+        self.orientation.limit(&self.settings);
 
-        let mouse_delta = app_state.input.mouse.position_delta * app_state.system.delta_time as f32;
+        let amount = self.settings.damping_amount(input.delta_time);
+        self.move_towards_orientation(app_state.world, entity, aspect_ratio, amount)?;
 
-        if app_state.input.mouse.is_left_clicked {
-            let mut delta = mouse_delta;
-            delta.x = -1.0 * mouse_delta.x;
-            self.orientation.rotate(&delta);
+        app_state.set_cursor_grab(false)?;
+        app_state.set_cursor_visible(true);
+
+        Ok(())
+    }
+
+    /// Snaps the orbit to an axis aligned view, switching the camera's projection to match
+    pub fn set_view(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        view: OrbitView,
+        aspect_ratio: f32,
+    ) -> Result<()> {
+        if let Some(direction) = view.direction() {
+            self.orientation.direction = direction;
         }
 
         {
-            let mut entry = app_state.world.ecs.entry_mut(entity)?;
-            let mut transform = entry.get_component_mut::<Transform>()?;
-            if app_state.input.mouse.is_right_clicked {
-                self.orientation.pan(&mouse_delta)
+            let mut entry = world.ecs.entry_mut(entity)?;
+            let camera = entry.get_component_mut::<Camera>()?;
+            if view == OrbitView::Perspective {
+                if let Some(perspective) = self.perspective.take() {
+                    camera.projection = Projection::Perspective(perspective);
+                }
+            } else {
+                if let Projection::Perspective(perspective) = &camera.projection {
+                    self.perspective = Some(perspective.clone());
+                }
+                camera.projection = Projection::Orthographic(self.orthographic(aspect_ratio));
+            }
+        }
+
+        self.apply(world, entity, aspect_ratio)
+    }
+
+    /// Centers the orbit on a bounding box, such as the bounds of a selection,
+    /// and zooms out until all of it is in view
+    pub fn frame(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        bounding_box: &BoundingBox,
+    ) -> Result<()> {
+        let y_fov = {
+            let entry = world.ecs.entry_ref(entity)?;
+            self.y_fov(entry.get_component::<Camera>()?)
+        };
+        self.orientation.frame(bounding_box, y_fov);
+        let aspect_ratio = self.aspect_ratio(world, entity)?;
+        self.apply(world, entity, aspect_ratio)
+    }
+
+    /// Saves the current viewpoint on the camera under a name,
+    /// replacing any bookmark that already has it
+    pub fn save_bookmark(&self, world: &mut World, entity: Entity, name: &str) -> Result<()> {
+        let mut entry = world
+            .ecs
+            .entry(entity)
+            .context("Failed to find camera entity!")?;
+        let bookmark = CameraBookmark {
+            name: name.to_string(),
+            translation: self.orientation.position(),
+            rotation: self.orientation.look_at_offset(),
+            focus: self.orientation.offset,
+            projection: entry.get_component::<Camera>()?.projection.clone(),
+        };
+        match entry.get_component_mut::<CameraBookmarks>() {
+            Ok(bookmarks) => {
+                bookmarks.0.retain(|existing| existing.name != name);
+                bookmarks.0.push(bookmark);
+            }
+            Err(_) => entry.add_component(CameraBookmarks(vec![bookmark])),
+        }
+        Ok(())
+    }
+
+    /// Returns the orbit and the camera's projection to a saved viewpoint
+    pub fn go_to_bookmark(&mut self, world: &mut World, entity: Entity, name: &str) -> Result<()> {
+        {
+            let mut entry = world.ecs.entry_mut(entity)?;
+            let bookmark = entry
+                .get_component::<CameraBookmarks>()?
+                .0
+                .iter()
+                .find(|bookmark| bookmark.name == name)
+                .cloned()
+                .context("Failed to find camera bookmark!")?;
+            self.orientation
+                .look_from(&bookmark.translation, &bookmark.focus);
+
+            let camera = entry.get_component_mut::<Camera>()?;
+            match (&camera.projection, &bookmark.projection) {
+                (Projection::Perspective(perspective), Projection::Orthographic(_)) => {
+                    self.perspective = Some(perspective.clone())
+                }
+                (_, Projection::Perspective(_)) => self.perspective = None,
+                _ => {}
+            }
+            camera.projection = bookmark.projection;
+        }
+        let aspect_ratio = self.aspect_ratio(world, entity)?;
+        self.apply(world, entity, aspect_ratio)
+    }
+
+    /// Moves the camera to a new orientation immediately, without trailing towards it
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+        self.current = None;
+    }
+
+    /// Writes the orbit to the camera's transform,
+    /// and resizes an orthographic projection to match the orbit's zoom
+    fn apply(&mut self, world: &mut World, entity: Entity, aspect_ratio: f32) -> Result<()> {
+        self.move_towards_orientation(world, entity, aspect_ratio, 1.0)
+    }
+
+    /// Moves the camera a fraction of the way towards the orbit, where one moves it all the way
+    fn move_towards_orientation(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        aspect_ratio: f32,
+        amount: f32,
+    ) -> Result<()> {
+        let goal = self.orientation.rig();
+        let mut rig = self.current.unwrap_or(goal);
+        rig.approach(&goal, amount, false);
+        self.current = Some(rig);
+
+        let mut entry = world.ecs.entry_mut(entity)?;
+        {
+            let transform = entry.get_component_mut::<Transform>()?;
+            transform.translation = rig.position();
+            transform.rotation = rig.rotation();
+        }
+        let camera = entry.get_component_mut::<Camera>()?;
+        if camera.is_orthographic() {
+            camera.projection = Projection::Orthographic(self.orthographic(aspect_ratio));
+        }
+        Ok(())
+    }
+
+    /// An orthographic projection that shows as much at the orbit's offset
+    /// as the perspective projection it replaces does
+    fn orthographic(&self, aspect_ratio: f32) -> OrthographicCamera {
+        let (y_fov, z_far) = match &self.perspective {
+            Some(perspective) => (
+                perspective.y_fov_rad,
+                perspective.z_far.unwrap_or(Self::DEFAULT_Z_FAR),
+            ),
+            None => (Self::DEFAULT_Y_FOV, Self::DEFAULT_Z_FAR),
+        };
+        let y_mag = self.orientation.radius * (y_fov / 2.0).tan();
+        OrthographicCamera {
+            x_mag: y_mag * aspect_ratio,
+            y_mag,
+            z_far,
+            // Distance doesn't change what an orthographic camera sees,
+            // so geometry behind the orbit's position is kept in view
+            z_near: -z_far,
+        }
+    }
+
+    /// The vertical field of view the camera has, or returns to when leaving an orthographic view
+    fn y_fov(&self, camera: &Camera) -> f32 {
+        match (&camera.projection, &self.perspective) {
+            (Projection::Perspective(perspective), _) | (_, Some(perspective)) => {
+                perspective.y_fov_rad
+            }
+            _ => Self::DEFAULT_Y_FOV,
+        }
+    }
+
+    /// Orthographic projections store their aspect ratio in their magnitudes
+    fn aspect_ratio(&self, world: &World, entity: Entity) -> Result<f32> {
+        let entry = world.ecs.entry_ref(entity)?;
+        Ok(match &entry.get_component::<Camera>()?.projection {
+            Projection::Orthographic(orthographic) if orthographic.y_mag > 0.0 => {
+                orthographic.x_mag / orthographic.y_mag
+            }
+            _ => 1.0,
+        })
+    }
+}
+
+/// Flies the camera with WASD while the right mouse button is held, looking around with the mouse.
+/// Q and E move down and up, shift moves faster, control moves slower,
+/// and the mouse wheel changes the base speed.
+pub struct FlyCamera {
+    /// The orientation's offset is where input has placed the camera,
+    /// which the camera trails when damped
+    pub orientation: Orientation,
+    pub settings: CameraSettings,
+    /// Where the camera is
+    current: Option<CameraRig>,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self {
+            orientation: Orientation::default().eye(),
+            settings: CameraSettings::default(),
+            current: None,
+        }
+    }
+}
+
+impl FlyCamera {
+    const MIN_SPEED: f32 = 0.1;
+    const MAX_SPEED: f32 = 500.0;
+
+    pub fn update(&mut self, app_state: &mut AppState, entity: Entity) -> Result<()> {
+        let input = CameraInput::from_app_state(app_state);
+        if input.wheel_delta != 0.0 {
+            self.settings.move_speed = (self.settings.move_speed * 1.2_f32.powf(input.wheel_delta))
+                .clamp(Self::MIN_SPEED, Self::MAX_SPEED);
+        }
+
+        if input.pan {
+            let turn = self
+                .settings
+                .mouse_delta(&input)
+                .component_mul(&self.settings.rotate_speed);
+            self.orientation.direction -= turn;
+            self.orientation.limit(&self.settings);
+
+            let forward = -self.orientation.direction();
+            let right = -self.orientation.right();
+            let movement = right * input.movement.x
+                + glm::Vec3::y() * input.movement.y
+                + forward * input.movement.z;
+
+            if movement != glm::Vec3::zeros() {
+                let speed = self.settings.speed(&input);
+                self.orientation.offset += movement.normalize() * speed * input.delta_time;
             }
-            transform.translation = self.orientation.position();
-            transform.rotation = self.orientation.look_at_offset();
+        }
+
+        // Flying cameras turn in place, so the rig is placed around the point being looked at
+        let goal = self.orientation.focus().rig();
+        let mut rig = self.current.unwrap_or(goal);
+        rig.approach(&goal, self.settings.damping_amount(input.delta_time), true);
+        self.current = Some(rig);
+
+        {
+            let mut entry = app_state.world.ecs.entry_mut(entity)?;
+            let transform = entry.get_component_mut::<Transform>()?;
+            transform.translation = rig.position();
+            transform.rotation = rig.rotation();
         }
 
         app_state.set_cursor_grab(false)?;
@@ -35,6 +362,12 @@ impl MouseOrbit {
 
         Ok(())
     }
+
+    /// Moves the camera to a new orientation immediately, without trailing towards it
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+        self.current = None;
+    }
 }
 
 #[derive(Default)]
@@ -51,7 +384,7 @@ impl MouseLook {
 
         {
             let mut entry = app_state.world.ecs.entry_mut(entity)?;
-            let mut transform = entry.get_component_mut::<Transform>()?;
+            let transform = entry.get_component_mut::<Transform>()?;
             transform.rotation = self.orientation.look_forward();
         }
 
@@ -63,9 +396,13 @@ impl MouseLook {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Orientation {
     pub min_radius: f32,
     pub max_radius: f32,
+    /// The pitch limits in radians from straight up
+    pub min_pitch: f32,
+    pub max_pitch: f32,
     pub radius: f32,
     pub offset: glm::Vec3,
     pub sensitivity: glm::Vec2,
@@ -84,11 +421,8 @@ impl Orientation {
     pub fn rotate(&mut self, position_delta: &glm::Vec2) {
         let delta = position_delta.component_mul(&self.sensitivity);
         self.direction.x += delta.x;
-        self.direction.y = glm::clamp_scalar(
-            self.direction.y + delta.y,
-            10.0_f32.to_radians(),
-            170.0_f32.to_radians(),
-        );
+        self.direction.y =
+            glm::clamp_scalar(self.direction.y + delta.y, self.min_pitch, self.max_pitch);
     }
 
     pub fn up(&self) -> glm::Vec3 {
@@ -118,6 +452,67 @@ impl Orientation {
         }
     }
 
+    /// Orbits the focus point from the given position
+    pub fn look_from(&mut self, position: &glm::Vec3, focus: &glm::Vec3) {
+        self.offset = *focus;
+        let offset = position - focus;
+        let radius = glm::length(&offset);
+        if radius <= f32::EPSILON {
+            return;
+        }
+        let direction = offset / radius;
+        self.direction = glm::vec2(
+            direction.x.atan2(direction.z),
+            direction.y.clamp(-1.0, 1.0).acos(),
+        );
+        self.max_radius = self.max_radius.max(radius);
+        self.radius = radius.max(self.min_radius);
+    }
+
+    /// Centers the orbit on a bounding box, moving back far enough
+    /// for the whole box to fit in the vertical field of view
+    pub fn frame(&mut self, bounding_box: &BoundingBox, y_fov_rad: f32) {
+        self.offset = bounding_box.center();
+        let radius = glm::length(&bounding_box.half_extents()) / (y_fov_rad / 2.0).sin();
+        self.max_radius = self.max_radius.max(radius);
+        self.radius = radius.max(self.min_radius);
+    }
+
+    /// The same view from a flying camera, whose offset is its position
+    pub fn eye(&self) -> Self {
+        Self {
+            offset: self.position(),
+            ..self.clone()
+        }
+    }
+
+    /// The orbit around the point a flying camera is looking at, the inverse of `eye`
+    pub fn focus(&self) -> Self {
+        Self {
+            offset: self.offset - self.direction() * self.radius,
+            ..self.clone()
+        }
+    }
+
+    /// Keeps the orbit's pitch and radius within the settings' limits.
+    /// Framing can push the radius past the settings' maximum, so the orbit's own maximum is kept too.
+    pub fn limit(&mut self, settings: &CameraSettings) {
+        self.direction.y =
+            glm::clamp_scalar(self.direction.y, settings.min_pitch, settings.max_pitch);
+        let max_radius = settings.max_radius.max(self.max_radius);
+        self.radius = glm::clamp_scalar(self.radius, settings.min_radius, max_radius);
+    }
+
+    /// The camera placement the orbit describes
+    pub fn rig(&self) -> CameraRig {
+        CameraRig {
+            focus: self.offset,
+            yaw: self.direction.x,
+            pitch: self.direction.y,
+            radius: self.radius,
+        }
+    }
+
     pub fn look_at_offset(&self) -> glm::Quat {
         self.look(self.offset - self.position())
     }
@@ -136,6 +531,8 @@ impl Default for Orientation {
         Self {
             min_radius: 1.0,
             max_radius: 100.0,
+            min_pitch: 10_f32.to_radians(),
+            max_pitch: 170_f32.to_radians(),
             radius: 5.0,
             offset: glm::vec3(0.0, 0.0, 0.0),
             sensitivity: glm::vec2(1.0, 1.0),
@@ -143,3 +540,574 @@ impl Default for Orientation {
         }
     }
 }
+
+/// A frame of input for a `CameraController`.
+/// It is gathered apart from `AppState`,
+/// so controllers can also be driven by tests, replays, or scripts.
+#[derive(Default, Debug, Clone)]
+pub struct CameraInput {
+    pub delta_time: f32,
+    /// Mouse movement in pixels
+    pub mouse_delta: glm::Vec2,
+    /// Wheel steps, positive when scrolling away from the user
+    pub wheel_delta: f32,
+    /// Held to turn the camera
+    pub rotate: bool,
+    /// Held to slide the camera across the view
+    pub pan: bool,
+    /// Movement along the camera's right, up, and forward axes, each between -1 and 1
+    pub movement: glm::Vec3,
+    pub fast: bool,
+    pub slow: bool,
+}
+
+impl CameraInput {
+    /// Left drags rotate, right drags pan, WASD moves, Q and E move down and up,
+    /// shift moves faster, and control moves slower
+    pub fn from_app_state(app_state: &AppState) -> Self {
+        Self::from_input(app_state.input, app_state.system.delta_time as f32)
+    }
+
+    /// Gathers camera input outside of the app loop, such as from the scheduler's `Input` resource
+    pub fn from_input(input: &Input, delta_time: f32) -> Self {
+        let pressed = |keycode| input.is_key_pressed(keycode);
+        let axis = |positive, negative| match (pressed(positive), pressed(negative)) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        Self {
+            delta_time,
+            mouse_delta: input.mouse.position_delta,
+            wheel_delta: input.mouse.wheel_delta.y,
+            rotate: input.mouse.is_left_clicked,
+            pan: input.mouse.is_right_clicked,
+            movement: glm::vec3(
+                axis(VirtualKeyCode::D, VirtualKeyCode::A),
+                axis(VirtualKeyCode::E, VirtualKeyCode::Q),
+                axis(VirtualKeyCode::W, VirtualKeyCode::S),
+            ),
+            fast: pressed(VirtualKeyCode::LShift) || pressed(VirtualKeyCode::RShift),
+            slow: pressed(VirtualKeyCode::LControl) || pressed(VirtualKeyCode::RControl),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Radians turned per pixel the mouse moves
+    pub rotate_speed: glm::Vec2,
+    /// The fraction of the orbit radius panned per pixel the mouse moves
+    pub pan_speed: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    /// Each wheel step scales the orbit radius by this,
+    /// so zooming slows down as the camera nears its focus
+    pub zoom_factor: f32,
+    /// The pitch limits in radians from straight up
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    /// Units moved per second when flying
+    pub move_speed: f32,
+    pub fast_multiplier: f32,
+    pub slow_multiplier: f32,
+    /// How quickly the camera catches up with its input, per second.
+    /// Zero moves the camera immediately.
+    pub damping: f32,
+    /// Where the focus point of a followed entity sits relative to its origin
+    pub follow_offset: glm::Vec3,
+    /// How far a follow camera's spring arm stays from the colliders it hits
+    pub arm_margin: f32,
+}
+
+impl CameraSettings {
+    /// The input's mouse movement, with the inverted axes flipped
+    pub fn mouse_delta(&self, input: &CameraInput) -> glm::Vec2 {
+        let mut mouse_delta = input.mouse_delta;
+        if self.invert_x {
+            mouse_delta.x = -mouse_delta.x;
+        }
+        if self.invert_y {
+            mouse_delta.y = -mouse_delta.y;
+        }
+        mouse_delta
+    }
+
+    /// Units moved per second when flying, sped up or slowed down by the input's modifiers
+    pub fn speed(&self, input: &CameraInput) -> f32 {
+        let mut speed = self.move_speed;
+        if input.fast {
+            speed *= self.fast_multiplier;
+        }
+        if input.slow {
+            speed *= self.slow_multiplier;
+        }
+        speed
+    }
+
+    /// The fraction of the way a damped camera catches up with its input over a frame
+    pub fn damping_amount(&self, delta_time: f32) -> f32 {
+        if self.damping > 0.0 {
+            1.0 - (-self.damping * delta_time).exp()
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            rotate_speed: glm::vec2(0.005, 0.005),
+            pan_speed: 0.002,
+            invert_x: false,
+            invert_y: false,
+            zoom_factor: 0.9,
+            min_pitch: 10_f32.to_radians(),
+            max_pitch: 170_f32.to_radians(),
+            min_radius: 0.5,
+            max_radius: 500.0,
+            move_speed: 5.0,
+            fast_multiplier: 4.0,
+            slow_multiplier: 0.25,
+            damping: 12.0,
+            follow_offset: glm::vec3(0.0, 1.5, 0.0),
+            arm_margin: 0.2,
+        }
+    }
+}
+
+/// A camera placement, looking at a focus point from some distance away
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CameraRig {
+    pub focus: glm::Vec3,
+    pub yaw: f32,
+    /// Radians from straight up
+    pub pitch: f32,
+    pub radius: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: glm::Vec3::zeros(),
+            yaw: 0.0,
+            pitch: 45_f32.to_radians(),
+            radius: 5.0,
+        }
+    }
+}
+
+impl CameraRig {
+    /// The direction from the focus point to the camera
+    pub fn direction(&self) -> glm::Vec3 {
+        glm::vec3(
+            self.pitch.sin() * self.yaw.sin(),
+            self.pitch.cos(),
+            self.pitch.sin() * self.yaw.cos(),
+        )
+    }
+
+    pub fn position(&self) -> glm::Vec3 {
+        self.focus + self.direction() * self.radius
+    }
+
+    pub fn rotation(&self) -> glm::Quat {
+        glm::quat_conjugate(&glm::quat_look_at(&(-self.direction()), &glm::Vec3::y()))
+    }
+
+    /// The camera's right, as seen on screen
+    pub fn right(&self) -> glm::Vec3 {
+        glm::Vec3::y().cross(&self.direction()).normalize()
+    }
+
+    /// The camera's up, as seen on screen
+    pub fn up(&self) -> glm::Vec3 {
+        self.right().cross(&(-self.direction()))
+    }
+
+    fn clamp(&mut self, settings: &CameraSettings) {
+        self.pitch = glm::clamp_scalar(self.pitch, settings.min_pitch, settings.max_pitch);
+        self.radius = glm::clamp_scalar(self.radius, settings.min_radius, settings.max_radius);
+    }
+
+    /// Moves a fraction of the way towards another rig.
+    /// Flying cameras turn in place, so their position is interpolated instead of their focus.
+    fn approach(&mut self, goal: &Self, amount: f32, turn_in_place: bool) {
+        let position = glm::lerp(&self.position(), &goal.position(), amount);
+        self.yaw += (goal.yaw - self.yaw) * amount;
+        self.pitch += (goal.pitch - self.pitch) * amount;
+        self.radius += (goal.radius - self.radius) * amount;
+        self.focus = if turn_in_place {
+            position - self.direction() * self.radius
+        } else {
+            glm::lerp(&self.focus, &goal.focus, amount)
+        };
+    }
+}
+
+/// How a camera controller responds to input
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CameraMode {
+    /// Turns around, pans, and zooms towards a focus point
+    Orbit,
+    /// Turns in place and moves freely while rotating or panning is held
+    Fly,
+    /// Trails an entity on a spring arm, which shortens rather than pass through colliders
+    Follow(Entity),
+}
+
+/// What a camera controller is doing with its input
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CameraState {
+    Idle,
+    Orbiting,
+    Panning,
+    Flying,
+}
+
+impl CameraState {
+    /// The state after a frame of input.
+    /// Drags keep their state until released,
+    /// so pressing another button mid drag doesn't change what the drag does.
+    pub fn next(self, mode: CameraMode, input: &CameraInput) -> Self {
+        let can_pan = mode == CameraMode::Orbit;
+        match mode {
+            CameraMode::Fly if input.rotate || input.pan => Self::Flying,
+            CameraMode::Fly => Self::Idle,
+            _ => match self {
+                Self::Orbiting if input.rotate => Self::Orbiting,
+                Self::Panning if input.pan && can_pan => Self::Panning,
+                _ if input.rotate => Self::Orbiting,
+                _ if input.pan && can_pan => Self::Panning,
+                _ => Self::Idle,
+            },
+        }
+    }
+}
+
+/// A configurable camera with smoothing, driven by a state machine over `CameraInput`
+pub struct CameraController {
+    pub settings: CameraSettings,
+    mode: CameraMode,
+    state: CameraState,
+    /// Where input has placed the camera
+    goal: CameraRig,
+    /// Where the camera is, which trails the goal when damped
+    current: CameraRig,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self::new(CameraSettings::default())
+    }
+}
+
+impl CameraController {
+    const MIN_MOVE_SPEED: f32 = 0.1;
+    const MAX_MOVE_SPEED: f32 = 500.0;
+
+    pub fn new(settings: CameraSettings) -> Self {
+        Self {
+            settings,
+            mode: CameraMode::Orbit,
+            state: CameraState::Idle,
+            goal: CameraRig::default(),
+            current: CameraRig::default(),
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    pub fn state(&self) -> CameraState {
+        self.state
+    }
+
+    /// Where the camera currently is
+    pub fn rig(&self) -> &CameraRig {
+        &self.current
+    }
+
+    /// Where the camera is heading
+    pub fn goal(&self) -> &CameraRig {
+        &self.goal
+    }
+
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        self.mode = mode;
+        self.state = CameraState::Idle;
+    }
+
+    /// Moves the camera immediately, such as when jumping to a bookmark
+    pub fn set_rig(&mut self, mut rig: CameraRig) {
+        rig.clamp(&self.settings);
+        self.goal = rig;
+        self.current = rig;
+    }
+
+    /// Moves the camera smoothly, such as when framing a selection
+    pub fn set_goal(&mut self, mut rig: CameraRig) {
+        rig.clamp(&self.settings);
+        self.goal = rig;
+    }
+
+    /// Advances the controller by a frame of input.
+    /// The world is only read to find a followed entity and to cast its spring arm.
+    pub fn update(&mut self, input: &CameraInput, world: &World) -> Result<()> {
+        self.state = self.state.next(self.mode, input);
+
+        let mouse_delta = self.settings.mouse_delta(input);
+        let turn = mouse_delta.component_mul(&self.settings.rotate_speed);
+
+        match self.state {
+            CameraState::Idle => {}
+            CameraState::Orbiting => {
+                self.goal.yaw -= turn.x;
+                self.goal.pitch += turn.y;
+            }
+            CameraState::Panning => {
+                let pan = mouse_delta * self.settings.pan_speed * self.goal.radius;
+                self.goal.focus += self.goal.up() * pan.y - self.goal.right() * pan.x;
+            }
+            CameraState::Flying => {
+                let mut position = self.goal.position();
+                self.goal.yaw -= turn.x;
+                self.goal.pitch -= turn.y;
+                self.goal.clamp(&self.settings);
+
+                let movement = self.goal.right() * input.movement.x
+                    + glm::Vec3::y() * input.movement.y
+                    - self.goal.direction() * input.movement.z;
+                if movement != glm::Vec3::zeros() {
+                    let speed = self.settings.speed(input);
+                    position += movement.normalize() * speed * input.delta_time;
+                }
+                self.goal.focus = position - self.goal.direction() * self.goal.radius;
+            }
+        }
+
+        if input.wheel_delta != 0.0 {
+            if self.mode == CameraMode::Fly {
+                self.settings.move_speed = glm::clamp_scalar(
+                    self.settings.move_speed * 1.2_f32.powf(input.wheel_delta),
+                    Self::MIN_MOVE_SPEED,
+                    Self::MAX_MOVE_SPEED,
+                );
+            } else {
+                self.goal.radius *= self.settings.zoom_factor.powf(input.wheel_delta);
+            }
+        }
+        self.goal.clamp(&self.settings);
+
+        if let CameraMode::Follow(target) = self.mode {
+            self.goal.focus =
+                world.entity_global_transform(target)?.translation + self.settings.follow_offset;
+        }
+
+        let amount = self.settings.damping_amount(input.delta_time);
+        self.current
+            .approach(&self.goal, amount, self.mode == CameraMode::Fly);
+
+        if let CameraMode::Follow(target) = self.mode {
+            // The arm snaps in to stay clear of obstacles and eases back out once they pass
+            self.current.radius = self
+                .current
+                .radius
+                .min(self.spring_arm_length(world, target)?);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the camera's placement to an entity's transform
+    pub fn apply(&self, world: &mut World, entity: Entity) -> Result<()> {
+        let mut entry = world.ecs.entry_mut(entity)?;
+        let transform = entry.get_component_mut::<Transform>()?;
+        transform.translation = self.current.position();
+        transform.rotation = self.current.rotation();
+        Ok(())
+    }
+
+    /// How long the arm from the followed entity to the camera can be
+    /// without passing through anything but the entity itself
+    fn spring_arm_length(&self, world: &World, target: Entity) -> Result<f32> {
+        let mut filter = QueryFilter::default();
+        if let Ok(rigid_body) = world.ecs.entry_ref(target)?.get_component::<RigidBody>() {
+            filter = filter.exclude_rigid_body(rigid_body.handle);
+        }
+        let margin = self.settings.arm_margin;
+        let ray = Ray::new(Point3::from(self.current.focus), self.current.direction());
+        Ok(
+            match world.cast_ray_nearest(&ray, self.current.radius + margin, filter) {
+                Some(hit) => (hit.distance - margin).max(0.0),
+                None => self.current.radius,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(rotate: bool, pan: bool) -> CameraInput {
+        CameraInput {
+            delta_time: 1.0 / 60.0,
+            rotate,
+            pan,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orbit_states_follow_buttons() {
+        let mode = CameraMode::Orbit;
+        let state = CameraState::Idle.next(mode, &input(true, false));
+        assert_eq!(state, CameraState::Orbiting);
+        let state = state.next(mode, &input(false, false));
+        assert_eq!(state, CameraState::Idle);
+        let state = state.next(mode, &input(false, true));
+        assert_eq!(state, CameraState::Panning);
+    }
+
+    #[test]
+    fn drags_keep_their_state_until_released() {
+        let mode = CameraMode::Orbit;
+        let state = CameraState::Orbiting.next(mode, &input(true, true));
+        assert_eq!(state, CameraState::Orbiting);
+        let state = CameraState::Panning.next(mode, &input(true, true));
+        assert_eq!(state, CameraState::Panning);
+        let state = CameraState::Panning.next(mode, &input(true, false));
+        assert_eq!(state, CameraState::Orbiting);
+    }
+
+    #[test]
+    fn fly_mode_flies_while_either_button_is_held() {
+        let mode = CameraMode::Fly;
+        assert_eq!(
+            CameraState::Idle.next(mode, &input(true, false)),
+            CameraState::Flying
+        );
+        assert_eq!(
+            CameraState::Idle.next(mode, &input(false, true)),
+            CameraState::Flying
+        );
+        assert_eq!(
+            CameraState::Flying.next(mode, &input(false, false)),
+            CameraState::Idle
+        );
+    }
+
+    #[test]
+    fn follow_mode_orbits_but_never_pans() -> Result<()> {
+        let mut world = World::new()?;
+        let mode = CameraMode::Follow(world.ecs.push((Transform::default(),)));
+        assert_eq!(
+            CameraState::Idle.next(mode, &input(false, true)),
+            CameraState::Idle
+        );
+        assert_eq!(
+            CameraState::Idle.next(mode, &input(true, true)),
+            CameraState::Orbiting
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn damping_trails_the_goal() -> Result<()> {
+        let world = World::new()?;
+        let mut controller = CameraController::default();
+        controller.set_goal(CameraRig {
+            focus: glm::vec3(10.0, 0.0, 0.0),
+            ..Default::default()
+        });
+
+        controller.update(&input(false, false), &world)?;
+        let first = controller.rig().focus.x;
+        assert!(first > 0.0 && first < 10.0);
+
+        controller.update(&input(false, false), &world)?;
+        let second = controller.rig().focus.x;
+        assert!(second > first && second < 10.0);
+
+        for _ in 0..600 {
+            controller.update(&input(false, false), &world)?;
+        }
+        assert!((controller.rig().focus.x - 10.0).abs() < 1e-3);
+        Ok(())
+    }
+
+    #[test]
+    fn zero_damping_moves_immediately() -> Result<()> {
+        let world = World::new()?;
+        let mut controller = CameraController::new(CameraSettings {
+            damping: 0.0,
+            ..Default::default()
+        });
+        let goal = CameraRig {
+            focus: glm::vec3(10.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        controller.set_goal(goal);
+        controller.update(&input(false, false), &world)?;
+        assert_eq!(*controller.rig(), goal);
+        Ok(())
+    }
+
+    #[test]
+    fn damping_amount_is_independent_of_frame_rate() {
+        let settings = CameraSettings::default();
+        let one_frame = settings.damping_amount(0.1);
+        let half_frame = settings.damping_amount(0.05);
+        let two_half_frames = 1.0 - (1.0 - half_frame) * (1.0 - half_frame);
+        assert!((one_frame - two_half_frames).abs() < 1e-6);
+    }
+
+    #[test]
+    fn orbiting_is_limited_by_settings() -> Result<()> {
+        let world = World::new()?;
+        let mut controller = CameraController::default();
+        let mut rotate = input(true, false);
+        rotate.mouse_delta = glm::vec2(0.0, 100_000.0);
+        controller.update(&rotate, &world)?;
+        assert_eq!(controller.goal().pitch, controller.settings.max_pitch);
+        Ok(())
+    }
+
+    #[test]
+    fn orientation_limit_keeps_framed_radius() {
+        let settings = CameraSettings::default();
+        let mut orientation = Orientation {
+            max_radius: 1000.0,
+            radius: 800.0,
+            direction: glm::vec2(0.0, 0.0),
+            ..Default::default()
+        };
+        orientation.limit(&settings);
+        assert_eq!(orientation.radius, 800.0);
+        assert_eq!(orientation.direction.y, settings.min_pitch);
+
+        orientation.radius = 0.0;
+        orientation.limit(&settings);
+        assert_eq!(orientation.radius, settings.min_radius);
+    }
+
+    #[test]
+    fn orientation_rig_matches_orbit() {
+        let orientation = Orientation {
+            offset: glm::vec3(1.0, 2.0, 3.0),
+            ..Default::default()
+        };
+        let rig = orientation.rig();
+        assert!(glm::distance(&rig.position(), &orientation.position()) < 1e-5);
+        let rotation = glm::quat_to_mat3(&rig.rotation());
+        let expected = glm::quat_to_mat3(&orientation.look_at_offset());
+        assert!((rotation - expected).abs().max() < 1e-5);
+    }
+}
@@ -1,5 +1,6 @@
 mod app;
 mod camera;
+mod plugins;
 mod state;
 
-pub use self::{app::*, camera::*, state::*};
+pub use self::{app::*, camera::*, plugins::*, state::*};
@@ -0,0 +1,40 @@
+use crate::{CameraController, CameraInput, Input};
+use dragonglass_dependencies::anyhow::Result;
+use dragonglass_world::{DeltaTime, Plugin, Scheduler, Stage};
+
+/// Drives the main camera with the `CameraController` resource,
+/// reading the `Input` resource the app inserts each frame.
+/// Cameras other than the main camera are left alone.
+#[derive(Default)]
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn name(&self) -> &str {
+        "camera"
+    }
+
+    fn build(&self, scheduler: &mut Scheduler) -> Result<()> {
+        scheduler.resources.insert(CameraController::default());
+        scheduler.add_world_system(Stage::Update, "camera_controller", |world, resources| {
+            if !world.active_camera_is_main()? {
+                return Ok(());
+            }
+            let delta_time = resources
+                .get::<DeltaTime>()
+                .map(|delta_time| delta_time.0)
+                .unwrap_or_default();
+            let input = match resources.get::<Input>() {
+                Some(input) => CameraInput::from_input(&input, delta_time),
+                None => return Ok(()),
+            };
+            let mut controller = match resources.get_mut::<CameraController>() {
+                Some(controller) => controller,
+                None => return Ok(()),
+            };
+            let camera_entity = world.active_camera()?;
+            controller.update(&input, world)?;
+            controller.apply(world, camera_entity)
+        });
+        Ok(())
+    }
+}
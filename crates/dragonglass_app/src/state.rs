@@ -13,7 +13,7 @@ use dragonglass_dependencies::{
 };
 use dragonglass_gui::Gui;
 use dragonglass_render::Renderer;
-use dragonglass_world::{load_gltf, MouseRayConfiguration, World};
+use dragonglass_world::{load_gltf, MouseRayConfiguration, Scheduler, Viewport, World};
 use std::{cmp, collections::HashMap, time::Instant};
 
 pub type KeyMap = HashMap<VirtualKeyCode, ElementState>;
@@ -25,6 +25,7 @@ pub struct AppState<'a> {
     pub gui: &'a mut Gui,
     pub renderer: &'a mut Box<dyn Renderer>,
     pub world: &'a mut World,
+    pub scheduler: &'a mut Scheduler,
 }
 
 impl<'a> AppState<'a> {
@@ -55,16 +56,22 @@ impl<'a> AppState<'a> {
             )));
     }
 
+    /// Casts rays through the renderer's viewport.
+    /// The mouse position is measured from the viewport's top left, wherever it is in the window.
     pub fn mouse_ray_configuration(&self) -> Result<MouseRayConfiguration> {
         let viewport = self.renderer.viewport();
 
         let (projection, view) = self.world.active_camera_matrices(viewport.aspect_ratio())?;
 
         let mouse_ray_configuration = MouseRayConfiguration {
-            viewport,
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                ..viewport
+            },
             projection_matrix: projection,
             view_matrix: view,
-            mouse_position: self.input.mouse.position,
+            mouse_position: self.input.mouse.position - glm::vec2(viewport.x, viewport.y),
         };
 
         Ok(mouse_ray_configuration)
@@ -77,6 +84,8 @@ impl<'a> AppState<'a> {
     }
 }
 
+/// The keyboard and mouse state, which is also copied into the scheduler's resources each frame
+#[derive(Clone)]
 pub struct Input {
     pub keystates: KeyMap,
     pub mouse: Mouse,
@@ -118,7 +127,7 @@ impl Input {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Mouse {
     pub is_left_clicked: bool,
     pub is_right_clicked: bool,
@@ -20,5 +20,7 @@ pub use petgraph;
 pub use rapier3d;
 pub use raw_window_handle;
 pub use rfd;
+pub use ron;
 pub use serde;
+pub use serde_json;
 pub use winit;
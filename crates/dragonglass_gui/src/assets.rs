@@ -0,0 +1,122 @@
+use dragonglass_dependencies::{
+    egui::{self, DragValue, Ui},
+    rapier3d::dynamics::RigidBodyType,
+};
+use dragonglass_world::{AssetId, AssetInfo, ImportColliders, ImportOptions, UpAxis};
+
+/// Lists assets grouped by kind with their memory usage,
+/// returning the asset that was clicked
+pub fn asset_browser(ui: &mut Ui, assets: &[AssetInfo]) -> Option<AssetId> {
+    let total = assets.iter().map(|asset| asset.bytes).sum::<usize>();
+    ui.label(format!("Total: {}", format_bytes(total)));
+    ui.separator();
+
+    let mut clicked = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for heading in ["Meshes", "Materials", "Textures", "Animations", "Fonts"] {
+            let group = assets
+                .iter()
+                .filter(|asset| asset_group(&asset.id) == heading)
+                .collect::<Vec<_>>();
+            let bytes = group.iter().map(|asset| asset.bytes).sum::<usize>();
+            egui::CollapsingHeader::new(format!(
+                "{} ({}, {})",
+                heading,
+                group.len(),
+                format_bytes(bytes)
+            ))
+            .id_source(heading)
+            .show(ui, |ui| {
+                egui::Grid::new(heading).striped(true).show(ui, |ui| {
+                    for asset in group.into_iter() {
+                        if ui
+                            .selectable_label(false, &asset.name)
+                            .on_hover_text(&asset.description)
+                            .clicked()
+                        {
+                            clicked = Some(asset.id.clone());
+                        }
+                        ui.label(format_bytes(asset.bytes));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+    });
+    clicked
+}
+
+fn asset_group(id: &AssetId) -> &'static str {
+    match id {
+        AssetId::Mesh(_) => "Meshes",
+        AssetId::Material(_) => "Materials",
+        AssetId::Texture(_) => "Textures",
+        AssetId::Animation(_) => "Animations",
+        AssetId::Font(_) => "Fonts",
+    }
+}
+
+/// Shows widgets for the options applied when importing a file
+pub fn import_options_editor(ui: &mut Ui, options: &mut ImportOptions) {
+    ui.horizontal(|ui| {
+        ui.label("Scale");
+        ui.add(
+            DragValue::new(&mut options.scale)
+                .speed(0.01)
+                .clamp_range(0.001..=1000.0),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Up Axis");
+        ui.selectable_value(&mut options.up_axis, UpAxis::Y, "Y");
+        ui.selectable_value(&mut options.up_axis, UpAxis::Z, "Z");
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Hierarchy");
+        ui.selectable_value(&mut options.merge, false, "Separate");
+        ui.selectable_value(&mut options.merge, true, "Merge");
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Colliders");
+        ui.selectable_value(&mut options.colliders, ImportColliders::None, "None");
+        ui.selectable_value(&mut options.colliders, ImportColliders::Trimesh, "Trimesh");
+        ui.selectable_value(
+            &mut options.colliders,
+            ImportColliders::BoundingBox,
+            "Bounding Box",
+        );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Rigid Body");
+        ui.selectable_value(&mut options.rigid_body, None, "None");
+        ui.selectable_value(&mut options.rigid_body, Some(RigidBodyType::Fixed), "Fixed");
+        ui.selectable_value(
+            &mut options.rigid_body,
+            Some(RigidBodyType::Dynamic),
+            "Dynamic",
+        );
+    });
+    if options.rigid_body.is_none() && options.colliders != ImportColliders::None {
+        ui.label("Meshes with colliders get a fixed body");
+    }
+}
+
+/// Formats a byte count with the largest fitting binary unit
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
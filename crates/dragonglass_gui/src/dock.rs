@@ -0,0 +1,156 @@
+use dragonglass_dependencies::{
+    anyhow::Result,
+    egui::{self, CtxRef, Ui},
+    serde::{Deserialize, Serialize},
+};
+
+/// The parts of the window that tabs can be docked in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub enum DockArea {
+    Left,
+    Right,
+    Bottom,
+    Center,
+}
+
+impl DockArea {
+    pub const ALL: [DockArea; 4] = [
+        DockArea::Left,
+        DockArea::Right,
+        DockArea::Bottom,
+        DockArea::Center,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Tabs docked around a central area.
+/// Each area shows one of its tabs at a time, and tabs move between areas from their context menu.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct Dock<T> {
+    /// Every tab, in the order shown in their area's tab bar
+    tabs: Vec<(T, DockArea)>,
+    /// The tab shown in each area, in the order of `DockArea::ALL`
+    active: [Option<T>; 4],
+}
+
+impl<T: Copy + PartialEq + ToString> Dock<T> {
+    pub fn new(tabs: Vec<(T, DockArea)>) -> Self {
+        let mut dock = Self {
+            tabs,
+            active: [None; 4],
+        };
+        for area in DockArea::ALL {
+            dock.active[area.index()] = dock.tabs_in(area).first().copied();
+        }
+        dock
+    }
+
+    pub fn tabs_in(&self, area: DockArea) -> Vec<T> {
+        self.tabs
+            .iter()
+            .filter(|(_, tab_area)| *tab_area == area)
+            .map(|(tab, _)| *tab)
+            .collect()
+    }
+
+    pub fn area_of(&self, tab: T) -> Option<DockArea> {
+        self.tabs
+            .iter()
+            .find(|(existing, _)| *existing == tab)
+            .map(|(_, area)| *area)
+    }
+
+    /// The tab an area shows, falling back to its first tab
+    pub fn active_in(&self, area: DockArea) -> Option<T> {
+        let tabs = self.tabs_in(area);
+        match self.active[area.index()] {
+            Some(tab) if tabs.contains(&tab) => Some(tab),
+            _ => tabs.first().copied(),
+        }
+    }
+
+    /// Shows a tab, bringing it to the front of its area
+    pub fn focus(&mut self, tab: T) {
+        if let Some(area) = self.area_of(tab) {
+            self.active[area.index()] = Some(tab);
+        }
+    }
+
+    /// Moves a tab to the end of another area's tab bar and shows it there
+    pub fn move_tab(&mut self, tab: T, area: DockArea) {
+        self.tabs.retain(|(existing, _)| *existing != tab);
+        self.tabs.push((tab, area));
+        self.active[area.index()] = Some(tab);
+    }
+
+    /// Lays out the docked areas in the space the gui has left, showing each area's active tab.
+    /// Side areas without tabs are hidden, while the center always fills what remains.
+    pub fn show(
+        &mut self,
+        ctx: &CtxRef,
+        mut show_tab: impl FnMut(&mut Ui, T) -> Result<()>,
+    ) -> Result<()> {
+        let mut result = Ok(());
+        let mut show_area = |dock: &mut Self, ui: &mut Ui, area: DockArea| {
+            dock.tab_bar(ui, area);
+            ui.separator();
+            if let Some(tab) = dock.active_in(area) {
+                if let Err(error) = show_tab(ui, tab) {
+                    result = Err(error);
+                }
+            }
+        };
+
+        if !self.tabs_in(DockArea::Left).is_empty() {
+            egui::SidePanel::left("dock_left")
+                .resizable(true)
+                .show(ctx, |ui| show_area(self, ui, DockArea::Left));
+        }
+        if !self.tabs_in(DockArea::Right).is_empty() {
+            egui::SidePanel::right("dock_right")
+                .resizable(true)
+                .show(ctx, |ui| show_area(self, ui, DockArea::Right));
+        }
+        if !self.tabs_in(DockArea::Bottom).is_empty() {
+            egui::TopBottomPanel::bottom("dock_bottom")
+                .resizable(true)
+                .show(ctx, |ui| show_area(self, ui, DockArea::Bottom));
+        }
+        egui::CentralPanel::default().show(ctx, |ui| show_area(self, ui, DockArea::Center));
+
+        result
+    }
+
+    fn tab_bar(&mut self, ui: &mut Ui, area: DockArea) {
+        let active = self.active_in(area);
+        let mut moved = None;
+        ui.horizontal(|ui| {
+            for tab in self.tabs_in(area).into_iter() {
+                let response = ui.selectable_label(active == Some(tab), tab.to_string());
+                if response.clicked() {
+                    self.active[area.index()] = Some(tab);
+                }
+                response.context_menu(|ui| {
+                    for destination in DockArea::ALL {
+                        if destination != area
+                            && ui.button(format!("Move to {:?}", destination)).clicked()
+                        {
+                            moved = Some((tab, destination));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+        });
+        if let Some((tab, destination)) = moved {
+            self.move_tab(tab, destination);
+            // The area falls back to its first remaining tab
+            self.active[area.index()] = None;
+        }
+    }
+}
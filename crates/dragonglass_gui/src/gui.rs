@@ -1,8 +1,12 @@
 use dragonglass_dependencies::{
-    egui::{epaint::ClippedShape, CtxRef, FontDefinitions},
+    egui::{epaint::ClippedShape, CtxRef, FontDefinitions, Order, Rect},
     egui_winit_platform::{Platform, PlatformDescriptor},
     epi,
-    winit::{dpi::PhysicalSize, event::Event, window::Window},
+    winit::{
+        dpi::PhysicalSize,
+        event::{ElementState, Event, WindowEvent},
+        window::Window,
+    },
 };
 use std::{sync::Arc, time::Instant};
 
@@ -23,6 +27,7 @@ pub struct Gui {
     start_time: Instant,
     last_frame_start: Instant,
     previous_frame_time: Option<f32>,
+    scene_region: Option<Rect>,
 }
 
 impl Gui {
@@ -41,13 +46,53 @@ impl Gui {
             start_time: Instant::now(),
             previous_frame_time: None,
             last_frame_start: Instant::now(),
+            scene_region: None,
         }
     }
 
     pub fn captures_event(&self, event: &Event<()>) -> bool {
+        if let Event::WindowEvent { event, .. } = event {
+            match event {
+                // A drag that began in the scene still ends if it is released over the gui
+                WindowEvent::MouseInput {
+                    state: ElementState::Released,
+                    ..
+                } => return false,
+                WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. }
+                    if self.pointer_over_scene() =>
+                {
+                    return false
+                }
+                _ => {}
+            }
+        }
         self.platform.captures_event(event)
     }
 
+    /// Sets a region of the window, in points, that shows the scene inside the gui.
+    /// Clicks and scrolling over it go to the app rather than the gui.
+    pub fn set_scene_region(&mut self, region: Option<Rect>) {
+        self.scene_region = region;
+    }
+
+    /// Whether the pointer is over the scene region and not over a window floating above it
+    pub fn pointer_over_scene(&self) -> bool {
+        let region = match self.scene_region {
+            Some(region) => region,
+            None => return false,
+        };
+        let context = self.context();
+        let position = match context.input().pointer.hover_pos() {
+            Some(position) => position,
+            None => return false,
+        };
+        region.contains(position)
+            && !context.is_using_pointer()
+            && context
+                .layer_id_at(position)
+                .map_or(true, |layer| layer.order == Order::Background)
+    }
+
     pub fn handle_event(&mut self, event: &Event<()>) {
         self.platform.handle_event(&event);
     }
@@ -0,0 +1,501 @@
+use dragonglass_dependencies::{
+    anyhow::Result,
+    egui::{self, DragValue, Response, Ui},
+    lazy_static::lazy_static,
+    nalgebra::UnitQuaternion,
+    nalgebra_glm as glm,
+    rapier3d::dynamics::RigidBodyType,
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::{self, Number, Value},
+};
+use dragonglass_world::{
+    Camera, Light, LightKind, Name, OrthographicCamera, PerspectiveCamera, Projection,
+    RigidBodySettings, Transform, GIZMO_MIN_SCALE,
+};
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    sync::{Arc, RwLock},
+};
+
+/// Whether an inspector widget changed its value this frame,
+/// and whether the edit is complete, such as when a drag is released
+#[derive(Default, Debug, Copy, Clone)]
+pub struct InspectResponse {
+    pub changed: bool,
+    pub finished: bool,
+}
+
+impl InspectResponse {
+    pub fn merge(&mut self, other: InspectResponse) {
+        self.changed |= other.changed;
+        self.finished |= other.finished;
+    }
+}
+
+impl From<Response> for InspectResponse {
+    fn from(response: Response) -> Self {
+        Self {
+            changed: response.changed(),
+            finished: response.drag_released()
+                || response.lost_focus()
+                || (response.changed() && !response.dragged() && !response.has_focus()),
+        }
+    }
+}
+
+/// Editable widgets for a component shown in the editor's inspector.
+/// Components registered with `register_component` can supply their own widgets
+/// by implementing this trait and calling `register_inspector` with the same key.
+pub trait Inspect {
+    fn inspect(&mut self, ui: &mut Ui) -> InspectResponse;
+}
+
+/// Edits the json value of a registered component
+#[derive(Copy, Clone)]
+pub struct ComponentInspector {
+    pub inspect: fn(&mut Value, &mut Ui) -> Result<InspectResponse>,
+}
+
+impl ComponentInspector {
+    pub fn of<T: Inspect + Serialize + DeserializeOwned>() -> Self {
+        Self {
+            inspect: inspect_component_value::<T>,
+        }
+    }
+}
+
+fn inspect_component_value<T: Inspect + Serialize + DeserializeOwned>(
+    value: &mut Value,
+    ui: &mut Ui,
+) -> Result<InspectResponse> {
+    let mut component = serde_json::from_value::<T>(value.clone())?;
+    let response = component.inspect(ui);
+    if response.changed {
+        *value = serde_json::to_value(&component)?;
+    }
+    Ok(response)
+}
+
+lazy_static! {
+    pub static ref COMPONENT_INSPECTORS: Arc<RwLock<HashMap<String, ComponentInspector>>> = {
+        let mut inspectors = HashMap::new();
+        inspectors.insert("name".to_string(), ComponentInspector::of::<Name>());
+        inspectors.insert(
+            "transform".to_string(),
+            ComponentInspector::of::<Transform>(),
+        );
+        inspectors.insert("camera".to_string(), ComponentInspector::of::<Camera>());
+        inspectors.insert("light".to_string(), ComponentInspector::of::<Light>());
+        Arc::new(RwLock::new(inspectors))
+    };
+}
+
+/// Uses the component's own widgets for the component registered with the key
+pub fn register_inspector<T: Inspect + Serialize + DeserializeOwned>(key: &str) {
+    COMPONENT_INSPECTORS
+        .write()
+        .expect("Failed to access component inspectors!")
+        .insert(key.to_string(), ComponentInspector::of::<T>());
+}
+
+/// Shows widgets for a component's json value, using its registered inspector if it has one
+pub fn inspect_component(key: &str, value: &mut Value, ui: &mut Ui) -> Result<InspectResponse> {
+    let inspector = COMPONENT_INSPECTORS
+        .read()
+        .expect("Failed to access component inspectors!")
+        .get(key)
+        .copied();
+    match inspector {
+        Some(inspector) => (inspector.inspect)(value, ui),
+        None => Ok(inspect_value(value, ui)),
+    }
+}
+
+/// Shows generic widgets for each field of a json value
+pub fn inspect_value(value: &mut Value, ui: &mut Ui) -> InspectResponse {
+    let mut response = InspectResponse::default();
+    let mut replacement = None;
+    match value {
+        Value::Null => {
+            ui.label("None");
+        }
+        Value::Bool(boolean) => response.merge(ui.checkbox(boolean, "").into()),
+        Value::Number(number) => {
+            let mut float = number.as_f64().unwrap_or_default();
+            let drag_response = ui.add(DragValue::new(&mut float).speed(0.1));
+            if drag_response.changed() {
+                replacement = Some(if number.is_u64() {
+                    Value::from(float.max(0.0).round() as u64)
+                } else if number.is_i64() {
+                    Value::from(float.round() as i64)
+                } else {
+                    Number::from_f64(float)
+                        .map(Value::Number)
+                        .unwrap_or(Value::Null)
+                });
+            }
+            response.merge(drag_response.into());
+        }
+        Value::String(string) => response.merge(ui.text_edit_singleline(string).into()),
+        Value::Array(items) if items.iter().all(Value::is_number) => {
+            ui.horizontal(|ui| {
+                for item in items.iter_mut() {
+                    response.merge(inspect_value(item, ui));
+                }
+            });
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                inspect_field(&index.to_string(), item, ui, &mut response);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                inspect_field(key, field, ui, &mut response);
+            }
+        }
+    }
+    if let Some(replacement) = replacement {
+        *value = replacement;
+    }
+    response
+}
+
+fn inspect_field(label: &str, value: &mut Value, ui: &mut Ui, response: &mut InspectResponse) {
+    let nested = match value {
+        Value::Object(fields) => !fields.is_empty(),
+        Value::Array(items) => !items.iter().all(Value::is_number),
+        _ => false,
+    };
+    if nested {
+        egui::CollapsingHeader::new(label).show(ui, |ui| {
+            response.merge(inspect_value(value, ui));
+        });
+    } else {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            response.merge(inspect_value(value, ui));
+        });
+    }
+}
+
+/// Any value can be entered
+const UNBOUNDED: RangeInclusive<f32> = f32::NEG_INFINITY..=f32::INFINITY;
+
+fn inspect_vec3(
+    label: &str,
+    vector: &mut glm::Vec3,
+    speed: f32,
+    range: RangeInclusive<f32>,
+    ui: &mut Ui,
+) -> InspectResponse {
+    let mut response = InspectResponse::default();
+    ui.horizontal(|ui| {
+        ui.label(label);
+        for (component, prefix) in vector.iter_mut().zip(["X: ", "Y: ", "Z: "]) {
+            response.merge(
+                ui.add(
+                    DragValue::new(component)
+                        .speed(speed)
+                        .prefix(prefix)
+                        .clamp_range(range.clone()),
+                )
+                .into(),
+            );
+        }
+    });
+    response
+}
+
+impl Inspect for Name {
+    fn inspect(&mut self, ui: &mut Ui) -> InspectResponse {
+        ui.text_edit_singleline(&mut self.0).into()
+    }
+}
+
+impl Inspect for Transform {
+    fn inspect(&mut self, ui: &mut Ui) -> InspectResponse {
+        let mut response = inspect_vec3("Translation", &mut self.translation, 0.1, UNBOUNDED, ui);
+
+        // Rotations are edited as euler angles in degrees.
+        // Converting the rotation back to angles can give a different but equivalent set of angles,
+        // which would make a drag jump, so the edited angles are kept while they match the rotation.
+        let id = ui.make_persistent_id("transform_rotation");
+        let stored = ui.memory().data.get_temp::<(glm::Quat, glm::Vec3)>(id);
+        let mut angles = match stored {
+            Some((rotation, angles)) if rotation == self.rotation => angles,
+            _ => {
+                let (roll, pitch, yaw) =
+                    UnitQuaternion::from_quaternion(self.rotation).euler_angles();
+                glm::vec3(roll, pitch, yaw).map(f32::to_degrees)
+            }
+        };
+        let rotation_response = inspect_vec3("Rotation", &mut angles, 1.0, UNBOUNDED, ui);
+        if rotation_response.changed {
+            let radians = angles.map(f32::to_radians);
+            self.rotation =
+                UnitQuaternion::from_euler_angles(radians.x, radians.y, radians.z).into_inner();
+        }
+        ui.memory().data.insert_temp(id, (self.rotation, angles));
+        response.merge(rotation_response);
+
+        response.merge(inspect_vec3(
+            "Scale",
+            &mut self.scale,
+            0.01,
+            GIZMO_MIN_SCALE..=f32::INFINITY,
+            ui,
+        ));
+        response
+    }
+}
+
+impl Inspect for Light {
+    fn inspect(&mut self, ui: &mut Ui) -> InspectResponse {
+        let mut response = InspectResponse::default();
+        ui.horizontal(|ui| {
+            ui.label("Color");
+            let mut color = [self.color.x, self.color.y, self.color.z];
+            let color_response = ui.color_edit_button_rgb(&mut color);
+            if color_response.changed() {
+                self.color = glm::Vec3::from(color);
+            }
+            response.merge(color_response.into());
+        });
+        ui.horizontal(|ui| {
+            ui.label("Intensity");
+            response.merge(
+                ui.add(
+                    DragValue::new(&mut self.intensity)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f32::MAX),
+                )
+                .into(),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Range");
+            response.merge(
+                ui.add(
+                    DragValue::new(&mut self.range)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f32::MAX),
+                )
+                .into(),
+            );
+        });
+
+        let kind_name = match self.kind {
+            LightKind::Directional => "Directional",
+            LightKind::Point => "Point",
+            LightKind::Spot { .. } => "Spot",
+        };
+        let mut selected_kind = None;
+        egui::ComboBox::from_label("Kind")
+            .selected_text(kind_name)
+            .show_ui(ui, |ui| {
+                for (name, kind) in [
+                    ("Directional", LightKind::Directional),
+                    ("Point", LightKind::Point),
+                    (
+                        "Spot",
+                        LightKind::Spot {
+                            inner_cone_angle: 0.0,
+                            outer_cone_angle: std::f32::consts::FRAC_PI_4,
+                        },
+                    ),
+                ] {
+                    if ui.selectable_label(name == kind_name, name).clicked() && name != kind_name {
+                        selected_kind = Some(kind);
+                    }
+                }
+            });
+        if let Some(kind) = selected_kind {
+            self.kind = kind;
+            response.merge(InspectResponse {
+                changed: true,
+                finished: true,
+            });
+        }
+
+        if let LightKind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } = &mut self.kind
+        {
+            for (label, angle) in [
+                ("Inner Cone", inner_cone_angle),
+                ("Outer Cone", outer_cone_angle),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    let mut degrees = angle.to_degrees();
+                    let angle_response = ui.add(
+                        DragValue::new(&mut degrees)
+                            .speed(0.5)
+                            .clamp_range(0.0..=90.0)
+                            .suffix("°"),
+                    );
+                    if angle_response.changed() {
+                        *angle = degrees.to_radians();
+                    }
+                    response.merge(angle_response.into());
+                });
+            }
+        }
+
+        response
+    }
+}
+
+impl Inspect for Camera {
+    fn inspect(&mut self, ui: &mut Ui) -> InspectResponse {
+        let mut response = InspectResponse::default();
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            response.merge(ui.text_edit_singleline(&mut self.name).into());
+        });
+        response.merge(ui.checkbox(&mut self.enabled, "Enabled").into());
+
+        let orthographic = self.is_orthographic();
+        ui.horizontal(|ui| {
+            if ui.selectable_label(!orthographic, "Perspective").clicked() && orthographic {
+                self.projection = Projection::Perspective(PerspectiveCamera {
+                    aspect_ratio: None,
+                    y_fov_rad: 70_f32.to_radians(),
+                    z_far: None,
+                    z_near: 0.1,
+                });
+                response.merge(InspectResponse {
+                    changed: true,
+                    finished: true,
+                });
+            }
+            if ui.selectable_label(orthographic, "Orthographic").clicked() && !orthographic {
+                self.projection = Projection::Orthographic(OrthographicCamera {
+                    x_mag: 10.0,
+                    y_mag: 10.0,
+                    z_far: 1000.0,
+                    z_near: 0.01,
+                });
+                response.merge(InspectResponse {
+                    changed: true,
+                    finished: true,
+                });
+            }
+        });
+
+        match &mut self.projection {
+            Projection::Perspective(camera) => {
+                ui.horizontal(|ui| {
+                    ui.label("Field of View");
+                    let mut degrees = camera.y_fov_rad.to_degrees();
+                    let fov_response = ui.add(
+                        DragValue::new(&mut degrees)
+                            .speed(0.5)
+                            .clamp_range(1.0..=179.0)
+                            .suffix("°"),
+                    );
+                    if fov_response.changed() {
+                        camera.y_fov_rad = degrees.to_radians();
+                    }
+                    response.merge(fov_response.into());
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Near");
+                    response.merge(
+                        ui.add(
+                            DragValue::new(&mut camera.z_near)
+                                .speed(0.01)
+                                .clamp_range(0.001..=f32::MAX),
+                        )
+                        .into(),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    let mut finite = camera.z_far.is_some();
+                    let checkbox_response = ui.checkbox(&mut finite, "Far");
+                    if checkbox_response.changed() {
+                        camera.z_far = finite.then(|| 1000.0);
+                    }
+                    response.merge(checkbox_response.into());
+                    if let Some(z_far) = camera.z_far.as_mut() {
+                        response.merge(ui.add(DragValue::new(z_far).speed(1.0)).into());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut fixed = camera.aspect_ratio.is_some();
+                    let checkbox_response = ui.checkbox(&mut fixed, "Aspect Ratio");
+                    if checkbox_response.changed() {
+                        camera.aspect_ratio = fixed.then(|| 16.0 / 9.0);
+                    }
+                    response.merge(checkbox_response.into());
+                    if let Some(aspect_ratio) = camera.aspect_ratio.as_mut() {
+                        response.merge(
+                            ui.add(
+                                DragValue::new(aspect_ratio)
+                                    .speed(0.01)
+                                    .clamp_range(0.01..=f32::MAX),
+                            )
+                            .into(),
+                        );
+                    }
+                });
+            }
+            Projection::Orthographic(camera) => {
+                for (label, value) in [
+                    ("Width", &mut camera.x_mag),
+                    ("Height", &mut camera.y_mag),
+                    ("Near", &mut camera.z_near),
+                    ("Far", &mut camera.z_far),
+                ] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        response.merge(ui.add(DragValue::new(value).speed(0.1)).into());
+                    });
+                }
+            }
+        }
+
+        response
+    }
+}
+
+impl Inspect for RigidBodySettings {
+    fn inspect(&mut self, ui: &mut Ui) -> InspectResponse {
+        let mut response = InspectResponse::default();
+        ui.horizontal(|ui| {
+            for (name, body_type) in [
+                ("Dynamic", RigidBodyType::Dynamic),
+                ("Fixed", RigidBodyType::Fixed),
+                ("Kinematic Position", RigidBodyType::KinematicPositionBased),
+                ("Kinematic Velocity", RigidBodyType::KinematicVelocityBased),
+            ] {
+                if ui
+                    .selectable_label(self.body_type == body_type, name)
+                    .clicked()
+                    && self.body_type != body_type
+                {
+                    self.body_type = body_type;
+                    response.merge(InspectResponse {
+                        changed: true,
+                        finished: true,
+                    });
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Mass");
+            response.merge(
+                ui.add(
+                    DragValue::new(&mut self.mass)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f32::MAX),
+                )
+                .into(),
+            );
+        });
+        response
+    }
+}
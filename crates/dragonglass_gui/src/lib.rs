@@ -1,3 +1,7 @@
+mod assets;
+mod dock;
 mod gui;
+mod inspector;
+mod material;
 
-pub use self::gui::*;
+pub use self::{assets::*, dock::*, gui::*, inspector::*, material::*};
@@ -0,0 +1,166 @@
+use crate::InspectResponse;
+use dragonglass_dependencies::{
+    egui::{self, DragValue, Slider, Ui},
+    nalgebra_glm as glm,
+};
+use dragonglass_world::{AlphaMode, Material, Texture};
+
+/// Shows widgets for a material's factors, alpha settings, and texture slots.
+/// Texture slots can point at any of the given textures.
+pub fn material_editor(
+    ui: &mut Ui,
+    material: &mut Material,
+    textures: &[Texture],
+) -> InspectResponse {
+    let mut response = InspectResponse::default();
+
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        response.merge(ui.text_edit_singleline(&mut material.name).into());
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Base Color");
+        let mut color: [f32; 4] = material.base_color_factor.into();
+        let color_response = ui.color_edit_button_rgba_unmultiplied(&mut color);
+        if color_response.changed() {
+            material.base_color_factor = glm::Vec4::from(color);
+        }
+        response.merge(color_response.into());
+    });
+
+    for (label, value) in [
+        ("Metallic", &mut material.metallic_factor),
+        ("Roughness", &mut material.roughness_factor),
+    ] {
+        response.merge(ui.add(Slider::new(value, 0.0..=1.0).text(label)).into());
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Emissive");
+        let mut color: [f32; 3] = material.emissive_factor.into();
+        let color_response = ui.color_edit_button_rgb(&mut color);
+        if color_response.changed() {
+            material.emissive_factor = glm::Vec3::from(color);
+        }
+        response.merge(color_response.into());
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Alpha");
+        for (name, alpha_mode) in [
+            ("Opaque", AlphaMode::Opaque),
+            ("Mask", AlphaMode::Mask),
+            ("Blend", AlphaMode::Blend),
+        ] {
+            if ui
+                .selectable_label(material.alpha_mode == alpha_mode, name)
+                .clicked()
+                && material.alpha_mode != alpha_mode
+            {
+                material.alpha_mode = alpha_mode;
+                response.merge(InspectResponse {
+                    changed: true,
+                    finished: true,
+                });
+            }
+        }
+    });
+    ui.add_enabled_ui(material.alpha_mode == AlphaMode::Mask, |ui| {
+        response.merge(
+            ui.add(Slider::new(&mut material.alpha_cutoff, 0.0..=1.0).text("Alpha Cutoff"))
+                .into(),
+        );
+    });
+
+    response.merge(ui.checkbox(&mut material.is_unlit, "Unlit").into());
+
+    ui.horizontal(|ui| {
+        ui.label("Normal Scale");
+        response.merge(
+            ui.add(DragValue::new(&mut material.normal_texture_scale).speed(0.01))
+                .into(),
+        );
+    });
+    response.merge(
+        ui.add(Slider::new(&mut material.occlusion_strength, 0.0..=1.0).text("Occlusion"))
+            .into(),
+    );
+
+    ui.separator();
+    ui.label("Textures");
+    for (label, index, set) in [
+        (
+            "Color",
+            &mut material.color_texture_index,
+            &mut material.color_texture_set,
+        ),
+        (
+            "Metallic Roughness",
+            &mut material.metallic_roughness_texture_index,
+            &mut material.metallic_roughness_texture_set,
+        ),
+        (
+            "Normal",
+            &mut material.normal_texture_index,
+            &mut material.normal_texture_set,
+        ),
+        (
+            "Occlusion",
+            &mut material.occlusion_texture_index,
+            &mut material.occlusion_texture_set,
+        ),
+        (
+            "Emissive",
+            &mut material.emissive_texture_index,
+            &mut material.emissive_texture_set,
+        ),
+    ] {
+        response.merge(texture_slot(ui, label, index, set, textures));
+    }
+
+    response
+}
+
+fn texture_label(index: i32, textures: &[Texture]) -> String {
+    match usize::try_from(index)
+        .ok()
+        .and_then(|index| textures.get(index))
+    {
+        Some(texture) => format!("Texture {} ({}x{})", index, texture.width, texture.height),
+        None => "None".to_string(),
+    }
+}
+
+fn texture_slot(
+    ui: &mut Ui,
+    label: &str,
+    index: &mut i32,
+    set: &mut i32,
+    textures: &[Texture],
+) -> InspectResponse {
+    let mut selected = *index;
+    egui::ComboBox::from_label(label)
+        .selected_text(texture_label(*index, textures))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, -1, "None");
+            for texture_index in 0..textures.len() as i32 {
+                ui.selectable_value(
+                    &mut selected,
+                    texture_index,
+                    texture_label(texture_index, textures),
+                );
+            }
+        });
+
+    if selected == *index {
+        return InspectResponse::default();
+    }
+    *index = selected;
+    // Textures sample the first uv set unless the material already picked one
+    *set = if selected < 0 { -1 } else { (*set).max(0) };
+    InspectResponse {
+        changed: true,
+        finished: true,
+    }
+}
@@ -3,8 +3,8 @@ use std::mem;
 
 pub struct GeometryBuffer {
     vao: u32,
-    _vbo: u32,
-    _ebo: Option<u32>,
+    vbo: u32,
+    ebo: Option<u32>,
 }
 
 impl GeometryBuffer {
@@ -23,11 +23,7 @@ impl GeometryBuffer {
 
         Self::add_vertex_attributes::<T>(vertex_attributes);
 
-        Self {
-            vao,
-            _vbo: vbo,
-            _ebo: ebo,
-        }
+        Self { vao, vbo, ebo }
     }
 
     fn create_vao() -> u32 {
@@ -81,3 +77,15 @@ impl GeometryBuffer {
         }
     }
 }
+
+impl Drop for GeometryBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+            if let Some(ebo) = self.ebo.as_ref() {
+                gl::DeleteBuffers(1, ebo);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::Texture;
+use dragonglass_dependencies::{
+    anyhow::{bail, Result},
+    gl,
+};
+use std::ptr;
+
+/// An offscreen render target, with a color texture that can be sampled once rendering is done
+/// and a combined depth and stencil buffer
+pub struct Framebuffer {
+    id: u32,
+    color_texture: Texture,
+    depth_stencil: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let (width, height) = (width.max(1), height.max(1));
+
+        let mut id = 0;
+        let mut depth_stencil = 0;
+        let color_texture = Texture::new();
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+
+            // Colors are stored in srgb, matching what is written to the window
+            color_texture.bind(0);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::SRGB8_ALPHA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                color_texture.id(),
+                0,
+            );
+
+            gl::GenRenderbuffers(1, &mut depth_stencil);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth_stencil);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH24_STENCIL8,
+                width as i32,
+                height as i32,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth_stencil,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+
+        let framebuffer = Self {
+            id,
+            color_texture,
+            depth_stencil,
+            width,
+            height,
+        };
+
+        let status = unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        Self::bind_default();
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            bail!("Framebuffer is incomplete, status: {:#x}", status);
+        }
+
+        Ok(framebuffer)
+    }
+
+    /// Directs rendering into this framebuffer, covering all of it
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, self.width as _, self.height as _);
+        }
+    }
+
+    /// Directs rendering back to the window
+    pub fn bind_default() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    pub fn color_texture(&self) -> &Texture {
+        &self.color_texture
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.depth_stencil);
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
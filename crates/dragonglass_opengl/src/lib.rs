@@ -1,5 +1,6 @@
 mod buffer;
+mod framebuffer;
 mod shader;
 mod texture;
 
-pub use self::{buffer::*, shader::*, texture::*};
+pub use self::{buffer::*, framebuffer::*, shader::*, texture::*};
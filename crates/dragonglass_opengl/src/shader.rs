@@ -219,6 +219,14 @@ impl ShaderProgram {
         }
     }
 
+    pub fn set_uniform_vec2(&self, name: &str, data: &[GLfloat]) {
+        self.use_program();
+        let location = self.uniform_location(name);
+        unsafe {
+            gl::Uniform2fv(location, 1, data.as_ptr());
+        }
+    }
+
     fn attach_shader_file(&mut self, kind: ShaderKind, path: &str) -> Result<&mut Self> {
         let mut shader = Shader::new(kind);
         shader.load_file(path)?;
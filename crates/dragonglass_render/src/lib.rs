@@ -1,4 +1,6 @@
 mod opengl;
 mod renderer;
 
-pub use crate::renderer::{create_render_backend, Backend, Renderer};
+pub use crate::renderer::{
+    create_render_backend, Backend, GridSettings, MaterialPreview, Renderer,
+};
@@ -1,5 +1,9 @@
+mod debug;
 mod device;
+mod grid;
+mod outline;
 mod pbr;
+mod preview;
 mod world;
 
 pub use self::device::OpenGLRenderDevice;
@@ -0,0 +1,160 @@
+use dragonglass_dependencies::{
+    anyhow::Result,
+    gl::{self, types::GLvoid},
+    nalgebra_glm as glm,
+};
+use dragonglass_opengl::ShaderProgram;
+use dragonglass_world::{DebugLine, World};
+use std::mem;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct DebugVertex {
+    position: glm::Vec3,
+    color: glm::Vec3,
+}
+
+/// Draws debug lines, such as physics debug geometry and gizmos, on top of the scene
+pub struct DebugRender {
+    vao: u32,
+    vbo: u32,
+    shader_program: ShaderProgram,
+}
+
+impl DebugRender {
+    const VERTEX_SHADER_SOURCE: &'static str = r#"
+#version 450 core
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec3 inColor;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec3 Color;
+
+void main()
+{
+   gl_Position = projection * view * vec4(inPosition, 1.0);
+   Color = inColor;
+}
+"#;
+
+    const FRAGMENT_SHADER_SOURCE: &'static str = r#"
+#version 450 core
+
+in vec3 Color;
+
+out vec4 color;
+
+void main(void)
+{
+    color = vec4(Color, 1.0);
+}
+"#;
+
+    pub fn new() -> Result<Self> {
+        let mut shader_program = ShaderProgram::new();
+        shader_program
+            .vertex_shader_source(Self::VERTEX_SHADER_SOURCE)?
+            .fragment_shader_source(Self::FRAGMENT_SHADER_SOURCE)?
+            .link();
+
+        let (mut vao, mut vbo) = (0, 0);
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let stride = mem::size_of::<DebugVertex>() as i32;
+            for (index, offset) in [0_usize, 3].iter().enumerate() {
+                gl::EnableVertexAttribArray(index as _);
+                gl::VertexAttribPointer(
+                    index as _,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    (offset * mem::size_of::<f32>()) as *const GLvoid,
+                );
+            }
+            gl::BindVertexArray(0);
+        }
+
+        Ok(Self {
+            vao,
+            vbo,
+            shader_program,
+        })
+    }
+
+    pub fn render(&self, world: &World, aspect_ratio: f32, lines: &[DebugLine]) -> Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let (projection, view) = world.active_camera_matrices(aspect_ratio)?;
+        self.render_with_matrices(&projection, &view, lines);
+        Ok(())
+    }
+
+    /// Draws lines with a projection and view other than the active camera's
+    pub fn render_with_matrices(
+        &self,
+        projection: &glm::Mat4,
+        view: &glm::Mat4,
+        lines: &[DebugLine],
+    ) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let vertices = lines
+            .iter()
+            .flat_map(|line| {
+                [
+                    DebugVertex {
+                        position: line.start,
+                        color: line.color,
+                    },
+                    DebugVertex {
+                        position: line.end,
+                        color: line.color,
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        self.shader_program.use_program();
+        self.shader_program
+            .set_uniform_matrix4x4("projection", projection.as_slice());
+        self.shader_program
+            .set_uniform_matrix4x4("view", view.as_slice());
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.len() * mem::size_of::<DebugVertex>()) as _,
+                vertices.as_ptr() as *const _,
+                gl::DYNAMIC_DRAW,
+            );
+
+            // Debug geometry is drawn over the scene so it is never hidden by meshes
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+            gl::DrawArrays(gl::LINES, 0, vertices.len() as _);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for DebugRender {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
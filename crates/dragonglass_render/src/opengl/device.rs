@@ -1,18 +1,50 @@
-use crate::{opengl::world::WorldRender, Renderer};
+use crate::{
+    opengl::{
+        debug::DebugRender, grid::GridRender, outline::OutlineRender,
+        preview::MaterialPreviewRender, world::WorldRender,
+    },
+    GridSettings, MaterialPreview, Renderer,
+};
 use dragonglass_dependencies::{
     anyhow::Result,
-    egui::{epaint::ClippedShape, CtxRef},
-    egui_glow, gl, glow,
+    egui::{epaint::ClippedShape, CtxRef, TextureId},
+    egui_glow,
+    epi::NativeTexture,
+    gl, glow,
     glutin::{window::Window, ContextWrapper, PossiblyCurrent},
+    nalgebra_glm as glm,
     winit::dpi::PhysicalSize,
 };
-use dragonglass_world::{Viewport, World};
+use dragonglass_opengl::Framebuffer;
+use dragonglass_world::{
+    axis_gizmo_lines, DebugLine, Entity, PhysicsDebugSettings, Viewport, World,
+};
+
+/// An offscreen target the gui shows as an image, such as the scene or the material preview
+struct OffscreenTarget {
+    framebuffer: Framebuffer,
+    texture_id: TextureId,
+}
 
 pub struct OpenGLRenderDevice {
-    world_render: Option<WorldRender>,
+    world_render: WorldRender,
+    debug_render: DebugRender,
+    physics_debug: PhysicsDebugSettings,
+    overlay_lines: Vec<DebugLine>,
+    grid_render: GridRender,
+    grid: GridSettings,
+    axis_gizmo: Option<Viewport>,
+    outline_render: OutlineRender,
+    selected_entities: Vec<Entity>,
+    preview_render: MaterialPreviewRender,
+    material_preview: Option<MaterialPreview>,
+    preview_target: Option<OffscreenTarget>,
     glow: glow::Context,
     egui_glow: egui_glow::EguiGlow,
     viewport: Viewport,
+    window_size: PhysicalSize<u32>,
+    offscreen: bool,
+    scene_target: Option<OffscreenTarget>,
 }
 
 impl OpenGLRenderDevice {
@@ -26,7 +58,18 @@ impl OpenGLRenderDevice {
         };
         let egui_glow = egui_glow::EguiGlow::new(context, &glow_context);
         Ok(Self {
-            world_render: None,
+            world_render: WorldRender::new()?,
+            debug_render: DebugRender::new()?,
+            physics_debug: PhysicsDebugSettings::default(),
+            overlay_lines: Vec::new(),
+            grid_render: GridRender::new()?,
+            grid: GridSettings::default(),
+            axis_gizmo: None,
+            outline_render: OutlineRender::new()?,
+            selected_entities: Vec::new(),
+            preview_render: MaterialPreviewRender::new()?,
+            material_preview: None,
+            preview_target: None,
             glow: glow_context,
             egui_glow,
             viewport: Viewport {
@@ -35,9 +78,88 @@ impl OpenGLRenderDevice {
                 width: dimensions.width as _,
                 height: dimensions.height as _,
             },
+            window_size: dimensions,
+            offscreen: false,
+            scene_target: None,
         })
     }
 
+    fn rendering_offscreen(&self) -> bool {
+        self.offscreen && self.scene_target.is_some()
+    }
+
+    /// Directs rendering to the scene's offscreen target, or to its region of the window
+    fn bind_scene_target(&self) {
+        match self.scene_target.as_ref().filter(|_| self.offscreen) {
+            Some(target) => target.framebuffer.bind(),
+            None => {
+                Framebuffer::bind_default();
+                let viewport = &self.viewport;
+                let y = self.window_size.height as f32 - viewport.y - viewport.height;
+                unsafe {
+                    gl::Viewport(
+                        viewport.x as _,
+                        y as _,
+                        viewport.width as _,
+                        viewport.height as _,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Keeps an offscreen target at the given size, recreating its framebuffer when it changes.
+    /// The framebuffer owns its color texture, so the gui only borrows it by its GL name
+    /// and a recreated target is shown under a new texture id.
+    fn resize_target(
+        &mut self,
+        target: Option<OffscreenTarget>,
+        width: u32,
+        height: u32,
+    ) -> Result<OffscreenTarget> {
+        if let Some(target) = target {
+            if target.framebuffer.width() == width && target.framebuffer.height() == height {
+                return Ok(target);
+            }
+            self.release_target(target);
+        }
+        let framebuffer = Framebuffer::new(width, height)?;
+        let texture =
+            unsafe { glow::Context::create_texture_from_gl_name(framebuffer.color_texture().id()) };
+        let texture_id = self.egui_glow.painter.register_native_texture(texture);
+        Ok(OffscreenTarget {
+            framebuffer,
+            texture_id,
+        })
+    }
+
+    /// Forgets the target's texture in the gui without deleting it,
+    /// leaving that to the framebuffer that owns it
+    fn release_target(&mut self, target: OffscreenTarget) {
+        if let TextureId::User(id) = target.texture_id {
+            self.egui_glow.painter.free_texture(id);
+        }
+    }
+
+    /// Converts a region given in points from the top left of the viewport
+    /// into pixels from the bottom left of the scene's render target, as x, y, width, and height
+    fn target_region(&self, region: &Viewport, pixels_per_point: f32) -> [i32; 4] {
+        let (origin_x, origin_y, target_height) = if self.rendering_offscreen() {
+            (0.0, 0.0, self.viewport.height)
+        } else {
+            (
+                self.viewport.x,
+                self.viewport.y,
+                self.window_size.height as f32,
+            )
+        };
+        let width = region.width * pixels_per_point;
+        let height = region.height * pixels_per_point;
+        let x = origin_x + region.x * pixels_per_point;
+        let y = target_height - (origin_y + region.y * pixels_per_point) - height;
+        [x as i32, y as i32, width as i32, height as i32]
+    }
+
     pub fn render_gui(
         &mut self,
         context: &ContextWrapper<PossiblyCurrent, Window>,
@@ -57,10 +179,44 @@ impl OpenGLRenderDevice {
             clipped_meshes,
         );
     }
+
+    /// Draws the world axes as the active camera sees them into their region of the viewport.
+    /// The region is given in points, so it is scaled to pixels first.
+    fn render_axis_gizmo(
+        &self,
+        world: &World,
+        region: &Viewport,
+        pixels_per_point: f32,
+    ) -> Result<()> {
+        let [x, y, width, height] = self.target_region(region, pixels_per_point);
+        if width <= 0 || height <= 0 {
+            return Ok(());
+        }
+
+        // Only the camera's rotation matters, and the axes keep their length in either direction
+        let (_, view) = world.active_camera_matrices(1.0)?;
+        let rotation = glm::mat3_to_mat4(&glm::mat4_to_mat3(&view));
+        let size = width.min(height) as f32;
+        let (extent_x, extent_y) = (width as f32 / size, height as f32 / size);
+        let projection = glm::ortho(-extent_x, extent_x, -extent_y, extent_y, -1.0, 1.0);
+
+        unsafe {
+            gl::Viewport(x, y, width, height);
+        }
+        self.debug_render
+            .render_with_matrices(&projection, &rotation, &axis_gizmo_lines());
+        Ok(())
+    }
 }
 
 impl Renderer for OpenGLRenderDevice {
     fn cleanup(&mut self) {
+        for target in [self.scene_target.take(), self.preview_target.take()]
+            .into_iter()
+            .flatten()
+        {
+            self.release_target(target);
+        }
         self.egui_glow.painter.destroy(&self.glow);
     }
 
@@ -71,9 +227,10 @@ impl Renderer for OpenGLRenderDevice {
         gui_context: &CtxRef,
         clipped_shapes: Vec<ClippedShape>,
     ) -> Result<()> {
+        self.bind_scene_target();
         unsafe {
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
             gl::ClearColor(0.3, 0.3, 0.3, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
 
             gl::Enable(gl::CULL_FACE);
             gl::CullFace(gl::BACK);
@@ -86,8 +243,62 @@ impl Renderer for OpenGLRenderDevice {
         let aspect_ratio =
             self.viewport.width as f32 / std::cmp::max(self.viewport.height as u32, 1) as f32;
 
-        if let Some(world_render) = self.world_render.as_ref() {
-            world_render.render(world, aspect_ratio)?;
+        // Only assets that changed since the last frame are uploaded
+        self.world_render.sync(world);
+        self.world_render.render(world, aspect_ratio)?;
+
+        if self.grid.enabled {
+            self.grid_render.render(world, aspect_ratio, &self.grid)?;
+        }
+
+        if !self.selected_entities.is_empty() {
+            self.outline_render.render(
+                world,
+                &self.world_render,
+                &self.viewport,
+                &self.selected_entities,
+            )?;
+        }
+
+        if self.physics_debug.enabled {
+            let lines = world.physics.debug_lines(&self.physics_debug);
+            self.debug_render.render(world, aspect_ratio, &lines)?;
+        }
+
+        self.debug_render
+            .render(world, aspect_ratio, &self.overlay_lines)?;
+
+        if let Some(region) = self.axis_gizmo.as_ref() {
+            self.render_axis_gizmo(world, region, gui_context.pixels_per_point())?;
+            self.bind_scene_target();
+        }
+
+        // The preview is drawn before the gui, which shows it as an image
+        if let (Some(preview), Some(target)) =
+            (self.material_preview.as_ref(), self.preview_target.as_ref())
+        {
+            self.preview_render.render(
+                world,
+                preview,
+                &self.world_render.textures,
+                &target.framebuffer,
+            )?;
+            self.bind_scene_target();
+        }
+
+        // The gui is drawn over the whole window, showing the offscreen scene as an image
+        if self.rendering_offscreen() {
+            Framebuffer::bind_default();
+            unsafe {
+                gl::Viewport(
+                    0,
+                    0,
+                    self.window_size.width as _,
+                    self.window_size.height as _,
+                );
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+            }
         }
 
         if !clipped_shapes.is_empty() {
@@ -100,7 +311,7 @@ impl Renderer for OpenGLRenderDevice {
     }
 
     fn load_world(&mut self, world: &World) -> Result<()> {
-        self.world_render = Some(WorldRender::new(world)?);
+        self.world_render.sync(world);
         Ok(())
     }
 
@@ -109,15 +320,72 @@ impl Renderer for OpenGLRenderDevice {
     }
 
     fn set_viewport(&mut self, viewport: Viewport) {
-        unsafe {
-            gl::Viewport(
-                viewport.x as _,
-                viewport.y as _,
-                viewport.width as _,
-                viewport.height as _,
-            );
-        }
         self.viewport = viewport;
+        if !self.offscreen {
+            self.bind_scene_target();
+        }
+    }
+
+    fn set_offscreen(&mut self, offscreen: bool) {
+        self.offscreen = offscreen;
+    }
+
+    fn scene_texture(&mut self) -> Result<Option<TextureId>> {
+        if !self.offscreen {
+            return Ok(None);
+        }
+
+        let width = self.viewport.width.max(1.0) as u32;
+        let height = self.viewport.height.max(1.0) as u32;
+        let target = self.scene_target.take();
+        let target = self.resize_target(target, width, height)?;
+        let texture_id = target.texture_id;
+        self.scene_target = Some(target);
+        Ok(Some(texture_id))
+    }
+
+    fn physics_debug(&self) -> PhysicsDebugSettings {
+        self.physics_debug
+    }
+
+    fn set_physics_debug(&mut self, settings: PhysicsDebugSettings) {
+        self.physics_debug = settings;
+    }
+
+    fn grid(&self) -> GridSettings {
+        self.grid
+    }
+
+    fn set_grid(&mut self, settings: GridSettings) {
+        self.grid = settings;
+    }
+
+    fn set_axis_gizmo(&mut self, region: Option<Viewport>) {
+        self.axis_gizmo = region;
+    }
+
+    fn set_overlay_lines(&mut self, lines: Vec<DebugLine>) {
+        self.overlay_lines = lines;
+    }
+
+    fn set_material_preview(&mut self, preview: Option<MaterialPreview>) {
+        self.material_preview = preview;
+    }
+
+    fn material_preview_texture(&mut self) -> Result<Option<TextureId>> {
+        let size = match self.material_preview.as_ref() {
+            Some(preview) => preview.size.max(1),
+            None => return Ok(None),
+        };
+        let target = self.preview_target.take();
+        let target = self.resize_target(target, size, size)?;
+        let texture_id = target.texture_id;
+        self.preview_target = Some(target);
+        Ok(Some(texture_id))
+    }
+
+    fn set_selected_entities(&mut self, entities: Vec<Entity>) {
+        self.selected_entities = entities;
     }
 
     fn resize(
@@ -125,6 +393,7 @@ impl Renderer for OpenGLRenderDevice {
         context: &ContextWrapper<PossiblyCurrent, Window>,
         dimensions: PhysicalSize<u32>,
     ) {
+        self.window_size = dimensions;
         self.set_viewport(Viewport {
             x: 0.0,
             y: 0.0,
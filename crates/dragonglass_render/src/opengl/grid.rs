@@ -0,0 +1,171 @@
+use crate::GridSettings;
+use dragonglass_dependencies::{anyhow::Result, gl};
+use dragonglass_opengl::ShaderProgram;
+use dragonglass_world::World;
+
+/// Draws an endless grid on the world's xz plane, fading out with distance from the camera.
+/// Each fragment of a screen covering triangle finds where its view ray meets the plane,
+/// and writes that point's depth so meshes still hide the grid.
+pub struct GridRender {
+    /// The triangle's corners come from the vertex index, but a vertex array must still be bound
+    vao: u32,
+    shader_program: ShaderProgram,
+}
+
+impl GridRender {
+    const VERTEX_SHADER_SOURCE: &'static str = r#"
+#version 450 core
+
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec3 nearPoint;
+out vec3 farPoint;
+
+vec3 unproject(vec2 position, float depth)
+{
+    vec4 point = inverse(view) * inverse(projection) * vec4(position, depth, 1.0);
+    return point.xyz / point.w;
+}
+
+void main()
+{
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+    // Any two depths on the view ray work, these stay finite for every projection
+    nearPoint = unproject(position, 0.0);
+    farPoint = unproject(position, 0.5);
+    gl_Position = vec4(position, 0.0, 1.0);
+}
+"#;
+
+    const FRAGMENT_SHADER_SOURCE: &'static str = r#"
+#version 450 core
+
+in vec3 nearPoint;
+in vec3 farPoint;
+
+uniform mat4 view;
+uniform mat4 projection;
+uniform vec3 cameraPosition;
+uniform float spacing;
+uniform float majorLines;
+uniform float fadeDistance;
+
+out vec4 color;
+
+float gridLine(vec2 coordinate, float scale)
+{
+    vec2 scaled = coordinate / scale;
+    vec2 lines = abs(fract(scaled - 0.5) - 0.5) / fwidth(scaled);
+    return 1.0 - min(min(lines.x, lines.y), 1.0);
+}
+
+void main(void)
+{
+    vec3 ray = farPoint - nearPoint;
+    if (abs(ray.y) < 0.000001) {
+        discard;
+    }
+    vec3 point = nearPoint - ray * (nearPoint.y / ray.y);
+
+    vec4 clip = projection * view * vec4(point, 1.0);
+    float depth = clip.z / clip.w;
+    if (clip.w <= 0.0 || depth < -1.0 || depth > 1.0) {
+        discard;
+    }
+    gl_FragDepth = depth * 0.5 + 0.5;
+
+    vec2 coordinate = point.xz;
+    float alpha = max(gridLine(coordinate, spacing) * 0.25,
+                      gridLine(coordinate, spacing * majorLines) * 0.5);
+    vec3 lineColor = vec3(0.7);
+
+    // The world's x and z axes are picked out in their gizmo colors
+    vec2 width = fwidth(coordinate);
+    if (abs(point.z) < width.y) {
+        lineColor = vec3(0.9, 0.2, 0.2);
+        alpha = 0.8;
+    }
+    if (abs(point.x) < width.x) {
+        lineColor = vec3(0.2, 0.4, 0.9);
+        alpha = 0.8;
+    }
+
+    alpha *= 1.0 - smoothstep(fadeDistance * 0.25, fadeDistance, distance(point, cameraPosition));
+    if (alpha <= 0.001) {
+        discard;
+    }
+    color = vec4(lineColor, alpha);
+}
+"#;
+
+    pub fn new() -> Result<Self> {
+        let mut shader_program = ShaderProgram::new();
+        shader_program
+            .vertex_shader_source(Self::VERTEX_SHADER_SOURCE)?
+            .fragment_shader_source(Self::FRAGMENT_SHADER_SOURCE)?
+            .link();
+
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+
+        Ok(Self {
+            vao,
+            shader_program,
+        })
+    }
+
+    pub fn render(&self, world: &World, aspect_ratio: f32, settings: &GridSettings) -> Result<()> {
+        if settings.spacing <= 0.0 {
+            return Ok(());
+        }
+
+        let (projection, view) = world.active_camera_matrices(aspect_ratio)?;
+        let camera_position = world
+            .entity_global_transform(world.active_camera()?)?
+            .translation;
+
+        self.shader_program.use_program();
+        self.shader_program
+            .set_uniform_matrix4x4("projection", projection.as_slice());
+        self.shader_program
+            .set_uniform_matrix4x4("view", view.as_slice());
+        self.shader_program
+            .set_uniform_vec3("cameraPosition", camera_position.as_slice());
+        self.shader_program
+            .set_uniform_float("spacing", settings.spacing);
+        self.shader_program
+            .set_uniform_float("majorLines", settings.major_lines.max(1) as f32);
+        self.shader_program
+            .set_uniform_float("fadeDistance", settings.fade_distance);
+
+        unsafe {
+            // The grid is unlit and translucent, and never hides what is drawn after it
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::Disable(gl::CULL_FACE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::CULL_FACE);
+            gl::DepthMask(gl::TRUE);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for GridRender {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::opengl::world::WorldRender;
+use dragonglass_dependencies::{anyhow::Result, gl, nalgebra_glm as glm};
+use dragonglass_opengl::ShaderProgram;
+use dragonglass_world::{Entity, EntityStore, MeshRender, Viewport, World};
+
+/// Outlines entities, such as the editor's selection, through the stencil buffer.
+/// Meshes are first written into the stencil, then drawn again pushed out along their normals,
+/// so only the ring around their silhouette is colored.
+pub struct OutlineRender {
+    shader_program: ShaderProgram,
+}
+
+impl OutlineRender {
+    const WIDTH: f32 = 3.0;
+    const COLOR: [f32; 4] = [1.0, 0.6, 0.1, 1.0];
+
+    const VERTEX_SHADER_SOURCE: &'static str = r#"
+#version 450 core
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec3 inNormal;
+
+uniform mat4 view;
+uniform mat4 projection;
+uniform mat4 model;
+uniform vec2 viewportSize;
+uniform float width;
+
+void main()
+{
+   gl_Position = projection * view * model * vec4(inPosition, 1.0);
+   vec2 clipNormal = (mat3(projection) * mat3(view) * mat3(model) * inNormal).xy;
+   if (length(clipNormal) > 0.0001) {
+       gl_Position.xy += normalize(clipNormal) / viewportSize * width * 2.0 * gl_Position.w;
+   }
+}
+"#;
+
+    const FRAGMENT_SHADER_SOURCE: &'static str = r#"
+#version 450 core
+
+uniform vec4 outlineColor;
+
+out vec4 color;
+
+void main(void)
+{
+    color = outlineColor;
+}
+"#;
+
+    pub fn new() -> Result<Self> {
+        let mut shader_program = ShaderProgram::new();
+        shader_program
+            .vertex_shader_source(Self::VERTEX_SHADER_SOURCE)?
+            .fragment_shader_source(Self::FRAGMENT_SHADER_SOURCE)?
+            .link();
+        Ok(Self { shader_program })
+    }
+
+    pub fn render(
+        &self,
+        world: &World,
+        world_render: &WorldRender,
+        viewport: &Viewport,
+        entities: &[Entity],
+    ) -> Result<()> {
+        let meshes = entities
+            .iter()
+            .filter_map(|entity| {
+                let entry = world.ecs.entry_ref(*entity).ok()?;
+                let mesh_render = entry.get_component::<MeshRender>().ok()?;
+                Some((*entity, mesh_render.name.to_string()))
+            })
+            .collect::<Vec<_>>();
+        if meshes.is_empty() {
+            return Ok(());
+        }
+
+        self.shader_program.use_program();
+        let aspect_ratio = viewport.width / viewport.height.max(1.0);
+        let (projection, view) = world.active_camera_matrices(aspect_ratio)?;
+        self.shader_program
+            .set_uniform_matrix4x4("projection", projection.as_slice());
+        self.shader_program
+            .set_uniform_matrix4x4("view", view.as_slice());
+        self.shader_program.set_uniform_vec2(
+            "viewportSize",
+            glm::vec2(viewport.width, viewport.height).as_slice(),
+        );
+        self.shader_program
+            .set_uniform_vec4("outlineColor", &Self::COLOR);
+
+        unsafe {
+            // Outlines show through other meshes so the selection is never hidden
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::CULL_FACE);
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::STENCIL_TEST);
+            gl::ClearStencil(0);
+            gl::Clear(gl::STENCIL_BUFFER_BIT);
+
+            gl::StencilOp(gl::KEEP, gl::KEEP, gl::REPLACE);
+            gl::StencilFunc(gl::ALWAYS, 1, 0xFF);
+            gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+        }
+        self.shader_program.set_uniform_float("width", 0.0);
+        self.draw_meshes(world, world_render, &meshes)?;
+
+        unsafe {
+            gl::StencilFunc(gl::NOTEQUAL, 1, 0xFF);
+            gl::StencilMask(0x00);
+            gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        }
+        self.shader_program.set_uniform_float("width", Self::WIDTH);
+        self.draw_meshes(world, world_render, &meshes)?;
+
+        unsafe {
+            gl::StencilMask(0xFF);
+            gl::Disable(gl::STENCIL_TEST);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Enable(gl::CULL_FACE);
+        }
+
+        Ok(())
+    }
+
+    fn draw_meshes(
+        &self,
+        world: &World,
+        world_render: &WorldRender,
+        meshes: &[(Entity, String)],
+    ) -> Result<()> {
+        for (entity, name) in meshes.iter() {
+            let model = world
+                .entity_model_matrix(*entity, world.entity_global_transform_matrix(*entity)?)?;
+            self.shader_program
+                .set_uniform_matrix4x4("model", model.as_slice());
+            world_render.draw_mesh(world, name);
+        }
+        Ok(())
+    }
+}
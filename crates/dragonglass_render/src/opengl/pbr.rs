@@ -101,6 +101,9 @@ impl PbrShader {
         self.shader_program
             .set_uniform_float("material.roughnessFactor", material.roughness_factor);
 
+        self.shader_program
+            .set_uniform_bool("material.isUnlit", material.is_unlit);
+
         for (index, descriptor) in ["Diffuse", "Physical", "Normal", "Occlusion", "Emissive"]
             .iter()
             .enumerate()
@@ -113,16 +116,21 @@ impl PbrShader {
                 "Emissive" => material.emissive_texture_index,
                 _ => bail!("Failed to find index for texture type!"),
             };
-            let has_texture = texture_index > -1;
+            // Slots pointing at textures that were never uploaded are treated as empty
+            let texture = usize::try_from(texture_index)
+                .ok()
+                .and_then(|texture_index| textures.get(texture_index));
 
-            self.shader_program
-                .set_uniform_bool(&format!("material.has{}Texture", *descriptor), has_texture);
+            self.shader_program.set_uniform_bool(
+                &format!("material.has{}Texture", *descriptor),
+                texture.is_some(),
+            );
 
             self.shader_program
                 .set_uniform_int(&format!("{}Texture", *descriptor), index as _);
 
-            if has_texture {
-                textures[texture_index as usize].bind(index as _);
+            if let Some(texture) = texture {
+                texture.bind(index as _);
             }
         }
 
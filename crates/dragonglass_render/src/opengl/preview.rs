@@ -0,0 +1,127 @@
+use crate::{opengl::pbr::PbrShader, MaterialPreview};
+use dragonglass_dependencies::{anyhow::Result, gl, nalgebra_glm as glm};
+use dragonglass_opengl::{Framebuffer, GeometryBuffer, Texture};
+use dragonglass_world::{AlphaMode, Vertex, World};
+use std::{f32::consts::PI, ptr};
+
+/// Draws a material on a sphere through the pbr shader, lit by fixed lights
+pub struct MaterialPreviewRender {
+    geometry: GeometryBuffer,
+    number_of_indices: usize,
+    pbr: PbrShader,
+}
+
+impl MaterialPreviewRender {
+    const RINGS: u32 = 24;
+    const SEGMENTS: u32 = 48;
+    const LIGHTS: [([f32; 3], [f32; 3]); 2] = [
+        ([2.0, 2.0, 3.0], [30.0, 30.0, 30.0]),
+        ([-3.0, -1.0, 2.0], [8.0, 8.0, 10.0]),
+    ];
+
+    pub fn new() -> Result<Self> {
+        let (vertices, indices) = sphere(Self::RINGS, Self::SEGMENTS);
+        let geometry = GeometryBuffer::new(&vertices, Some(&indices), &[3, 3, 2, 2, 4, 4, 3]);
+        Ok(Self {
+            geometry,
+            number_of_indices: indices.len(),
+            pbr: PbrShader::new()?,
+        })
+    }
+
+    /// Renders the preview into the framebuffer, covering all of it
+    pub fn render(
+        &self,
+        world: &World,
+        preview: &MaterialPreview,
+        textures: &[Texture],
+        framebuffer: &Framebuffer,
+    ) -> Result<()> {
+        let material = match world.materials.get(preview.material_index) {
+            Some(material) => material,
+            None => return Ok(()),
+        };
+
+        framebuffer.bind();
+        unsafe {
+            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+            gl::FrontFace(gl::CCW);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LEQUAL);
+            if material.alpha_mode == AlphaMode::Blend {
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            } else {
+                gl::Disable(gl::BLEND);
+            }
+        }
+
+        let shader_program = &self.pbr.shader_program;
+        shader_program.use_program();
+
+        for (index, (position, color)) in Self::LIGHTS.iter().enumerate() {
+            let name = |key: &str| format!("lights[{}].{}", index, key);
+            shader_program.set_uniform_vec3(&name("position"), position);
+            shader_program.set_uniform_vec3(&name("color"), color);
+            shader_program.set_uniform_int(&name("kind"), 1);
+        }
+        shader_program.set_uniform_int("numberOfLights", Self::LIGHTS.len() as _);
+
+        let camera_position = glm::vec3(0.0, 0.0, 2.6);
+        let view = glm::look_at(&camera_position, &glm::Vec3::zeros(), &glm::Vec3::y());
+        let aspect_ratio = framebuffer.width() as f32 / framebuffer.height() as f32;
+        let projection = glm::perspective_zo(aspect_ratio, 45_f32.to_radians(), 0.1, 10.0);
+        shader_program.set_uniform_vec3("cameraPosition", camera_position.as_slice());
+        shader_program.set_uniform_matrix4x4("projection", projection.as_slice());
+        shader_program.set_uniform_matrix4x4("view", view.as_slice());
+
+        self.pbr.update_model_matrix(glm::Mat4::identity());
+        self.pbr.update_material(material, textures)?;
+
+        self.geometry.bind();
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                self.number_of_indices as _,
+                gl::UNSIGNED_INT,
+                ptr::null(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Generates a unit sphere with normals and texture coordinates,
+/// wound counter-clockwise when viewed from outside
+fn sphere(rings: u32, segments: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    for ring in 0..=rings {
+        let phi = PI * ring as f32 / rings as f32;
+        for segment in 0..=segments {
+            let theta = 2.0 * PI * segment as f32 / segments as f32;
+            let position = glm::vec3(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+            vertices.push(Vertex {
+                position,
+                normal: position,
+                uv_0: glm::vec2(segment as f32 / segments as f32, ring as f32 / rings as f32),
+                ..Default::default()
+            });
+        }
+    }
+
+    let mut indices = Vec::new();
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let top = ring * (segments + 1) + segment;
+            let bottom = top + segments + 1;
+            indices.extend_from_slice(&[top, top + 1, bottom, top + 1, bottom + 1, bottom]);
+        }
+    }
+
+    (vertices, indices)
+}
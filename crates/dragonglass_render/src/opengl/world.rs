@@ -1,13 +1,13 @@
 use dragonglass_dependencies::{
-    anyhow::{bail, Context, Result},
+    anyhow::{bail, Result},
     gl, nalgebra_glm as glm,
 };
 use dragonglass_opengl::{GeometryBuffer, ShaderProgram, Texture};
 use dragonglass_world::{
-    AlphaMode, EntityStore, LightKind, Material, MeshRender, RigidBody, TextureFormat, Transform,
-    World,
+    AlphaMode, EntityStore, Geometry, LightKind, Material, Mesh, MeshRender, Primitive,
+    TextureFormat, Transform, World,
 };
-use std::{ptr, str};
+use std::{collections::HashMap, ptr, str};
 
 // TODO: This is duplicated in the vulkan backend and should be moved
 #[derive(Default, Debug, Copy, Clone)]
@@ -57,10 +57,72 @@ impl Light {
     }
 }
 
+/// The gpu copy of a single mesh's vertices and indices
+struct MeshBuffer {
+    geometry: GeometryBuffer,
+    generation: u64,
+    /// Where the mesh's indices begin in the world's index buffer,
+    /// used to offset primitives into this buffer
+    first_index: usize,
+}
+
+impl MeshBuffer {
+    /// Uploads the vertex and index ranges covered by a mesh's primitives,
+    /// rebasing the indices onto the mesh's first vertex
+    fn new(geometry: &Geometry, mesh: &Mesh, generation: u64) -> Option<Self> {
+        let first_vertex = mesh.primitives.iter().map(|p| p.first_vertex).min()?;
+        let last_vertex = mesh
+            .primitives
+            .iter()
+            .map(|p| p.first_vertex + p.number_of_vertices)
+            .max()?;
+        let first_index = mesh.primitives.iter().map(|p| p.first_index).min()?;
+        let last_index = mesh
+            .primitives
+            .iter()
+            .map(|p| p.first_index + p.number_of_indices)
+            .max()?;
+
+        let vertices = geometry.vertices.get(first_vertex..last_vertex)?;
+        if vertices.is_empty() {
+            return None;
+        }
+        let indices = geometry
+            .indices
+            .get(first_index..last_index)?
+            .iter()
+            .map(|index| index.saturating_sub(first_vertex as u32))
+            .collect::<Vec<_>>();
+
+        Some(Self {
+            geometry: GeometryBuffer::new(vertices, Some(&indices), &[3, 3, 2, 2, 4, 4, 3]),
+            generation,
+            first_index,
+        })
+    }
+
+    /// Draws a primitive of the mesh, the buffer must already be bound
+    fn draw_primitive(&self, primitive: &Primitive) {
+        let ptr: *const u8 = ptr::null_mut();
+        let ptr = unsafe {
+            ptr.add((primitive.first_index - self.first_index) * std::mem::size_of::<u32>())
+        };
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                primitive.number_of_indices as _,
+                gl::UNSIGNED_INT,
+                ptr as *const _,
+            );
+        }
+    }
+}
+
 pub struct WorldRender {
-    pub geometry: GeometryBuffer,
     pub shader_program: ShaderProgram,
     pub textures: Vec<Texture>,
+    texture_generations: Vec<u64>,
+    meshes: HashMap<String, MeshBuffer>,
 }
 
 impl WorldRender {
@@ -267,32 +329,80 @@ vec3 fresnelSchlick(float cosTheta, vec3 F0)
 }
 "#;
 
-    pub fn new(world: &World) -> Result<Self> {
-        let geometry = GeometryBuffer::new(
-            &world.geometry.vertices,
-            Some(&world.geometry.indices),
-            &[3, 3, 2, 2, 4, 4, 3],
-        );
-
+    pub fn new() -> Result<Self> {
         let mut shader_program = ShaderProgram::new();
         shader_program
             .vertex_shader_source(Self::VERTEX_SHADER_SOURCE)?
             .fragment_shader_source(Self::FRAGMENT_SHADER_SOURCE)?
             .link();
 
-        let textures = world
-            .textures
-            .iter()
-            .map(Self::map_world_texture)
-            .collect::<Vec<_>>();
-
         Ok(Self {
-            geometry,
             shader_program,
-            textures,
+            textures: Vec::new(),
+            texture_generations: Vec::new(),
+            meshes: HashMap::new(),
         })
     }
 
+    /// Uploads the world's new and changed meshes and textures,
+    /// and frees the ones the world no longer has.
+    /// Meshes no entity renders are freed too, and uploaded again once something renders them.
+    pub fn sync(&mut self, world: &World) {
+        self.textures.truncate(world.textures.len());
+        self.texture_generations.truncate(world.textures.len());
+        for (index, world_texture) in world.textures.iter().enumerate() {
+            let generation = world.generations.texture(index);
+            if self.texture_generations.get(index) == Some(&generation) {
+                continue;
+            }
+            let texture = Self::map_world_texture(world_texture);
+            if index < self.textures.len() {
+                self.textures[index] = texture;
+                self.texture_generations[index] = generation;
+            } else {
+                self.textures.push(texture);
+                self.texture_generations.push(generation);
+            }
+        }
+
+        let referenced_meshes = world.referenced_meshes();
+        self.meshes.retain(|name, _| {
+            world.geometry.meshes.contains_key(name) && referenced_meshes.contains(name)
+        });
+        for (name, mesh) in world.geometry.meshes.iter() {
+            if !referenced_meshes.contains(name) {
+                continue;
+            }
+            let generation = world.generations.mesh(name);
+            if let Some(buffer) = self.meshes.get(name) {
+                if buffer.generation == generation {
+                    continue;
+                }
+            }
+            match MeshBuffer::new(&world.geometry, mesh, generation) {
+                Some(buffer) => {
+                    self.meshes.insert(name.to_string(), buffer);
+                }
+                None => {
+                    self.meshes.remove(name);
+                }
+            }
+        }
+    }
+
+    /// Draws every primitive of a mesh with the currently bound program,
+    /// for passes that ignore materials
+    pub fn draw_mesh(&self, world: &World, name: &str) {
+        let mesh = world.geometry.meshes.get(name);
+        let buffer = self.meshes.get(name);
+        if let (Some(mesh), Some(buffer)) = (mesh, buffer) {
+            buffer.geometry.bind();
+            mesh.primitives
+                .iter()
+                .for_each(|primitive| buffer.draw_primitive(primitive));
+        }
+    }
+
     fn map_world_texture(
         world_texture: &dragonglass_world::Texture,
     ) -> dragonglass_opengl::Texture {
@@ -341,7 +451,6 @@ vec3 fresnelSchlick(float cosTheta, vec3 F0)
             gl::DepthFunc(gl::LEQUAL);
         }
 
-        self.geometry.bind();
         self.shader_program.use_program();
 
         let world_lights = world
@@ -389,35 +498,20 @@ vec3 fresnelSchlick(float cosTheta, vec3 F0)
                 graph.walk(|node_index| {
                     let entity = graph[node_index];
 
-                    let entry = world.ecs.entry_ref(entity)?;
-
                     // Render rigid bodies at the transform specified by the physics world instead of the scenegraph
-                    // NOTE: The rigid body collider scaling should be the same as the scale of the entity transform
-                    //       otherwise this won't look right. It's probably best to just not scale entities that have rigid bodies
-                    //       with colliders on them.
-                    let model = match entry.get_component::<RigidBody>() {
-                        Ok(rigid_body) => {
-                            let body = world
-                                .physics
-                                .bodies
-                                .get(rigid_body.handle)
-                                .context("Failed to acquire physics body to render!")?;
-                            let position = body.position();
-                            let translation = position.translation.vector;
-                            let rotation = *position.rotation.quaternion();
-                            let scale =
-                                Transform::from(world.global_transform(graph, node_index)?).scale;
-                            Transform::new(translation, rotation, scale).matrix()
-                        }
-                        Err(_) => world.global_transform(graph, node_index)?,
-                    };
+                    let model = world
+                        .entity_model_matrix(entity, world.global_transform(graph, node_index)?)?;
 
                     self.shader_program
                         .set_uniform_matrix4x4("model", model.as_slice());
 
                     match world.ecs.entry_ref(entity)?.get_component::<MeshRender>() {
                         Ok(mesh_render) => {
-                            if let Some(mesh) = world.geometry.meshes.get(&mesh_render.name) {
+                            let mesh = world.geometry.meshes.get(&mesh_render.name);
+                            let buffer = self.meshes.get(&mesh_render.name);
+                            if let (Some(mesh), Some(buffer)) = (mesh, buffer) {
+                                buffer.geometry.bind();
+
                                 match alpha_mode {
                                     AlphaMode::Opaque | AlphaMode::Mask => unsafe {
                                         gl::Disable(gl::BLEND);
@@ -476,6 +570,9 @@ vec3 fresnelSchlick(float cosTheta, vec3 F0)
                                         material.roughness_factor,
                                     );
 
+                                    self.shader_program
+                                        .set_uniform_bool("material.isUnlit", material.is_unlit);
+
                                     for (index, descriptor) in
                                         ["Diffuse", "Physical", "Normal", "Occlusion", "Emissive"]
                                             .iter()
@@ -489,11 +586,14 @@ vec3 fresnelSchlick(float cosTheta, vec3 F0)
                                             "Emissive" => material.emissive_texture_index,
                                             _ => bail!("Failed to find index for texture type!"),
                                         };
-                                        let has_texture = texture_index > -1;
+                                        // Materials can be edited to point at any texture slot
+                                        let texture = usize::try_from(texture_index).ok().and_then(
+                                            |texture_index| self.textures.get(texture_index),
+                                        );
 
                                         self.shader_program.set_uniform_bool(
                                             &format!("material.has{}Texture", *descriptor),
-                                            has_texture,
+                                            texture.is_some(),
                                         );
 
                                         self.shader_program.set_uniform_int(
@@ -501,23 +601,12 @@ vec3 fresnelSchlick(float cosTheta, vec3 F0)
                                             index as _,
                                         );
 
-                                        if has_texture {
-                                            self.textures[texture_index as usize].bind(index as _);
+                                        if let Some(texture) = texture {
+                                            texture.bind(index as _);
                                         }
                                     }
 
-                                    let ptr: *const u8 = ptr::null_mut();
-                                    let ptr = unsafe {
-                                        ptr.add(primitive.first_index * std::mem::size_of::<u32>())
-                                    };
-                                    unsafe {
-                                        gl::DrawElements(
-                                            gl::TRIANGLES,
-                                            primitive.number_of_indices as _,
-                                            gl::UNSIGNED_INT,
-                                            ptr as *const _,
-                                        );
-                                    }
+                                    buffer.draw_primitive(primitive);
                                 }
                             }
                         }
@@ -1,10 +1,11 @@
 use dragonglass_dependencies::{
     anyhow::Result,
-    egui::{epaint::ClippedShape, CtxRef},
+    egui::{epaint::ClippedShape, CtxRef, TextureId},
     glutin::{window::Window, ContextWrapper, PossiblyCurrent},
+    serde::{Deserialize, Serialize},
     winit::dpi::PhysicalSize,
 };
-use dragonglass_world::{Viewport, World};
+use dragonglass_world::{DebugLine, Entity, PhysicsDebugSettings, Viewport, World};
 
 use crate::opengl::OpenGLRenderDevice;
 
@@ -12,6 +13,39 @@ pub enum Backend {
     OpenGL,
 }
 
+/// A world material drawn on a sphere into an offscreen texture,
+/// which the gui shows as an image
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MaterialPreview {
+    pub material_index: usize,
+    /// The width and height of the preview texture in pixels
+    pub size: u32,
+}
+
+/// The ground grid drawn across the world's xz plane
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct GridSettings {
+    pub enabled: bool,
+    /// The distance between grid lines
+    pub spacing: f32,
+    /// Every this many lines is drawn brighter
+    pub major_lines: u32,
+    /// How far from the camera the grid fades out
+    pub fade_distance: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 1.0,
+            major_lines: 10,
+            fade_distance: 100.0,
+        }
+    }
+}
+
 pub trait Renderer {
     fn cleanup(&mut self);
     fn render(
@@ -21,9 +55,31 @@ pub trait Renderer {
         gui_context: &CtxRef,
         clipped_shapes: Vec<ClippedShape>,
     ) -> Result<()>;
+    /// Uploads the world's new and changed assets and frees the ones it no longer uses
     fn load_world(&mut self, world: &World) -> Result<()>;
+    /// The region of the window the scene is shown in, in pixels from the top left
     fn viewport(&self) -> Viewport;
     fn set_viewport(&mut self, viewport: Viewport);
+    /// Renders the scene into an offscreen texture the size of the viewport,
+    /// for the gui to show instead of the scene being drawn into the window
+    fn set_offscreen(&mut self, offscreen: bool);
+    /// Sizes the offscreen texture to the viewport, returning the id the gui shows it with.
+    /// Returns `None` while the scene renders into the window.
+    fn scene_texture(&mut self) -> Result<Option<TextureId>>;
+    fn physics_debug(&self) -> PhysicsDebugSettings;
+    fn set_physics_debug(&mut self, settings: PhysicsDebugSettings);
+    fn grid(&self) -> GridSettings;
+    fn set_grid(&mut self, settings: GridSettings);
+    /// Sets the region the world axes are drawn in, in points from the top left of the viewport
+    fn set_axis_gizmo(&mut self, region: Option<Viewport>);
+    /// Sets lines drawn over the scene each frame, such as editor gizmos
+    fn set_overlay_lines(&mut self, lines: Vec<DebugLine>);
+    fn set_material_preview(&mut self, preview: Option<MaterialPreview>);
+    /// Sizes the preview texture to the material preview, returning the id the gui shows it with.
+    /// Returns `None` while no material is previewed.
+    fn material_preview_texture(&mut self) -> Result<Option<TextureId>>;
+    /// Sets the entities outlined each frame, such as the editor's selection
+    fn set_selected_entities(&mut self, entities: Vec<Entity>);
     fn resize(
         &mut self,
         context: &ContextWrapper<PossiblyCurrent, Window>,
@@ -0,0 +1,256 @@
+use crate::{
+    Animation, Material, Mesh, MeshRender, SdfFont, Texture, TransformationSet, Vertex, World,
+};
+use dragonglass_dependencies::{
+    anyhow::{Context, Result},
+    legion::IntoQuery,
+    nalgebra_glm as glm,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a generation that no other asset in the process has used
+pub fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Tracks when the world's meshes and textures last changed,
+/// so renderers can upload only what is new or modified.
+/// Materials are read by renderers as they draw, so they need no tracking.
+///
+/// Generations are unique within the process, so a renderer can never mistake
+/// the assets of a newly loaded or restored world for ones it has already uploaded.
+#[derive(Debug, Clone)]
+pub struct AssetGenerations {
+    /// The generation of every asset that has not changed since the world was created
+    base: u64,
+    meshes: HashMap<String, u64>,
+    textures: HashMap<usize, u64>,
+}
+
+impl Default for AssetGenerations {
+    fn default() -> Self {
+        Self {
+            base: next_generation(),
+            meshes: HashMap::new(),
+            textures: HashMap::new(),
+        }
+    }
+}
+
+impl AssetGenerations {
+    pub fn mesh(&self, name: &str) -> u64 {
+        self.meshes.get(name).copied().unwrap_or(self.base)
+    }
+
+    pub fn texture(&self, index: usize) -> u64 {
+        self.textures.get(&index).copied().unwrap_or(self.base)
+    }
+
+    /// Marks a mesh's geometry as changed
+    pub fn touch_mesh(&mut self, name: &str) {
+        self.meshes.insert(name.to_string(), next_generation());
+    }
+
+    /// Marks a texture's pixels or sampler as changed
+    pub fn touch_texture(&mut self, index: usize) {
+        self.textures.insert(index, next_generation());
+    }
+}
+
+/// Identifies an asset within the world's asset collections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetId {
+    Mesh(String),
+    Material(usize),
+    Texture(usize),
+    Animation(usize),
+    Font(String),
+}
+
+/// A loaded asset and the approximate number of bytes it holds in memory
+#[derive(Debug, Clone)]
+pub struct AssetInfo {
+    pub id: AssetId,
+    pub name: String,
+    pub description: String,
+    pub bytes: usize,
+}
+
+impl World {
+    /// Replaces one of the world's textures, so renderers upload it again
+    pub fn set_texture(&mut self, index: usize, texture: Texture) -> Result<()> {
+        *self
+            .textures
+            .get_mut(index)
+            .context("Failed to set texture, the index is out of range!")? = texture;
+        self.generations.touch_texture(index);
+        Ok(())
+    }
+
+    /// The names of the meshes that at least one entity renders
+    pub fn referenced_meshes(&self) -> HashSet<String> {
+        <&MeshRender>::query()
+            .iter(&self.ecs)
+            .map(|mesh_render| mesh_render.name.to_string())
+            .collect()
+    }
+
+    /// Lists the world's meshes, materials, textures, animations, and fonts
+    pub fn assets(&self) -> Vec<AssetInfo> {
+        let mut assets = Vec::new();
+
+        let mut mesh_names = self.geometry.meshes.keys().collect::<Vec<_>>();
+        mesh_names.sort();
+        for name in mesh_names.into_iter() {
+            let mesh = &self.geometry.meshes[name];
+            let vertices = mesh
+                .primitives
+                .iter()
+                .map(|primitive| primitive.number_of_vertices)
+                .sum::<usize>();
+            assets.push(AssetInfo {
+                id: AssetId::Mesh(name.to_string()),
+                name: name.to_string(),
+                description: format!(
+                    "{} primitives, {} vertices",
+                    mesh.primitives.len(),
+                    vertices
+                ),
+                bytes: mesh_memory_usage(mesh),
+            });
+        }
+
+        for (index, material) in self.materials.iter().enumerate() {
+            assets.push(AssetInfo {
+                id: AssetId::Material(index),
+                name: material.name.to_string(),
+                description: format!("{:?}", material.alpha_mode),
+                bytes: mem::size_of::<Material>() + material.name.len(),
+            });
+        }
+
+        for (index, texture) in self.textures.iter().enumerate() {
+            assets.push(AssetInfo {
+                id: AssetId::Texture(index),
+                name: format!("Texture {}", index),
+                description: format!("{}x{} {:?}", texture.width, texture.height, texture.format),
+                bytes: texture_memory_usage(texture),
+            });
+        }
+
+        for (index, animation) in self.animations.iter().enumerate() {
+            assets.push(AssetInfo {
+                id: AssetId::Animation(index),
+                name: animation.name.to_string(),
+                description: format!(
+                    "{} channels, {:.2}s",
+                    animation.channels.len(),
+                    animation.max_animation_time
+                ),
+                bytes: animation_memory_usage(animation),
+            });
+        }
+
+        let mut font_names = self.fonts.keys().collect::<Vec<_>>();
+        font_names.sort();
+        for name in font_names.into_iter() {
+            let font = &self.fonts[name];
+            assets.push(AssetInfo {
+                id: AssetId::Font(name.to_string()),
+                name: name.to_string(),
+                description: "Signed distance field".to_string(),
+                bytes: font_memory_usage(font),
+            });
+        }
+
+        assets
+    }
+}
+
+/// Counts the mesh's vertices, indices, and morph targets
+fn mesh_memory_usage(mesh: &Mesh) -> usize {
+    mesh.primitives
+        .iter()
+        .map(|primitive| {
+            primitive.number_of_vertices * mem::size_of::<Vertex>()
+                + primitive.number_of_indices * mem::size_of::<u32>()
+                + primitive
+                    .morph_targets
+                    .iter()
+                    .map(|target| target.total_length() * mem::size_of::<glm::Vec4>())
+                    .sum::<usize>()
+        })
+        .sum()
+}
+
+fn texture_memory_usage(texture: &Texture) -> usize {
+    texture.pixels.len()
+}
+
+fn animation_memory_usage(animation: &Animation) -> usize {
+    animation
+        .channels
+        .iter()
+        .map(|channel| {
+            let outputs = match &channel.transformations {
+                TransformationSet::Translations(values) | TransformationSet::Scales(values) => {
+                    values.len() * mem::size_of::<glm::Vec3>()
+                }
+                TransformationSet::Rotations(values) => values.len() * mem::size_of::<glm::Vec4>(),
+                TransformationSet::MorphTargetWeights(values) => {
+                    values.len() * mem::size_of::<f32>()
+                }
+            };
+            channel.inputs.len() * mem::size_of::<f32>() + outputs
+        })
+        .sum()
+}
+
+fn font_memory_usage(font: &SdfFont) -> usize {
+    texture_memory_usage(font.texture())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextureFormat;
+
+    #[test]
+    fn set_texture_changes_generation() -> Result<()> {
+        let mut world = World::new()?;
+        world
+            .textures
+            .push(Texture::empty(1, 1, TextureFormat::R8G8B8A8));
+        let before = world.generations.texture(0);
+
+        world.set_texture(0, Texture::empty(2, 2, TextureFormat::R8))?;
+
+        assert_ne!(world.generations.texture(0), before);
+        assert_eq!(world.textures[0].format, TextureFormat::R8);
+        let texture = Texture::empty(1, 1, TextureFormat::R8);
+        assert!(world.set_texture(1, texture).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn referenced_meshes_lists_rendered_meshes() -> Result<()> {
+        let mut world = World::new()?;
+        world.ecs.push((MeshRender {
+            name: "cube".to_string(),
+        },));
+        world.ecs.push((MeshRender {
+            name: "cube".to_string(),
+        },));
+
+        let meshes = world.referenced_meshes();
+        assert_eq!(meshes.len(), 1);
+        assert!(meshes.contains("cube"));
+        Ok(())
+    }
+}
@@ -0,0 +1,550 @@
+use crate::WorldPhysics;
+use dragonglass_dependencies::{
+    nalgebra::{Isometry3, Point3},
+    nalgebra_glm as glm,
+    rapier3d::{
+        dynamics::RigidBodyType,
+        geometry::{Shape, AABB},
+    },
+};
+use std::f32::consts::PI;
+
+#[derive(Debug, Copy, Clone)]
+pub struct DebugLine {
+    pub start: glm::Vec3,
+    pub end: glm::Vec3,
+    pub color: glm::Vec3,
+}
+
+impl DebugLine {
+    pub fn new(start: glm::Vec3, end: glm::Vec3, color: glm::Vec3) -> Self {
+        Self { start, end, color }
+    }
+}
+
+/// Controls which physics debug geometry is generated
+#[derive(Debug, Copy, Clone)]
+pub struct PhysicsDebugSettings {
+    pub enabled: bool,
+    pub colliders: bool,
+    pub aabbs: bool,
+    pub contacts: bool,
+    pub joints: bool,
+    /// Draws colliders attached to sleeping bodies in a dimmed color
+    pub sleep_states: bool,
+}
+
+impl Default for PhysicsDebugSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            colliders: true,
+            aabbs: false,
+            contacts: true,
+            joints: true,
+            sleep_states: true,
+        }
+    }
+}
+
+impl PhysicsDebugSettings {
+    pub const DYNAMIC_COLOR: [f32; 3] = [0.2, 0.9, 0.3];
+    pub const FIXED_COLOR: [f32; 3] = [0.3, 0.5, 0.9];
+    pub const KINEMATIC_COLOR: [f32; 3] = [0.9, 0.8, 0.2];
+    pub const SENSOR_COLOR: [f32; 3] = [0.8, 0.3, 0.9];
+    pub const SLEEPING_COLOR: [f32; 3] = [0.45, 0.45, 0.45];
+    pub const AABB_COLOR: [f32; 3] = [0.9, 0.5, 0.1];
+    pub const CONTACT_COLOR: [f32; 3] = [1.0, 0.1, 0.1];
+    pub const JOINT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+    /// The length of drawn contact normals and joint frame axes
+    pub const AXIS_LENGTH: f32 = 0.25;
+
+    /// The number of segments used to approximate a full circle
+    pub const CIRCLE_SEGMENTS: usize = 24;
+}
+
+impl WorldPhysics {
+    /// Generates line lists visualizing the physics world
+    pub fn debug_lines(&self, settings: &PhysicsDebugSettings) -> Vec<DebugLine> {
+        let mut lines = Vec::new();
+        if !settings.enabled {
+            return lines;
+        }
+        if settings.colliders {
+            self.collider_lines(settings, &mut lines);
+        }
+        if settings.aabbs {
+            self.aabb_lines(&mut lines);
+        }
+        if settings.contacts {
+            self.contact_lines(&mut lines);
+        }
+        if settings.joints {
+            self.joint_lines(&mut lines);
+        }
+        lines
+    }
+
+    fn collider_lines(&self, settings: &PhysicsDebugSettings, lines: &mut Vec<DebugLine>) {
+        for (_handle, collider) in self.colliders.iter() {
+            let body = collider.parent().and_then(|handle| self.bodies.get(handle));
+            let color = match body {
+                Some(body) if settings.sleep_states && body.is_sleeping() => {
+                    PhysicsDebugSettings::SLEEPING_COLOR
+                }
+                _ if collider.is_sensor() => PhysicsDebugSettings::SENSOR_COLOR,
+                Some(body) => match body.body_type() {
+                    RigidBodyType::Dynamic => PhysicsDebugSettings::DYNAMIC_COLOR,
+                    RigidBodyType::Fixed => PhysicsDebugSettings::FIXED_COLOR,
+                    _ => PhysicsDebugSettings::KINEMATIC_COLOR,
+                },
+                None => PhysicsDebugSettings::FIXED_COLOR,
+            };
+            shape_lines(
+                collider.shape(),
+                collider.position(),
+                &glm::Vec3::from(color),
+                lines,
+            );
+        }
+    }
+
+    fn aabb_lines(&self, lines: &mut Vec<DebugLine>) {
+        let color = glm::Vec3::from(PhysicsDebugSettings::AABB_COLOR);
+        for (_handle, collider) in self.colliders.iter() {
+            aabb_lines(&collider.compute_aabb(), &color, lines);
+        }
+    }
+
+    fn contact_lines(&self, lines: &mut Vec<DebugLine>) {
+        let color = glm::Vec3::from(PhysicsDebugSettings::CONTACT_COLOR);
+        for pair in self.narrow_phase.contact_pairs() {
+            if !pair.has_any_active_contact {
+                continue;
+            }
+            for manifold in pair.manifolds.iter() {
+                let normal = manifold.data.normal * PhysicsDebugSettings::AXIS_LENGTH;
+                for contact in manifold.data.solver_contacts.iter() {
+                    let point = contact.point.coords;
+                    lines.push(DebugLine::new(point, point + normal, color));
+                }
+            }
+        }
+    }
+
+    fn joint_lines(&self, lines: &mut Vec<DebugLine>) {
+        let color = glm::Vec3::from(PhysicsDebugSettings::JOINT_COLOR);
+        let mut add_joint = |frame1: Isometry3<f32>, frame2: Isometry3<f32>| {
+            frame_lines(&frame1, lines);
+            frame_lines(&frame2, lines);
+            lines.push(DebugLine::new(
+                frame1.translation.vector,
+                frame2.translation.vector,
+                color,
+            ));
+        };
+
+        for (_handle, joint) in self.impulse_joints.iter() {
+            let (body1, body2) = match (self.bodies.get(joint.body1), self.bodies.get(joint.body2))
+            {
+                (Some(body1), Some(body2)) => (body1, body2),
+                _ => continue,
+            };
+            add_joint(
+                body1.position() * joint.data.local_frame1,
+                body2.position() * joint.data.local_frame2,
+            );
+        }
+
+        for (_handle, multibody, link) in self.multibody_joints.iter() {
+            let parent = match link.parent_id().and_then(|id| multibody.link(id)) {
+                Some(parent) => parent,
+                None => continue,
+            };
+            let (body1, body2) = match (
+                self.bodies.get(parent.rigid_body_handle()),
+                self.bodies.get(link.rigid_body_handle()),
+            ) {
+                (Some(body1), Some(body2)) => (body1, body2),
+                _ => continue,
+            };
+            let data = &link.joint().data;
+            add_joint(
+                body1.position() * data.local_frame1,
+                body2.position() * data.local_frame2,
+            );
+        }
+    }
+}
+
+/// Appends the wireframe of a shape placed at the given position
+pub fn shape_lines(
+    shape: &dyn Shape,
+    position: &Isometry3<f32>,
+    color: &glm::Vec3,
+    lines: &mut Vec<DebugLine>,
+) {
+    let point = |local: glm::Vec3| (position * Point3::from(local)).coords;
+    let mut line = |start: glm::Vec3, end: glm::Vec3| {
+        lines.push(DebugLine::new(point(start), point(end), *color))
+    };
+
+    if let Some(ball) = shape.as_ball() {
+        let center = glm::Vec3::zeros();
+        let (x, y, z) = (glm::Vec3::x(), glm::Vec3::y(), glm::Vec3::z());
+        arc(&center, &x, &y, ball.radius, 0.0, 2.0 * PI, &mut line);
+        arc(&center, &y, &z, ball.radius, 0.0, 2.0 * PI, &mut line);
+        arc(&center, &z, &x, ball.radius, 0.0, 2.0 * PI, &mut line);
+        return;
+    }
+
+    if let Some(cuboid) = shape.as_cuboid() {
+        box_lines(&-cuboid.half_extents, &cuboid.half_extents, &mut line);
+        return;
+    }
+
+    if let Some(capsule) = shape.as_capsule() {
+        let (a, b) = (capsule.segment.a.coords, capsule.segment.b.coords);
+        let radius = capsule.radius;
+        let axis = match (b - a).try_normalize(f32::EPSILON) {
+            Some(axis) => axis,
+            None => glm::Vec3::y(),
+        };
+        let (u, v) = orthonormal_basis(&axis);
+        arc(&a, &u, &v, radius, 0.0, 2.0 * PI, &mut line);
+        arc(&b, &u, &v, radius, 0.0, 2.0 * PI, &mut line);
+        for side in [u, -u, v, -v].iter() {
+            line(a + side * radius, b + side * radius);
+        }
+        for side in [u, v].iter() {
+            arc(&a, &axis, side, radius, 0.5 * PI, 1.5 * PI, &mut line);
+            arc(&b, &axis, side, radius, -0.5 * PI, 0.5 * PI, &mut line);
+        }
+        return;
+    }
+
+    if let Some(cylinder) = shape.as_cylinder() {
+        let (radius, half_height) = (cylinder.radius, cylinder.half_height);
+        let (x, z) = (glm::Vec3::x(), glm::Vec3::z());
+        let top = glm::vec3(0.0, half_height, 0.0);
+        let bottom = -top;
+        arc(&top, &x, &z, radius, 0.0, 2.0 * PI, &mut line);
+        arc(&bottom, &x, &z, radius, 0.0, 2.0 * PI, &mut line);
+        for side in [x, -x, z, -z].iter() {
+            line(bottom + side * radius, top + side * radius);
+        }
+        return;
+    }
+
+    if let Some(trimesh) = shape.as_trimesh() {
+        for triangle in trimesh.triangles() {
+            let (a, b, c) = (triangle.a.coords, triangle.b.coords, triangle.c.coords);
+            line(a, b);
+            line(b, c);
+            line(c, a);
+        }
+        return;
+    }
+
+    if let Some(polyhedron) = shape.as_convex_polyhedron() {
+        let points = polyhedron.points();
+        for edge in polyhedron.edges() {
+            line(
+                points[edge.vertices.x as usize].coords,
+                points[edge.vertices.y as usize].coords,
+            );
+        }
+        return;
+    }
+
+    if let Some(compound) = shape.as_compound() {
+        for (local_position, shape) in compound.shapes() {
+            shape_lines(shape.as_ref(), &(position * local_position), color, lines);
+        }
+        return;
+    }
+
+    // Shapes without a dedicated wireframe are drawn as their bounds
+    aabb_lines(&shape.compute_aabb(position), color, lines);
+}
+
+/// Appends the twelve edges of an axis-aligned bounding box
+pub fn aabb_lines(aabb: &AABB, color: &glm::Vec3, lines: &mut Vec<DebugLine>) {
+    box_lines(&aabb.mins.coords, &aabb.maxs.coords, &mut |start, end| {
+        lines.push(DebugLine::new(start, end, *color))
+    });
+}
+
+/// Appends the x, y, and z axes of a frame in red, green, and blue
+pub fn frame_lines(frame: &Isometry3<f32>, lines: &mut Vec<DebugLine>) {
+    let origin = frame.translation.vector;
+    for axis in [glm::Vec3::x(), glm::Vec3::y(), glm::Vec3::z()].iter() {
+        let end = origin + frame.rotation * (axis * PhysicsDebugSettings::AXIS_LENGTH);
+        lines.push(DebugLine::new(origin, end, *axis));
+    }
+}
+
+pub(crate) fn box_lines(
+    min: &glm::Vec3,
+    max: &glm::Vec3,
+    line: &mut impl FnMut(glm::Vec3, glm::Vec3),
+) {
+    let corner = |index: usize| {
+        glm::vec3(
+            if index & 1 == 0 { min.x } else { max.x },
+            if index & 2 == 0 { min.y } else { max.y },
+            if index & 4 == 0 { min.z } else { max.z },
+        )
+    };
+    for index in 0..8 {
+        for bit in [1, 2, 4].iter() {
+            if index & bit == 0 {
+                line(corner(index), corner(index | bit));
+            }
+        }
+    }
+}
+
+pub(crate) fn arc(
+    center: &glm::Vec3,
+    x_axis: &glm::Vec3,
+    y_axis: &glm::Vec3,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    line: &mut impl FnMut(glm::Vec3, glm::Vec3),
+) {
+    let sweep = end_angle - start_angle;
+    let segments =
+        ((PhysicsDebugSettings::CIRCLE_SEGMENTS as f32 * sweep.abs() / (2.0 * PI)).ceil() as usize)
+            .max(1);
+    let point = |angle: f32| center + (x_axis * angle.cos() + y_axis * angle.sin()) * radius;
+    for segment in 0..segments {
+        let angle = start_angle + sweep * segment as f32 / segments as f32;
+        let next_angle = start_angle + sweep * (segment + 1) as f32 / segments as f32;
+        line(point(angle), point(next_angle));
+    }
+}
+
+fn orthonormal_basis(axis: &glm::Vec3) -> (glm::Vec3, glm::Vec3) {
+    let reference = if axis.x.abs() < 0.9 {
+        glm::Vec3::x()
+    } else {
+        glm::Vec3::y()
+    };
+    let u = axis.cross(&reference).normalize();
+    let v = axis.cross(&u);
+    (u, v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragonglass_dependencies::rapier3d::{
+        dynamics::{RigidBodyBuilder, RigidBodyHandle},
+        geometry::ColliderBuilder,
+        prelude::{FixedJointBuilder, GenericJoint},
+    };
+
+    fn enabled_settings() -> PhysicsDebugSettings {
+        PhysicsDebugSettings {
+            enabled: true,
+            colliders: false,
+            aabbs: false,
+            contacts: false,
+            joints: false,
+            sleep_states: true,
+        }
+    }
+
+    fn insert_body(
+        physics: &mut WorldPhysics,
+        body_type: RigidBodyType,
+        translation: glm::Vec3,
+    ) -> RigidBodyHandle {
+        let handle = physics.bodies.insert(
+            RigidBodyBuilder::new(body_type)
+                .translation(translation)
+                .build(),
+        );
+        physics.colliders.insert_with_parent(
+            ColliderBuilder::cuboid(1.0, 2.0, 3.0).build(),
+            handle,
+            &mut physics.bodies,
+        );
+        handle
+    }
+
+    fn is_near(first: &glm::Vec3, second: &glm::Vec3) -> bool {
+        glm::distance(first, second) < 0.0001
+    }
+
+    #[test]
+    fn disabled_settings_generate_no_lines() {
+        let mut physics = WorldPhysics::new();
+        insert_body(&mut physics, RigidBodyType::Dynamic, glm::Vec3::zeros());
+
+        let settings = PhysicsDebugSettings {
+            enabled: false,
+            aabbs: true,
+            ..Default::default()
+        };
+
+        assert!(physics.debug_lines(&settings).is_empty());
+    }
+
+    #[test]
+    fn aabb_lines_cover_the_box_edges() {
+        let aabb = AABB::new(Point3::new(-1.0, -2.0, -3.0), Point3::new(1.0, 2.0, 3.0));
+        let mut lines = Vec::new();
+
+        aabb_lines(&aabb, &glm::Vec3::x(), &mut lines);
+
+        assert_eq!(lines.len(), 12);
+        for line in lines.iter() {
+            let edge = line.end - line.start;
+            // Each edge runs along exactly one axis, from a corner of the box
+            assert_eq!(edge.iter().filter(|length| **length != 0.0).count(), 1);
+            assert!(edge.iter().all(|length| *length >= 0.0));
+            for corner in [line.start, line.end].iter() {
+                assert_eq!(corner.abs(), glm::vec3(1.0, 2.0, 3.0));
+            }
+            assert_eq!(line.color, glm::Vec3::x());
+        }
+    }
+
+    #[test]
+    fn aabb_settings_draw_collider_bounds() {
+        let mut physics = WorldPhysics::new();
+        insert_body(
+            &mut physics,
+            RigidBodyType::Dynamic,
+            glm::vec3(10.0, 0.0, 0.0),
+        );
+        let settings = PhysicsDebugSettings {
+            aabbs: true,
+            ..enabled_settings()
+        };
+
+        let lines = physics.debug_lines(&settings);
+
+        assert_eq!(lines.len(), 12);
+        assert!(lines
+            .iter()
+            .flat_map(|line| [line.start, line.end])
+            .all(|point| point.x == 9.0 || point.x == 11.0));
+    }
+
+    #[test]
+    fn collider_lines_are_colored_by_body_type() {
+        let mut physics = WorldPhysics::new();
+        insert_body(&mut physics, RigidBodyType::Fixed, glm::Vec3::zeros());
+        let settings = PhysicsDebugSettings {
+            colliders: true,
+            ..enabled_settings()
+        };
+
+        let lines = physics.debug_lines(&settings);
+
+        assert_eq!(lines.len(), 12);
+        assert!(lines
+            .iter()
+            .all(|line| line.color == glm::Vec3::from(PhysicsDebugSettings::FIXED_COLOR)));
+    }
+
+    #[test]
+    fn collider_lines_follow_the_collider_position() {
+        let mut physics = WorldPhysics::new();
+        insert_body(
+            &mut physics,
+            RigidBodyType::Dynamic,
+            glm::vec3(0.0, 5.0, 0.0),
+        );
+        let settings = PhysicsDebugSettings {
+            colliders: true,
+            ..enabled_settings()
+        };
+
+        let lines = physics.debug_lines(&settings);
+
+        let color = glm::Vec3::from(PhysicsDebugSettings::DYNAMIC_COLOR);
+        assert!(lines.iter().all(|line| line.color == color));
+        assert!(lines
+            .iter()
+            .flat_map(|line| [line.start, line.end])
+            .all(|point| point.y == 3.0 || point.y == 7.0));
+    }
+
+    #[test]
+    fn ball_lines_draw_three_circles() {
+        let mut lines = Vec::new();
+        let ball = ColliderBuilder::ball(2.0).build();
+
+        shape_lines(
+            ball.shape(),
+            &Isometry3::identity(),
+            &glm::Vec3::y(),
+            &mut lines,
+        );
+
+        assert_eq!(lines.len(), 3 * PhysicsDebugSettings::CIRCLE_SEGMENTS);
+        assert!(lines
+            .iter()
+            .all(|line| (glm::length(&line.start) - 2.0).abs() < 0.0001));
+    }
+
+    #[test]
+    fn joint_lines_connect_the_joint_frames() {
+        let mut physics = WorldPhysics::new();
+        let body1 = insert_body(&mut physics, RigidBodyType::Fixed, glm::Vec3::zeros());
+        let body2 = insert_body(
+            &mut physics,
+            RigidBodyType::Dynamic,
+            glm::vec3(0.0, -2.0, 0.0),
+        );
+        let joint: GenericJoint = FixedJointBuilder::new()
+            .local_anchor1(Point3::new(0.0, -1.0, 0.0))
+            .local_anchor2(Point3::new(0.0, 1.0, 0.0))
+            .build()
+            .into();
+        physics.impulse_joints.insert(body1, body2, joint, true);
+        let settings = PhysicsDebugSettings {
+            joints: true,
+            ..enabled_settings()
+        };
+
+        let lines = physics.debug_lines(&settings);
+
+        // Both frames draw three axes, then a line joins their origins
+        assert_eq!(lines.len(), 7);
+        let anchor = glm::vec3(0.0, -1.0, 0.0);
+        assert!(lines[..6].iter().all(|line| is_near(&line.start, &anchor)));
+        let connection = lines[6];
+        assert!(is_near(&connection.start, &anchor));
+        assert!(is_near(&connection.end, &anchor));
+        assert_eq!(
+            connection.color,
+            glm::Vec3::from(PhysicsDebugSettings::JOINT_COLOR)
+        );
+    }
+
+    #[test]
+    fn frame_lines_use_axis_colors() {
+        let mut lines = Vec::new();
+
+        frame_lines(&Isometry3::translation(1.0, 0.0, 0.0), &mut lines);
+
+        assert_eq!(lines.len(), 3);
+        for (line, axis) in lines
+            .iter()
+            .zip([glm::Vec3::x(), glm::Vec3::y(), glm::Vec3::z()].iter())
+        {
+            assert_eq!(line.color, *axis);
+            assert!(is_near(
+                &(line.end - line.start),
+                &(axis * PhysicsDebugSettings::AXIS_LENGTH)
+            ));
+        }
+    }
+}
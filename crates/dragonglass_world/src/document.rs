@@ -0,0 +1,331 @@
+use crate::{
+    deserialize_ecs, read_world_header, serialize_ecs, write_world_header, Animation,
+    AssetGenerations, Ecs, Geometry, Material, Mesh, NodeCache, Sampler, Scene, SdfFont, Texture,
+    TextureFormat, Vertex, World, WorldPhysics, ENTITY_SERIALIZER, WORLD_FILE_VERSION,
+};
+use dragonglass_dependencies::{
+    anyhow::{bail, Context, Result},
+    bincode,
+    legion::serialize::set_entity_serializer,
+    ron::{self, ser::PrettyConfig},
+    serde::{Deserialize, Serialize, Serializer},
+};
+use std::{collections::HashMap, path::Path};
+
+/// The on-disk formats a world can be saved to and loaded from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WorldFormat {
+    /// A single bincode file, using the `.dga` extension
+    Binary,
+    /// A human-readable RON document, using the `.ron` extension,
+    /// with geometry, textures, fonts, and physics state
+    /// stored in a sibling `.bin` blob file.
+    /// The document and blob file share the `.dga` format version,
+    /// and the blob file starts with the same header.
+    Ron,
+}
+
+impl WorldFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("ron") => Self::Ron,
+            _ => Self::Binary,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+struct TextureDescription {
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    sampler: Sampler,
+    mip_levels: u32,
+}
+
+impl TextureDescription {
+    fn new(texture: &Texture) -> Self {
+        Self {
+            format: texture.format,
+            width: texture.width,
+            height: texture.height,
+            sampler: texture.sampler.clone(),
+            mip_levels: texture.mip_levels,
+        }
+    }
+
+    fn into_texture(self, pixels: Vec<u8>) -> Texture {
+        Texture {
+            pixels,
+            format: self.format,
+            width: self.width,
+            height: self.height,
+            sampler: self.sampler,
+            mip_levels: self.mip_levels,
+        }
+    }
+}
+
+// The borrowed and owned variants of the document and blobs
+// must keep their fields in the same order so they share a layout
+
+#[derive(Serialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+struct WorldDocumentRef<'a> {
+    version: u32,
+    blobs: String,
+    #[serde(serialize_with = "serialize_ecs_ref")]
+    ecs: &'a Ecs,
+    scene: &'a Scene,
+    meshes: &'a HashMap<String, Mesh>,
+    materials: &'a [Material],
+    animations: &'a [Animation],
+    textures: Vec<TextureDescription>,
+    hdr_textures: Vec<TextureDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+struct WorldDocument {
+    /// Documents written before they were versioned have no version
+    #[serde(default)]
+    version: u32,
+    blobs: String,
+    #[serde(deserialize_with = "deserialize_ecs")]
+    ecs: Ecs,
+    scene: Scene,
+    meshes: HashMap<String, Mesh>,
+    materials: Vec<Material>,
+    animations: Vec<Animation>,
+    textures: Vec<TextureDescription>,
+    hdr_textures: Vec<TextureDescription>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+struct WorldBlobsRef<'a> {
+    physics: &'a WorldPhysics,
+    vertices: &'a [Vertex],
+    indices: &'a [u32],
+    textures: Vec<&'a [u8]>,
+    hdr_textures: Vec<&'a [u8]>,
+    fonts: &'a HashMap<String, SdfFont>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+struct WorldBlobs {
+    physics: WorldPhysics,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    textures: Vec<Vec<u8>>,
+    hdr_textures: Vec<Vec<u8>>,
+    fonts: HashMap<String, SdfFont>,
+}
+
+fn serialize_ecs_ref<S>(ecs: &&Ecs, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_ecs(ecs, serializer)
+}
+
+impl World {
+    /// Saves the world as a RON document that names components
+    /// by their `COMPONENT_REGISTRY` keys, with bulk data in a blob file
+    pub fn save_ron(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let blobs_path = path.with_extension("bin");
+        let blobs_name = blobs_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("Failed to determine the world blob file name!")?
+            .to_string();
+
+        let document = WorldDocumentRef {
+            version: WORLD_FILE_VERSION,
+            blobs: blobs_name,
+            ecs: &self.ecs,
+            scene: &self.scene,
+            meshes: &self.geometry.meshes,
+            materials: &self.materials,
+            animations: &self.animations,
+            textures: self.textures.iter().map(TextureDescription::new).collect(),
+            hdr_textures: self
+                .hdr_textures
+                .iter()
+                .map(TextureDescription::new)
+                .collect(),
+        };
+        let blobs = WorldBlobsRef {
+            physics: &self.physics,
+            vertices: &self.geometry.vertices,
+            indices: &self.geometry.indices,
+            textures: self.textures.iter().map(|t| t.pixels.as_slice()).collect(),
+            hdr_textures: self
+                .hdr_textures
+                .iter()
+                .map(|t| t.pixels.as_slice())
+                .collect(),
+            fonts: &self.fonts,
+        };
+
+        let (text, bytes) = set_entity_serializer(&*ENTITY_SERIALIZER, || -> Result<_> {
+            let text = ron::ser::to_string_pretty(&document, PrettyConfig::new())
+                .context("Failed to serialize world document!")?;
+            let bytes = bincode::serialize(&blobs).context("Failed to serialize world blobs!")?;
+            Ok((text, bytes))
+        })?;
+
+        std::fs::write(path, text).context("Failed to write world document!")?;
+        std::fs::write(&blobs_path, write_world_header(&bytes))
+            .context("Failed to write world blobs!")?;
+        Ok(())
+    }
+
+    /// Loads a world saved with `save_ron`
+    pub fn from_ron(path: impl AsRef<Path>) -> Result<World> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).context("Failed to read world document!")?;
+
+        let (document, blobs) = set_entity_serializer(&*ENTITY_SERIALIZER, || -> Result<_> {
+            let document: WorldDocument =
+                ron::de::from_str(&text).context("Failed to parse world document!")?;
+            check_document_version("document", document.version)?;
+            let blobs_path = path.with_file_name(&document.blobs);
+            let bytes = std::fs::read(&blobs_path).with_context(|| {
+                format!("Failed to read world blobs from {}!", blobs_path.display())
+            })?;
+            let (version, payload) = read_world_header(&bytes)?;
+            check_document_version("blob file", version)?;
+            let blobs: WorldBlobs =
+                bincode::deserialize(payload).context("Failed to deserialize world blobs!")?;
+            Ok((document, blobs))
+        })?;
+
+        let mut world = World {
+            ecs: document.ecs,
+            physics: blobs.physics,
+            scene: document.scene,
+            animations: document.animations,
+            materials: document.materials,
+            textures: zip_textures(document.textures, blobs.textures)
+                .context("Failed to load world textures!")?,
+            hdr_textures: zip_textures(document.hdr_textures, blobs.hdr_textures)
+                .context("Failed to load world hdr textures!")?,
+            geometry: Geometry {
+                vertices: blobs.vertices,
+                indices: blobs.indices,
+                meshes: document.meshes,
+            },
+            fonts: blobs.fonts,
+            generations: AssetGenerations::default(),
+            node_cache: NodeCache::default(),
+        };
+        world.rebuild_multibody_joints()?;
+        world.rebuild_collider_user_data()?;
+        world.sync_hierarchy()?;
+        Ok(world)
+    }
+}
+
+/// Documents are not migrated, so they must match the current world file version
+fn check_document_version(part: &str, version: u32) -> Result<()> {
+    if version != WORLD_FILE_VERSION {
+        bail!(
+            "World {} version {} is unsupported, only version {} can be loaded!",
+            part,
+            version,
+            WORLD_FILE_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Pairs each texture description with its pixels from the blob file
+fn zip_textures(
+    descriptions: Vec<TextureDescription>,
+    pixels: Vec<Vec<u8>>,
+) -> Result<Vec<Texture>> {
+    if descriptions.len() != pixels.len() {
+        bail!(
+            "The world document describes {} textures, but the blob file has pixels for {}!",
+            descriptions.len(),
+            pixels.len()
+        );
+    }
+    Ok(descriptions
+        .into_iter()
+        .zip(pixels)
+        .map(|(description, pixels)| description.into_texture(pixels))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn description() -> TextureDescription {
+        TextureDescription {
+            format: TextureFormat::R8G8B8A8,
+            width: 1,
+            height: 1,
+            sampler: Sampler::default(),
+            mip_levels: 1,
+        }
+    }
+
+    fn document_path(name: &str) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join("dragonglass_document_tests");
+        std::fs::create_dir_all(&directory)?;
+        Ok(directory.join(name).with_extension("ron"))
+    }
+
+    #[test]
+    fn zip_textures_pairs_descriptions_with_pixels() -> Result<()> {
+        let textures = zip_textures(vec![description()], vec![vec![1, 2, 3, 4]])?;
+
+        assert_eq!(textures.len(), 1);
+        assert_eq!(textures[0].pixels, vec![1, 2, 3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn zip_textures_rejects_missing_pixels() {
+        assert!(zip_textures(vec![description(), description()], vec![vec![0; 4]]).is_err());
+        assert!(zip_textures(Vec::new(), vec![vec![0; 4]]).is_err());
+    }
+
+    #[test]
+    fn documents_round_trip() -> Result<()> {
+        let path = document_path("round_trip")?;
+        let mut world = World::new()?;
+        world.add_default_light()?;
+
+        world.save_ron(&path)?;
+        let loaded = World::from_ron(&path)?;
+
+        assert_eq!(loaded.ecs.len(), world.ecs.len());
+        assert_eq!(loaded.scene.name, world.scene.name);
+        Ok(())
+    }
+
+    #[test]
+    fn blob_files_without_a_header_are_rejected() -> Result<()> {
+        let path = document_path("headerless_blobs")?;
+        World::new()?.save_ron(&path)?;
+        let blobs_path = path.with_extension("bin");
+        let bytes = std::fs::read(&blobs_path)?;
+        let (_, payload) = read_world_header(&bytes)?;
+        std::fs::write(&blobs_path, payload)?;
+
+        assert!(World::from_ron(&path).is_err());
+        Ok(())
+    }
+}
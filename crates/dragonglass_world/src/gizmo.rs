@@ -0,0 +1,855 @@
+use crate::{
+    debug::{arc, box_lines},
+    DebugLine, Transform, Viewport,
+};
+use dragonglass_dependencies::{
+    nalgebra_glm as glm,
+    rapier3d::prelude::Ray,
+    serde::{Deserialize, Serialize},
+};
+use std::f32::consts::PI;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// Whether the gizmo follows the entity's rotation or the world axes.
+/// Scale handles always use the entity's local axes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GizmoSpace {
+    Local,
+    World,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    pub const ALL: [GizmoAxis; 3] = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z];
+
+    pub fn index(&self) -> usize {
+        match self {
+            GizmoAxis::X => 0,
+            GizmoAxis::Y => 1,
+            GizmoAxis::Z => 2,
+        }
+    }
+
+    /// The indices of the two axes perpendicular to this one
+    pub fn others(&self) -> [usize; 2] {
+        match self {
+            GizmoAxis::X => [1, 2],
+            GizmoAxis::Y => [2, 0],
+            GizmoAxis::Z => [0, 1],
+        }
+    }
+
+    pub fn color(&self) -> glm::Vec3 {
+        match self {
+            GizmoAxis::X => glm::vec3(0.9, 0.2, 0.2),
+            GizmoAxis::Y => glm::vec3(0.2, 0.9, 0.2),
+            GizmoAxis::Z => glm::vec3(0.2, 0.4, 0.9),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GizmoHandle {
+    /// Moves or scales along an axis
+    Axis(GizmoAxis),
+    /// Moves within the plane perpendicular to an axis
+    Plane(GizmoAxis),
+    /// Rotates around an axis
+    Ring(GizmoAxis),
+    /// Scales all axes together
+    Uniform,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct GizmoSnapping {
+    pub enabled: bool,
+    /// Moves entities onto multiples of the translation increment,
+    /// rather than by multiples of it from where they started
+    pub to_grid: bool,
+    pub translation: f32,
+    pub rotation_degrees: f32,
+    pub scale: f32,
+}
+
+impl Default for GizmoSnapping {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            to_grid: false,
+            translation: 0.5,
+            rotation_degrees: 15.0,
+            scale: 0.1,
+        }
+    }
+}
+
+/// The gizmo's placement in world space
+#[derive(Debug, Copy, Clone)]
+pub struct GizmoFrame {
+    pub origin: glm::Vec3,
+    pub axes: [glm::Vec3; 3],
+    /// The length of the handles in world units
+    pub scale: f32,
+}
+
+impl GizmoFrame {
+    pub fn new(origin: glm::Vec3, rotation: &glm::Quat, space: GizmoSpace, scale: f32) -> Self {
+        let axes = match space {
+            GizmoSpace::Local => [
+                glm::quat_rotate_vec3(rotation, &glm::Vec3::x()).normalize(),
+                glm::quat_rotate_vec3(rotation, &glm::Vec3::y()).normalize(),
+                glm::quat_rotate_vec3(rotation, &glm::Vec3::z()).normalize(),
+            ],
+            GizmoSpace::World => [glm::Vec3::x(), glm::Vec3::y(), glm::Vec3::z()],
+        };
+        Self {
+            origin,
+            axes,
+            scale,
+        }
+    }
+
+    pub fn axis(&self, axis: GizmoAxis) -> glm::Vec3 {
+        self.axes[axis.index()]
+    }
+}
+
+/// Handles closer to the ray than this fraction of the gizmo scale can be picked
+pub const GIZMO_PICK_TOLERANCE: f32 = 0.08;
+
+/// Where the plane handles sit along their two axes, as fractions of the gizmo scale
+pub const GIZMO_PLANE_RANGE: [f32; 2] = [0.2, 0.45];
+
+/// The size of the uniform scale handle, as a fraction of the gizmo scale
+pub const GIZMO_CENTER_SIZE: f32 = 0.12;
+
+/// The smallest scale the scale handles produce,
+/// since dragging past the gizmo's center would otherwise flip or collapse the entity
+pub const GIZMO_MIN_SCALE: f32 = 0.01;
+
+const HIGHLIGHT_COLOR: [f32; 3] = [1.0, 0.9, 0.1];
+
+/// Returns the parameters of the closest points between a ray and a line,
+/// as (distance along the ray, distance along the line), or None if they are parallel
+pub fn closest_points(
+    ray_origin: &glm::Vec3,
+    ray_direction: &glm::Vec3,
+    line_origin: &glm::Vec3,
+    line_direction: &glm::Vec3,
+) -> Option<(f32, f32)> {
+    let offset = ray_origin - line_origin;
+    let a = ray_direction.dot(ray_direction);
+    let b = ray_direction.dot(line_direction);
+    let c = line_direction.dot(line_direction);
+    let d = ray_direction.dot(&offset);
+    let e = line_direction.dot(&offset);
+    let denominator = a * c - b * b;
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+    let ray_parameter = (b * e - c * d) / denominator;
+    let line_parameter = (a * e - b * d) / denominator;
+    Some((ray_parameter, line_parameter))
+}
+
+/// Returns where a ray hits a plane, if it hits in front of the ray's origin
+pub fn ray_plane_intersection(
+    ray_origin: &glm::Vec3,
+    ray_direction: &glm::Vec3,
+    plane_point: &glm::Vec3,
+    plane_normal: &glm::Vec3,
+) -> Option<glm::Vec3> {
+    let denominator = plane_normal.dot(ray_direction);
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+    let distance = plane_normal.dot(&(plane_point - ray_origin)) / denominator;
+    if distance < 0.0 {
+        return None;
+    }
+    Some(ray_origin + ray_direction * distance)
+}
+
+/// Returns the angle in radians that rotates `from` onto `to` around `normal`
+pub fn signed_angle(from: &glm::Vec3, to: &glm::Vec3, normal: &glm::Vec3) -> f32 {
+    from.cross(to).dot(normal).atan2(from.dot(to))
+}
+
+/// Rounds a value to the nearest multiple of the increment
+pub fn snap(value: f32, increment: f32) -> f32 {
+    if increment > 0.0 {
+        (value / increment).round() * increment
+    } else {
+        value
+    }
+}
+
+/// Finds the handle a ray points at, preferring the handle closest to the ray's origin
+pub fn pick_handle(mode: GizmoMode, frame: &GizmoFrame, ray: &Ray) -> Option<GizmoHandle> {
+    let ray_origin = ray.origin.coords;
+    let ray_direction = ray.dir.normalize();
+    let tolerance = GIZMO_PICK_TOLERANCE * frame.scale;
+    let mut best: Option<(f32, GizmoHandle)> = None;
+    let mut consider = |distance: f32, handle: GizmoHandle| {
+        if best.map(|(best, _)| distance < best).unwrap_or(true) {
+            best = Some((distance, handle));
+        }
+    };
+
+    match mode {
+        GizmoMode::Translate | GizmoMode::Scale => {
+            for axis in GizmoAxis::ALL {
+                let direction = frame.axis(axis);
+                if let Some((ray_parameter, line_parameter)) =
+                    closest_points(&ray_origin, &ray_direction, &frame.origin, &direction)
+                {
+                    let on_ray = ray_origin + ray_direction * ray_parameter;
+                    let on_axis = frame.origin + direction * line_parameter;
+                    if ray_parameter >= 0.0
+                        && (0.0..=frame.scale).contains(&line_parameter)
+                        && glm::distance(&on_ray, &on_axis) <= tolerance
+                    {
+                        consider(ray_parameter, GizmoHandle::Axis(axis));
+                    }
+                }
+            }
+        }
+        GizmoMode::Rotate => {}
+    }
+
+    match mode {
+        GizmoMode::Translate => {
+            for axis in GizmoAxis::ALL {
+                let normal = frame.axis(axis);
+                if let Some(point) =
+                    ray_plane_intersection(&ray_origin, &ray_direction, &frame.origin, &normal)
+                {
+                    let offset = point - frame.origin;
+                    let inside = axis.others().iter().all(|other| {
+                        let coordinate = offset.dot(&frame.axes[*other]) / frame.scale;
+                        (GIZMO_PLANE_RANGE[0]..=GIZMO_PLANE_RANGE[1]).contains(&coordinate)
+                    });
+                    if inside {
+                        consider(glm::distance(&ray_origin, &point), GizmoHandle::Plane(axis));
+                    }
+                }
+            }
+        }
+        GizmoMode::Rotate => {
+            for axis in GizmoAxis::ALL {
+                let normal = frame.axis(axis);
+                if let Some(point) =
+                    ray_plane_intersection(&ray_origin, &ray_direction, &frame.origin, &normal)
+                {
+                    let radius = glm::distance(&point, &frame.origin);
+                    if (radius - frame.scale).abs() <= tolerance {
+                        consider(glm::distance(&ray_origin, &point), GizmoHandle::Ring(axis));
+                    }
+                }
+            }
+        }
+        GizmoMode::Scale => {
+            let to_origin = frame.origin - ray_origin;
+            let ray_parameter = to_origin.dot(&ray_direction);
+            let closest = ray_origin + ray_direction * ray_parameter;
+            if ray_parameter >= 0.0
+                && glm::distance(&closest, &frame.origin) <= GIZMO_CENTER_SIZE * frame.scale
+            {
+                consider(ray_parameter, GizmoHandle::Uniform);
+            }
+        }
+    }
+
+    best.map(|(_, handle)| handle)
+}
+
+/// Appends the gizmo's handles, highlighting the active handle
+pub fn gizmo_lines(
+    mode: GizmoMode,
+    frame: &GizmoFrame,
+    highlighted: Option<GizmoHandle>,
+    lines: &mut Vec<DebugLine>,
+) {
+    let color = |handle: GizmoHandle, axis: GizmoAxis| {
+        if highlighted == Some(handle) {
+            glm::Vec3::from(HIGHLIGHT_COLOR)
+        } else {
+            axis.color()
+        }
+    };
+    let scale = frame.scale;
+    let origin = frame.origin;
+
+    for axis in GizmoAxis::ALL {
+        let direction = frame.axis(axis);
+        let [first, second] = axis.others();
+        let (u, v) = (frame.axes[first], frame.axes[second]);
+        match mode {
+            GizmoMode::Translate => {
+                let color = color(GizmoHandle::Axis(axis), axis);
+                let tip = origin + direction * scale;
+                lines.push(DebugLine::new(origin, tip, color));
+                let base = origin + direction * (scale * 0.85);
+                for side in [u, -u, v, -v] {
+                    lines.push(DebugLine::new(tip, base + side * (scale * 0.05), color));
+                }
+
+                let plane_color = color_for_plane(highlighted, axis);
+                let [near, far] = GIZMO_PLANE_RANGE;
+                let corner = |a: f32, b: f32| origin + (u * a + v * b) * scale;
+                let corners = [
+                    corner(near, near),
+                    corner(far, near),
+                    corner(far, far),
+                    corner(near, far),
+                ];
+                for index in 0..4 {
+                    lines.push(DebugLine::new(
+                        corners[index],
+                        corners[(index + 1) % 4],
+                        plane_color,
+                    ));
+                }
+            }
+            GizmoMode::Rotate => {
+                let color = color(GizmoHandle::Ring(axis), axis);
+                arc(&origin, &u, &v, scale, 0.0, 2.0 * PI, &mut |start, end| {
+                    lines.push(DebugLine::new(start, end, color))
+                });
+            }
+            GizmoMode::Scale => {
+                let color = color(GizmoHandle::Axis(axis), axis);
+                let tip = origin + direction * scale;
+                lines.push(DebugLine::new(origin, tip, color));
+                let half = glm::Vec3::repeat(scale * 0.04);
+                box_lines(&(tip - half), &(tip + half), &mut |start, end| {
+                    lines.push(DebugLine::new(start, end, color))
+                });
+            }
+        }
+    }
+
+    if mode == GizmoMode::Scale {
+        let color = if highlighted == Some(GizmoHandle::Uniform) {
+            glm::Vec3::from(HIGHLIGHT_COLOR)
+        } else {
+            glm::vec3(0.9, 0.9, 0.9)
+        };
+        let half = glm::Vec3::repeat(scale * GIZMO_CENTER_SIZE * 0.5);
+        box_lines(&(origin - half), &(origin + half), &mut |start, end| {
+            lines.push(DebugLine::new(start, end, color))
+        });
+    }
+}
+
+fn color_for_plane(highlighted: Option<GizmoHandle>, axis: GizmoAxis) -> glm::Vec3 {
+    if highlighted == Some(GizmoHandle::Plane(axis)) {
+        glm::Vec3::from(HIGHLIGHT_COLOR)
+    } else {
+        axis.color() * 0.7
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct GizmoDrag {
+    handle: GizmoHandle,
+    frame: GizmoFrame,
+    start_local: Transform,
+    parent: glm::Mat4,
+    /// Where the drag started along an axis handle
+    start_parameter: f32,
+    /// Where the drag started on a plane or ring handle
+    start_point: glm::Vec3,
+    plane_normal: glm::Vec3,
+}
+
+/// Translate, rotate, and scale handles for an entity
+#[derive(Debug, Clone)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    pub snapping: GizmoSnapping,
+    /// The handle length as a fraction of the camera distance,
+    /// which keeps the gizmo the same size on screen
+    pub screen_size: f32,
+    pub hovered: Option<GizmoHandle>,
+    drag: Option<GizmoDrag>,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            snapping: GizmoSnapping::default(),
+            screen_size: 0.15,
+            hovered: None,
+            drag: None,
+        }
+    }
+}
+
+impl Gizmo {
+    /// Places the gizmo on an entity's global transform
+    pub fn frame(&self, global: &Transform, camera_position: &glm::Vec3) -> GizmoFrame {
+        let space = match self.mode {
+            GizmoMode::Scale => GizmoSpace::Local,
+            _ => self.space,
+        };
+        let scale = glm::distance(&global.translation, camera_position) * self.screen_size;
+        GizmoFrame::new(global.translation, &global.rotation, space, scale.max(0.01))
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Updates the hovered handle, which stays fixed while dragging
+    pub fn hover(&mut self, frame: &GizmoFrame, ray: &Ray) {
+        if !self.is_dragging() {
+            self.hovered = pick_handle(self.mode, frame, ray);
+        }
+    }
+
+    /// Starts dragging the handle under the ray,
+    /// returning false if the ray misses the gizmo
+    pub fn begin_drag(
+        &mut self,
+        frame: &GizmoFrame,
+        ray: &Ray,
+        local: Transform,
+        parent: glm::Mat4,
+    ) -> bool {
+        let handle = match pick_handle(self.mode, frame, ray) {
+            Some(handle) => handle,
+            None => return false,
+        };
+        let ray_origin = ray.origin.coords;
+        let ray_direction = ray.dir.normalize();
+        let plane_normal = match handle {
+            GizmoHandle::Plane(axis) | GizmoHandle::Ring(axis) => frame.axis(axis),
+            GizmoHandle::Uniform | GizmoHandle::Axis(_) => -ray_direction,
+        };
+        let (start_parameter, start_point) = match handle {
+            GizmoHandle::Axis(axis) => {
+                match closest_points(
+                    &ray_origin,
+                    &ray_direction,
+                    &frame.origin,
+                    &frame.axis(axis),
+                ) {
+                    Some((_, parameter)) => (parameter, frame.origin),
+                    None => return false,
+                }
+            }
+            _ => match ray_plane_intersection(
+                &ray_origin,
+                &ray_direction,
+                &frame.origin,
+                &plane_normal,
+            ) {
+                Some(point) => (0.0, point),
+                None => return false,
+            },
+        };
+        self.hovered = Some(handle);
+        self.drag = Some(GizmoDrag {
+            handle,
+            frame: *frame,
+            start_local: local,
+            parent,
+            start_parameter,
+            start_point,
+            plane_normal,
+        });
+        true
+    }
+
+    /// Returns the dragged entity's new local transform for the current ray
+    pub fn drag(&self, ray: &Ray) -> Option<Transform> {
+        let drag = self.drag.as_ref()?;
+        let ray_origin = ray.origin.coords;
+        let ray_direction = ray.dir.normalize();
+        let frame = &drag.frame;
+        let snapping = self.snapping.enabled;
+        let mut transform = drag.start_local;
+
+        match (self.mode, drag.handle) {
+            (GizmoMode::Translate, GizmoHandle::Axis(axis)) => {
+                let direction = frame.axis(axis);
+                let (_, parameter) =
+                    closest_points(&ray_origin, &ray_direction, &frame.origin, &direction)?;
+                let mut distance = parameter - drag.start_parameter;
+                if snapping {
+                    distance = self.snap_translation(&frame.origin, &direction, distance);
+                }
+                transform.translation += parent_space(&drag.parent, &(direction * distance));
+            }
+            (GizmoMode::Translate, GizmoHandle::Plane(_)) => {
+                let point = ray_plane_intersection(
+                    &ray_origin,
+                    &ray_direction,
+                    &frame.origin,
+                    &drag.plane_normal,
+                )?;
+                let offset = point - drag.start_point;
+                let mut delta = glm::Vec3::zeros();
+                for direction in frame.axes.iter() {
+                    // The normal's component is zero for points on the plane
+                    let mut distance = offset.dot(direction);
+                    if snapping {
+                        distance = self.snap_translation(&frame.origin, direction, distance);
+                    }
+                    delta += direction * distance;
+                }
+                transform.translation += parent_space(&drag.parent, &delta);
+            }
+            (GizmoMode::Rotate, GizmoHandle::Ring(axis)) => {
+                let point = ray_plane_intersection(
+                    &ray_origin,
+                    &ray_direction,
+                    &frame.origin,
+                    &drag.plane_normal,
+                )?;
+                let normal = frame.axis(axis);
+                let mut angle = signed_angle(
+                    &(drag.start_point - frame.origin),
+                    &(point - frame.origin),
+                    &normal,
+                );
+                if snapping {
+                    angle = snap(angle, self.snapping.rotation_degrees.to_radians());
+                }
+                let parent_rotation = Transform::from(drag.parent).rotation;
+                let global_rotation = parent_rotation * drag.start_local.rotation;
+                let rotated = glm::quat_angle_axis(angle, &normal) * global_rotation;
+                transform.rotation =
+                    glm::quat_normalize(&(glm::quat_inverse(&parent_rotation) * rotated));
+            }
+            (GizmoMode::Scale, GizmoHandle::Axis(axis)) => {
+                let (_, parameter) = closest_points(
+                    &ray_origin,
+                    &ray_direction,
+                    &frame.origin,
+                    &frame.axis(axis),
+                )?;
+                if drag.start_parameter.abs() <= f32::EPSILON {
+                    return None;
+                }
+                let index = axis.index();
+                let mut scale = drag.start_local.scale[index] * parameter / drag.start_parameter;
+                if snapping {
+                    scale = snap(scale, self.snapping.scale);
+                }
+                transform.scale[index] = scale.max(GIZMO_MIN_SCALE);
+            }
+            (GizmoMode::Scale, GizmoHandle::Uniform) => {
+                let point = ray_plane_intersection(
+                    &ray_origin,
+                    &ray_direction,
+                    &frame.origin,
+                    &drag.plane_normal,
+                )?;
+                let start = glm::distance(&drag.start_point, &frame.origin);
+                let current = glm::distance(&point, &frame.origin);
+                let mut factor = (1.0 + (current - start) / frame.scale).max(GIZMO_MIN_SCALE);
+                if snapping {
+                    factor = snap(factor, self.snapping.scale).max(self.snapping.scale);
+                }
+                transform.scale = drag.start_local.scale * factor;
+            }
+            _ => return None,
+        }
+
+        Some(transform)
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Snaps a distance moved from the origin along a direction
+    fn snap_translation(&self, origin: &glm::Vec3, direction: &glm::Vec3, distance: f32) -> f32 {
+        let increment = self.snapping.translation;
+        if self.snapping.to_grid {
+            let start = origin.dot(direction);
+            snap(start + distance, increment) - start
+        } else {
+            snap(distance, increment)
+        }
+    }
+
+    /// Generates the gizmo's line geometry
+    pub fn lines(&self, frame: &GizmoFrame) -> Vec<DebugLine> {
+        let mut lines = Vec::new();
+        let frame = self.drag.as_ref().map(|drag| &drag.frame).unwrap_or(frame);
+        gizmo_lines(self.mode, frame, self.hovered, &mut lines);
+        lines
+    }
+}
+
+/// How far the axes of the orientation gizmo reach from its center,
+/// as a fraction of half the size of the region it is drawn in
+pub const AXIS_GIZMO_LENGTH: f32 = 0.7;
+
+/// How close to the end of an axis a click on the orientation gizmo must be to pick it,
+/// as a fraction of half the size of the region it is drawn in
+pub const AXIS_GIZMO_PICK_RADIUS: f32 = 0.2;
+
+/// The end of an axis on the orientation gizmo,
+/// which shows the world axes in a corner of the viewport and aligns the view to one when clicked
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AxisGizmoHandle {
+    pub axis: GizmoAxis,
+    /// Whether this end is on the positive side of the axis
+    pub positive: bool,
+    /// Where the end appears, in the same units and space as the gizmo's region
+    pub position: glm::Vec2,
+    /// How far the end points towards the camera
+    pub depth: f32,
+}
+
+/// Places the ends of the orientation gizmo's axes for a camera's view matrix.
+/// The ends are sorted from the farthest to the nearest, the order they are drawn in.
+pub fn axis_gizmo_handles(view: &glm::Mat4, region: &Viewport) -> Vec<AxisGizmoHandle> {
+    let rotation = glm::mat4_to_mat3(view);
+    let center = glm::vec2(
+        region.x + region.width / 2.0,
+        region.y + region.height / 2.0,
+    );
+    let length = region.width.min(region.height) / 2.0 * AXIS_GIZMO_LENGTH;
+    let mut handles = Vec::new();
+    for axis in GizmoAxis::ALL {
+        for positive in [true, false] {
+            let mut direction = glm::Vec3::zeros();
+            direction[axis.index()] = if positive { 1.0 } else { -1.0 };
+            let direction = rotation * direction;
+            handles.push(AxisGizmoHandle {
+                axis,
+                positive,
+                // Screen coordinates grow downwards
+                position: center + glm::vec2(direction.x, -direction.y) * length,
+                depth: direction.z,
+            });
+        }
+    }
+    handles.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+    handles
+}
+
+/// Finds the axis end of the orientation gizmo under a point, preferring the nearest end
+pub fn pick_axis_gizmo_handle(
+    view: &glm::Mat4,
+    region: &Viewport,
+    point: &glm::Vec2,
+) -> Option<AxisGizmoHandle> {
+    let radius = region.width.min(region.height) / 2.0 * AXIS_GIZMO_PICK_RADIUS;
+    axis_gizmo_handles(view, region)
+        .into_iter()
+        .rev()
+        .find(|handle| glm::distance(&handle.position, point) <= radius)
+}
+
+/// Generates the orientation gizmo's lines, in a space where the gizmo spans -1 to 1 on each axis.
+/// The negative halves of the axes are drawn darker.
+pub fn axis_gizmo_lines() -> Vec<DebugLine> {
+    let mut lines = Vec::new();
+    for axis in GizmoAxis::ALL {
+        let mut direction = glm::Vec3::zeros();
+        direction[axis.index()] = AXIS_GIZMO_LENGTH;
+        let color = axis.color();
+        lines.push(DebugLine::new(glm::Vec3::zeros(), direction, color));
+        lines.push(DebugLine::new(glm::Vec3::zeros(), -direction, color * 0.4));
+    }
+    lines
+}
+
+/// Converts a world space offset into the parent's space
+fn parent_space(parent: &glm::Mat4, offset: &glm::Vec3) -> glm::Vec3 {
+    let offset = glm::inverse(parent) * glm::vec4(offset.x, offset.y, offset.z, 0.0);
+    glm::vec4_to_vec3(&offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    /// A ray looking down the negative z axis through the point
+    fn ray_down(x: f32, y: f32) -> Ray {
+        Ray::new(glm::vec3(x, y, 10.0).into(), -glm::Vec3::z())
+    }
+
+    fn gizmo(mode: GizmoMode, snapping: bool) -> Gizmo {
+        Gizmo {
+            mode,
+            snapping: GizmoSnapping {
+                enabled: snapping,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn frame_at(origin: glm::Vec3, space: GizmoSpace) -> GizmoFrame {
+        GizmoFrame::new(origin, &glm::Quat::identity(), space, 1.0)
+    }
+
+    /// An untransformed entity, since the default transform is turned to face the camera
+    fn unrotated() -> Transform {
+        Transform {
+            rotation: glm::Quat::identity(),
+            ..Default::default()
+        }
+    }
+
+    fn drag(gizmo: &mut Gizmo, local: Transform, start: &Ray, end: &Ray) -> Transform {
+        let frame = frame_at(local.translation, gizmo.space);
+        assert!(gizmo.begin_drag(&frame, start, local, glm::Mat4::identity()));
+        gizmo.drag(end).expect("Failed to drag the gizmo!")
+    }
+
+    fn assert_close(actual: &glm::Vec3, expected: &glm::Vec3) {
+        assert!(
+            glm::distance(actual, expected) <= EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn translate_axis_moves_along_the_axis() {
+        let mut gizmo = gizmo(GizmoMode::Translate, false);
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(0.5, 0.0),
+            &ray_down(1.25, 0.3),
+        );
+        assert_close(&transform.translation, &glm::vec3(0.75, 0.0, 0.0));
+    }
+
+    #[test]
+    fn translate_axis_snaps_the_distance_moved() {
+        let mut gizmo = gizmo(GizmoMode::Translate, true);
+        let start = Transform {
+            translation: glm::vec3(0.2, 0.0, 0.0),
+            ..unrotated()
+        };
+        let transform = drag(&mut gizmo, start, &ray_down(0.7, 0.0), &ray_down(1.45, 0.0));
+        assert_close(&transform.translation, &glm::vec3(1.2, 0.0, 0.0));
+    }
+
+    #[test]
+    fn translate_axis_snaps_to_the_grid() {
+        let mut gizmo = gizmo(GizmoMode::Translate, true);
+        gizmo.snapping.to_grid = true;
+        let start = Transform {
+            translation: glm::vec3(0.2, 0.0, 0.0),
+            ..unrotated()
+        };
+        let transform = drag(&mut gizmo, start, &ray_down(0.7, 0.0), &ray_down(1.45, 0.0));
+        assert_close(&transform.translation, &glm::vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn translate_plane_moves_within_the_plane() {
+        let mut gizmo = gizmo(GizmoMode::Translate, false);
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(0.3, 0.3),
+            &ray_down(1.0, -0.2),
+        );
+        assert_close(&transform.translation, &glm::vec3(0.7, -0.5, 0.0));
+    }
+
+    #[test]
+    fn rotate_ring_rotates_around_the_axis() {
+        let mut gizmo = gizmo(GizmoMode::Rotate, false);
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(1.0, 0.0),
+            &ray_down(0.0, 1.0),
+        );
+        let expected = glm::quat_angle_axis(90_f32.to_radians(), &glm::Vec3::z());
+        assert!((transform.rotation.coords - expected.coords).norm() <= EPSILON);
+    }
+
+    #[test]
+    fn rotate_ring_snaps_the_angle() {
+        let mut gizmo = gizmo(GizmoMode::Rotate, true);
+        let angle = 50_f32.to_radians();
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(1.0, 0.0),
+            &ray_down(angle.cos(), angle.sin()),
+        );
+        let expected = glm::quat_angle_axis(45_f32.to_radians(), &glm::Vec3::z());
+        assert!((transform.rotation.coords - expected.coords).norm() <= EPSILON);
+    }
+
+    #[test]
+    fn scale_axis_scales_by_the_distance_ratio() {
+        let mut gizmo = gizmo(GizmoMode::Scale, false);
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(0.5, 0.0),
+            &ray_down(1.0, 0.0),
+        );
+        assert_close(&transform.scale, &glm::vec3(2.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn scale_axis_snaps_the_scale() {
+        let mut gizmo = gizmo(GizmoMode::Scale, true);
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(0.5, 0.0),
+            &ray_down(0.76, 0.0),
+        );
+        assert_close(&transform.scale, &glm::vec3(1.5, 1.0, 1.0));
+    }
+
+    #[test]
+    fn scale_axis_stops_at_the_minimum_scale() {
+        let mut gizmo = gizmo(GizmoMode::Scale, false);
+        let transform = drag(
+            &mut gizmo,
+            unrotated(),
+            &ray_down(0.5, 0.0),
+            &ray_down(-0.5, 0.0),
+        );
+        assert_close(&transform.scale, &glm::vec3(GIZMO_MIN_SCALE, 1.0, 1.0));
+    }
+
+    #[test]
+    fn uniform_scale_snaps_the_factor() {
+        let mut gizmo = gizmo(GizmoMode::Scale, true);
+        // Looking down the negative x axis, just off the y and z handles
+        let offset = 0.0825;
+        let start = Ray::new(glm::vec3(10.0, offset, offset).into(), -glm::Vec3::x());
+        let end = Ray::new(glm::vec3(10.0, 0.5, offset).into(), -glm::Vec3::x());
+        let transform = drag(&mut gizmo, unrotated(), &start, &end);
+        assert_close(&transform.scale, &glm::vec3(1.4, 1.4, 1.4));
+    }
+}
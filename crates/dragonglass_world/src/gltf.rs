@@ -10,9 +10,75 @@ use dragonglass_dependencies::{
     legion::EntityStore,
     nalgebra_glm as glm,
     petgraph::prelude::*,
+    rapier3d::prelude::{ColliderBuilder, InteractionGroups, RigidBodyType},
 };
 use std::path::Path;
 
+/// The axis a file treats as up. Worlds are y-up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UpAxis {
+    Y,
+    Z,
+}
+
+/// The colliders generated for imported meshes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImportColliders {
+    None,
+    Trimesh,
+    BoundingBox,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ImportOptions {
+    /// A uniform scale converting the file's units into world units
+    pub scale: f32,
+    pub up_axis: UpAxis,
+    /// Parents every imported root under a single entity named after the file,
+    /// instead of adding each root to the scene separately
+    pub merge: bool,
+    pub colliders: ImportColliders,
+    /// The rigid body given to each imported mesh.
+    /// Colliders need a body to attach to,
+    /// so meshes get a fixed body when colliders are generated without one.
+    pub rigid_body: Option<RigidBodyType>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            up_axis: UpAxis::Y,
+            merge: false,
+            colliders: ImportColliders::None,
+            rigid_body: None,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// The transform that converts the file's units and up axis into the world's
+    pub fn root_transform(&self) -> Transform {
+        let rotation = match self.up_axis {
+            UpAxis::Y => glm::quat_identity(),
+            UpAxis::Z => glm::quat_angle_axis(-90_f32.to_radians(), &glm::Vec3::x()),
+        };
+        Transform::new(
+            glm::Vec3::zeros(),
+            rotation,
+            glm::vec3(self.scale, self.scale, self.scale),
+        )
+    }
+
+    fn body_type(&self) -> Option<RigidBodyType> {
+        match (self.rigid_body, self.colliders) {
+            (None, ImportColliders::None) => None,
+            (None, _) => Some(RigidBodyType::Fixed),
+            (Some(body_type), _) => Some(body_type),
+        }
+    }
+}
+
 pub fn create_scene_graph(node: &gltf::Node, ecs: &mut Ecs, entities: &[Entity]) -> SceneGraph {
     let mut node_graph = SceneGraph::new();
     graph_node(&mut node_graph, node, NodeIndex::new(0), ecs, entities);
@@ -49,6 +115,17 @@ fn node_transform(node: &gltf::Node) -> Transform {
 const DEFAULT_NAME: &str = "<Unnamed>";
 
 pub fn load_gltf(path: impl AsRef<Path>, world: &mut World) -> Result<()> {
+    import_gltf(path, world, &ImportOptions::default())?;
+    Ok(())
+}
+
+/// Appends a gltf file's default scene to the world, returning the imported root entities
+pub fn import_gltf(
+    path: impl AsRef<Path>,
+    world: &mut World,
+    options: &ImportOptions,
+) -> Result<Vec<Entity>> {
+    let path = path.as_ref();
     let (gltf, buffers, images) = gltf::import(path)?;
 
     let number_of_materials = world.materials.len();
@@ -71,9 +148,11 @@ pub fn load_gltf(path: impl AsRef<Path>, world: &mut World) -> Result<()> {
         .into_iter()
         .for_each(|material| world.materials.push(material));
 
-    load_textures(&gltf, &images)?
-        .into_iter()
-        .for_each(|texture| world.textures.push(texture));
+    // Touched so renderers upload them even where an index was used by an earlier texture
+    for texture in load_textures(&gltf, &images)?.into_iter() {
+        world.generations.touch_texture(world.textures.len());
+        world.textures.push(texture);
+    }
 
     let entities = world
         .ecs
@@ -92,24 +171,99 @@ pub fn load_gltf(path: impl AsRef<Path>, world: &mut World) -> Result<()> {
         &entities,
     )?;
 
+    let mut mesh_entities = Vec::new();
     for entity in entities.iter() {
-        if let Ok(mesh) = world.ecs.entry_mut(*entity)?.get_component_mut::<Mesh>() {
+        let mesh_name = match world.ecs.entry_ref(*entity)?.get_component::<MeshRender>() {
+            Ok(mesh_render) => mesh_render.name.to_string(),
+            Err(_) => continue,
+        };
+        world.generations.touch_mesh(&mesh_name);
+        if let Some(mesh) = world.geometry.meshes.get_mut(&mesh_name) {
             mesh.primitives.iter_mut().for_each(|primitive| {
                 if let Some(material_index) = primitive.material_index.as_mut() {
                     *material_index += number_of_materials
                 }
             })
         }
+        mesh_entities.push(*entity);
     }
 
     // Only merge default scene
+    let mut roots = Vec::new();
     let new_scenes = load_scenes(&gltf, &mut world.ecs, &entities);
     if let Some(new_scene) = new_scenes.into_iter().next() {
-        new_scene.graphs.into_iter().for_each(|graph| {
+        if options.merge {
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(DEFAULT_NAME)
+                .to_string();
+            let root = world.ecs.push((Name(name), options.root_transform()));
+            let mut graph = SceneGraph::new();
+            let root_index = graph.add_node(root);
+            let scene = gltf.scenes().next().context("Failed to find a scene!")?;
+            for node in scene.nodes() {
+                graph_node(&mut graph, &node, root_index, &mut world.ecs, &entities);
+            }
             world.scene.graphs.push(graph);
-        });
+            roots.push(root);
+        } else {
+            for graph in new_scene.graphs.into_iter() {
+                for root_index in graph.roots() {
+                    let root = graph[root_index];
+                    let mut entry = world.ecs.entry_mut(root)?;
+                    if let Ok(transform) = entry.get_component_mut::<Transform>() {
+                        *transform =
+                            Transform::from(options.root_transform().matrix() * transform.matrix());
+                    }
+                    roots.push(root);
+                }
+                world.scene.graphs.push(graph);
+            }
+        }
+    }
+    world.sync_hierarchy()?;
+
+    if let Some(body_type) = options.body_type() {
+        for entity in mesh_entities.into_iter() {
+            if world.entity_node(entity).is_none() {
+                continue;
+            }
+            world.add_rigid_body(entity, body_type)?;
+            add_import_collider(world, entity, options.colliders)?;
+        }
     }
 
+    Ok(roots)
+}
+
+fn add_import_collider(
+    world: &mut World,
+    entity: Entity,
+    colliders: ImportColliders,
+) -> Result<()> {
+    match colliders {
+        ImportColliders::None => {}
+        ImportColliders::Trimesh => world.add_trimesh_collider(entity, InteractionGroups::all())?,
+        ImportColliders::BoundingBox => {
+            let bounding_box = {
+                let entry = world.ecs.entry_ref(entity)?;
+                let mesh_render = entry.get_component::<MeshRender>()?;
+                world
+                    .geometry
+                    .meshes
+                    .get(&mesh_render.name)
+                    .context("Failed to find mesh!")?
+                    .bounding_box()
+            };
+            let half_extents = bounding_box.half_extents();
+            let collider = ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z)
+                .translation(bounding_box.center())
+                .collision_groups(InteractionGroups::all())
+                .build();
+            world.attach_collider(entity, collider)?;
+        }
+    }
     Ok(())
 }
 
@@ -291,16 +445,15 @@ fn load_nodes(
         }
 
         if let Some(gltf_mesh) = node.mesh() {
-            let mesh = load_mesh(&gltf_mesh, buffers, geometry)?;
-            let name = if geometry.meshes.contains_key(&mesh.name) {
-                // FIXME: increment a repeated name with a number
-                //        instead of just adding an underscore
-                let name = mesh.name.to_string();
-                name + "_"
-            } else {
-                mesh.name.to_string()
-            };
-            if geometry.meshes.insert(name.clone(), mesh).is_some() {}
+            let mut mesh = load_mesh(&gltf_mesh, buffers, geometry)?;
+            let mut name = mesh.name.to_string();
+            let mut suffix = 1;
+            while geometry.meshes.contains_key(&name) {
+                name = format!("{}_{}", mesh.name, suffix);
+                suffix += 1;
+            }
+            mesh.name = name.to_string();
+            geometry.meshes.insert(name.to_string(), mesh);
             entry.add_component(MeshRender { name });
         }
 
@@ -0,0 +1,674 @@
+use crate::{Entity, Name, PhysicsJoint, Ragdoll, RigidBody, Skin, Transform, World};
+use dragonglass_dependencies::{
+    anyhow::{bail, Context, Result},
+    legion::{EntityStore, IntoQuery},
+    nalgebra_glm as glm,
+    petgraph::graph::NodeIndex,
+    rapier3d::{dynamics::RigidBodyType, geometry::Collider},
+    serde::{Deserialize, Serialize},
+};
+use std::{cell::RefCell, collections::HashMap, fmt};
+
+/// The entity's parent in the scenegraph
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct Parent(pub Entity);
+
+/// The entity's children in the scenegraph, in sibling order
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct Children(pub Vec<Entity>);
+
+/// A rigid body's type and unscaled colliders
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct RigidBodySnapshot {
+    pub body_type: RigidBodyType,
+    pub colliders: Vec<Collider>,
+}
+
+impl fmt::Debug for RigidBodySnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RigidBodySnapshot")
+            .field("body_type", &self.body_type)
+            .field("colliders", &self.colliders.len())
+            .finish()
+    }
+}
+
+/// Where each entity's scenegraph node was last found.
+/// Graphs can be edited directly, so an entry is only trusted while its node still holds the entity.
+#[derive(Default, Debug)]
+pub struct NodeCache(RefCell<HashMap<Entity, (usize, NodeIndex)>>);
+
+impl World {
+    /// Components that are not copied when duplicating or restoring entities.
+    /// The hierarchy and rigid bodies are rebuilt for the new entities,
+    /// while joints and ragdolls would share the source's physics handles.
+    pub(crate) const REBUILT_COMPONENTS: [&'static str; 5] = [
+        "parent",
+        "children",
+        "rigid_body",
+        "physics_joint",
+        "ragdoll",
+    ];
+
+    /// Returns the index of the scenegraph containing the entity and the entity's node
+    pub fn entity_node(&self, entity: Entity) -> Option<(usize, NodeIndex)> {
+        let mut cache = self.node_cache.0.borrow_mut();
+        if let Some((graph_index, node_index)) = cache.get(&entity).copied() {
+            let node = self
+                .scene
+                .graphs
+                .get(graph_index)
+                .and_then(|graph| graph.0.node_weight(node_index));
+            if node == Some(&entity) {
+                return Some((graph_index, node_index));
+            }
+        }
+        let found = self
+            .scene
+            .graphs
+            .iter()
+            .enumerate()
+            .find_map(|(graph_index, graph)| Some((graph_index, graph.find_node(entity)?)));
+        match found {
+            Some(found) => cache.insert(entity, found),
+            None => cache.remove(&entity),
+        };
+        found
+    }
+
+    /// Returns the children of an entity in sibling order
+    pub fn children(&self, entity: Entity) -> Vec<Entity> {
+        match self.entity_node(entity) {
+            Some((graph_index, node_index)) => {
+                let graph = &self.scene.graphs[graph_index];
+                graph
+                    .children(node_index)
+                    .into_iter()
+                    .map(|child| graph[child])
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the entity and all of its descendants, parents before children
+    pub fn descendants(&self, entity: Entity) -> Vec<Entity> {
+        match self.entity_node(entity) {
+            Some((graph_index, node_index)) => {
+                let graph = &self.scene.graphs[graph_index];
+                graph
+                    .descendants(node_index)
+                    .into_iter()
+                    .map(|index| graph[index])
+                    .collect()
+            }
+            None => vec![entity],
+        }
+    }
+
+    /// Creates an unnamed entity as the last child of the parent,
+    /// or as a root of the default scenegraph if no parent is given
+    pub(crate) fn spawn_entity(&mut self, parent: Option<Entity>) -> Result<Entity> {
+        match parent {
+            Some(parent) => self.spawn_child(parent, ""),
+            None => {
+                let entity = self.ecs.push((Transform::default(),));
+                self.scene.default_scenegraph_mut()?.add_node(entity);
+                Ok(entity)
+            }
+        }
+    }
+
+    /// Creates a new entity as the last child of the parent
+    pub fn spawn_child(&mut self, parent: Entity, name: &str) -> Result<Entity> {
+        let (graph_index, parent_index) = self
+            .entity_node(parent)
+            .context("Failed to spawn child, the parent is not in the scene!")?;
+        let entity = self
+            .ecs
+            .push((Transform::default(), Name(name.to_string())));
+        let graph = &mut self.scene.graphs[graph_index];
+        let node_index = graph.add_node(entity);
+        graph.add_edge(parent_index, node_index);
+        self.sync_hierarchy_components(parent)?;
+        self.sync_hierarchy_components(entity)?;
+        Ok(entity)
+    }
+
+    /// Removes an entity and its descendants from the scene and the ecs,
+    /// along with their rigid bodies, colliders, joints, and ragdolls
+    pub fn despawn(&mut self, entity: Entity) -> Result<()> {
+        let parent = self.parent_entity(entity);
+        let entities = match self.entity_node(entity) {
+            Some((graph_index, node_index)) => {
+                self.scene.graphs[graph_index].remove_subtree(node_index)
+            }
+            None => vec![entity],
+        };
+
+        for entity in entities.iter() {
+            self.remove_physics(*entity)?;
+        }
+
+        // Joints on surviving entities that target a despawned entity were
+        // removed with the target's rigid body, so their components are stale
+        let mut query = <(Entity, &PhysicsJoint)>::query();
+        let dangling_joints = query
+            .iter(&self.ecs)
+            .filter(|(_, joint)| entities.contains(&joint.target))
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+        for entity in dangling_joints.into_iter() {
+            if let Some(mut entry) = self.ecs.entry(entity) {
+                entry.remove_component::<PhysicsJoint>();
+            }
+        }
+
+        for entity in entities.into_iter() {
+            self.physics.release_entity_user_data(entity);
+            self.ecs.remove(entity);
+        }
+
+        if let Some(parent) = parent {
+            self.sync_hierarchy_components(parent)?;
+        }
+        Ok(())
+    }
+
+    fn remove_physics(&mut self, entity: Entity) -> Result<()> {
+        let (has_joint, has_ragdoll, has_rigid_body) = match self.ecs.entry_ref(entity) {
+            Ok(entry) => (
+                entry.get_component::<PhysicsJoint>().is_ok(),
+                entry.get_component::<Ragdoll>().is_ok(),
+                entry.get_component::<RigidBody>().is_ok(),
+            ),
+            Err(_) => return Ok(()),
+        };
+        if has_joint {
+            self.remove_joint(entity)?;
+        }
+        if has_ragdoll {
+            self.remove_ragdoll(entity)?;
+        }
+        if has_rigid_body {
+            self.remove_rigid_body(entity)?;
+        }
+        Ok(())
+    }
+
+    /// Moves an entity and its descendants under a new parent,
+    /// or to the root of its scenegraph if no parent is given.
+    /// When `keep_world_transform` is set the local transform is adjusted
+    /// so the entity does not move in world space.
+    pub fn reparent(
+        &mut self,
+        entity: Entity,
+        new_parent: Option<Entity>,
+        keep_world_transform: bool,
+    ) -> Result<()> {
+        let (graph_index, node_index) = self
+            .entity_node(entity)
+            .context("Failed to reparent, the entity is not in the scene!")?;
+        let global_matrix = self.entity_global_transform_matrix(entity)?;
+        let old_parent = self.parent_entity(entity);
+
+        match new_parent {
+            Some(new_parent) => {
+                let (parent_graph_index, parent_index) = self
+                    .entity_node(new_parent)
+                    .context("Failed to reparent, the new parent is not in the scene!")?;
+                if parent_graph_index == graph_index {
+                    self.scene.graphs[graph_index].set_parent(node_index, Some(parent_index))?;
+                } else {
+                    let node_index =
+                        self.move_subtree(graph_index, node_index, parent_graph_index)?;
+                    self.scene.graphs[parent_graph_index]
+                        .set_parent(node_index, Some(parent_index))?;
+                }
+            }
+            None => self.scene.graphs[graph_index].set_parent(node_index, None)?,
+        }
+
+        for affected in old_parent.iter().chain(new_parent.iter()) {
+            self.sync_hierarchy_components(*affected)?;
+        }
+        self.sync_hierarchy_components(entity)?;
+
+        if keep_world_transform {
+            let parent_matrix = match new_parent {
+                Some(new_parent) => self.entity_global_transform_matrix(new_parent)?,
+                None => glm::Mat4::identity(),
+            };
+            let local = Transform::from(glm::inverse(&parent_matrix) * global_matrix);
+            let mut entry = self.ecs.entry_mut(entity)?;
+            let transform = entry.get_component_mut::<Transform>()?;
+            transform.translation = local.translation;
+            transform.rotation = glm::quat_normalize(&local.rotation);
+            transform.scale = local.scale;
+        }
+
+        self.sync_descendant_rigid_bodies(entity)
+    }
+
+    /// Moves a node and its descendants into another scenegraph,
+    /// returning the node's index in the destination graph
+    pub(crate) fn move_subtree(
+        &mut self,
+        source_graph_index: usize,
+        node_index: NodeIndex,
+        destination_graph_index: usize,
+    ) -> Result<NodeIndex> {
+        let (nodes, edges) = {
+            let source = &self.scene.graphs[source_graph_index];
+            let nodes = source.descendants(node_index);
+            let edges = nodes
+                .iter()
+                .flat_map(|parent| {
+                    source
+                        .children(*parent)
+                        .into_iter()
+                        .map(move |child| (*parent, child))
+                })
+                .collect::<Vec<_>>();
+            let nodes = nodes
+                .into_iter()
+                .map(|index| (index, source[index]))
+                .collect::<Vec<_>>();
+            (nodes, edges)
+        };
+
+        self.scene.graphs[source_graph_index].remove_subtree(node_index);
+
+        let destination = &mut self.scene.graphs[destination_graph_index];
+        let mut mapping = HashMap::new();
+        for (index, entity) in nodes.into_iter() {
+            mapping.insert(index, destination.add_node(entity));
+        }
+        for (parent, child) in edges.into_iter() {
+            destination.add_edge(mapping[&parent], mapping[&child]);
+        }
+        mapping
+            .get(&node_index)
+            .copied()
+            .context("Failed to move subtree between scenegraphs!")
+    }
+
+    /// Moves an entity to a new position among its siblings
+    pub fn set_sibling_index(&mut self, entity: Entity, sibling_index: usize) -> Result<()> {
+        let (graph_index, node_index) = self
+            .entity_node(entity)
+            .context("Failed to reorder, the entity is not in the scene!")?;
+        self.scene.graphs[graph_index].set_sibling_index(node_index, sibling_index)?;
+        if let Some(parent) = self.parent_entity(entity) {
+            self.sync_hierarchy_components(parent)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a copy of an entity and its descendants as the entity's next sibling.
+    /// Registered components are deep-copied, skins are retargeted to the copied joints,
+    /// and rigid bodies are recreated with copies of their colliders.
+    /// Joints and ragdolls are not duplicated.
+    pub fn duplicate(&mut self, entity: Entity) -> Result<Entity> {
+        let (graph_index, node_index) = self
+            .entity_node(entity)
+            .context("Failed to duplicate, the entity is not in the scene!")?;
+
+        let source_nodes = self.scene.graphs[graph_index].descendants(node_index);
+        let mut mapping = HashMap::new();
+        let mut entity_mapping = HashMap::new();
+        for source_index in source_nodes.iter() {
+            let source_entity = self.scene.graphs[graph_index][*source_index];
+            let copy = self.duplicate_components(source_entity)?;
+            mapping.insert(*source_index, copy);
+            entity_mapping.insert(source_entity, copy);
+        }
+
+        let graph = &mut self.scene.graphs[graph_index];
+        let mut node_mapping = HashMap::new();
+        for source_index in source_nodes.iter() {
+            node_mapping.insert(*source_index, graph.add_node(mapping[source_index]));
+        }
+        for source_index in source_nodes.iter() {
+            let parent = match graph.parent_of(*source_index) {
+                Some(parent) if *source_index != node_index => node_mapping[&parent],
+                Some(parent) => parent,
+                None => continue,
+            };
+            graph.add_edge(parent, node_mapping[source_index]);
+        }
+
+        let copy_node = node_mapping[&node_index];
+        if let Some(parent) = graph.parent_of(node_index) {
+            let sibling_index = graph
+                .children(parent)
+                .iter()
+                .position(|child| *child == node_index)
+                .map(|position| position + 1)
+                .unwrap_or_default();
+            graph.set_sibling_index(copy_node, sibling_index)?;
+        }
+
+        for source_index in source_nodes.iter() {
+            let source_entity = self.scene.graphs[graph_index][*source_index];
+            self.duplicate_rigid_body(source_entity, mapping[source_index])?;
+            self.retarget_skin(mapping[source_index], &entity_mapping)?;
+            self.sync_hierarchy_components(mapping[source_index])?;
+        }
+        if let Some(parent) = self.parent_entity(entity) {
+            self.sync_hierarchy_components(parent)?;
+        }
+        Ok(mapping[&node_index])
+    }
+
+    /// Copies every registered component through its serialized value,
+    /// except for the components that are rebuilt for the copy
+    fn duplicate_components(&mut self, entity: Entity) -> Result<Entity> {
+        let mut components = self.component_values(entity)?;
+        for key in Self::REBUILT_COMPONENTS.iter() {
+            components.remove(*key);
+        }
+        let copy = self.ecs.push((Transform::default(),));
+        for (key, component) in components.into_iter() {
+            self.set_component_value(copy, &key, Some(component))?;
+        }
+        Ok(copy)
+    }
+
+    /// Points a copied skin at the copies of its joints,
+    /// leaving joints outside of the duplicated entities untouched
+    fn retarget_skin(&mut self, copy: Entity, mapping: &HashMap<Entity, Entity>) -> Result<()> {
+        let mut entry = self.ecs.entry_mut(copy)?;
+        if let Ok(skin) = entry.get_component_mut::<Skin>() {
+            for joint in skin.joints.iter_mut() {
+                if let Some(target) = mapping.get(&joint.target) {
+                    joint.target = *target;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn duplicate_rigid_body(&mut self, source: Entity, copy: Entity) -> Result<()> {
+        match self.rigid_body_snapshot(source)? {
+            Some(snapshot) => self.restore_rigid_body(copy, &snapshot),
+            None => Ok(()),
+        }
+    }
+
+    /// Copies the entity's rigid body type and colliders, if it has a rigid body
+    pub fn rigid_body_snapshot(&self, entity: Entity) -> Result<Option<RigidBodySnapshot>> {
+        let (handle, collider_handles) =
+            match self.ecs.entry_ref(entity)?.get_component::<RigidBody>() {
+                Ok(rigid_body) => (rigid_body.handle, rigid_body.colliders.clone()),
+                Err(_) => return Ok(None),
+            };
+        let body_type = match self.physics.bodies.get(handle) {
+            Some(body) => body.body_type(),
+            None => bail!("Failed to copy rigid body, the body no longer exists!"),
+        };
+        let mut colliders = Vec::new();
+        for collider_handle in collider_handles.into_iter() {
+            let mut collider = match self.physics.colliders.get(collider_handle) {
+                Some(collider) => collider.clone(),
+                None => continue,
+            };
+            // Restore the unscaled shape so the copy is scaled only once when attached
+            if let Some(base) = self.physics.collider_bases.get(&collider_handle) {
+                collider.set_shape(base.shape.clone());
+                if let Some(mut position) = collider.position_wrt_parent().copied() {
+                    position.translation.vector = base.translation;
+                    collider.set_position_wrt_parent(position);
+                }
+            }
+            colliders.push(collider);
+        }
+        Ok(Some(RigidBodySnapshot {
+            body_type,
+            colliders,
+        }))
+    }
+
+    /// Gives the entity a new rigid body with copies of the snapshot's colliders
+    pub fn restore_rigid_body(
+        &mut self,
+        entity: Entity,
+        snapshot: &RigidBodySnapshot,
+    ) -> Result<()> {
+        self.add_rigid_body(entity, snapshot.body_type)?;
+        for collider in snapshot.colliders.iter() {
+            self.attach_collider(entity, collider.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the parent and children components of every entity in the scene.
+    /// Call this after editing a `SceneGraph` directly.
+    pub fn sync_hierarchy(&mut self) -> Result<()> {
+        let mut relationships = Vec::new();
+        for graph in self.scene.graphs.iter() {
+            for node_index in graph.0.node_indices() {
+                let parent = graph.parent_of(node_index).map(|parent| graph[parent]);
+                let children = graph
+                    .children(node_index)
+                    .into_iter()
+                    .map(|child| graph[child])
+                    .collect::<Vec<_>>();
+                relationships.push((graph[node_index], parent, children));
+            }
+        }
+        for (entity, parent, children) in relationships.into_iter() {
+            self.set_hierarchy_components(entity, parent, children);
+        }
+        Ok(())
+    }
+
+    fn sync_hierarchy_components(&mut self, entity: Entity) -> Result<()> {
+        let (parent, children) = match self.entity_node(entity) {
+            Some((graph_index, node_index)) => {
+                let graph = &self.scene.graphs[graph_index];
+                let parent = graph.parent_of(node_index).map(|parent| graph[parent]);
+                let children = graph
+                    .children(node_index)
+                    .into_iter()
+                    .map(|child| graph[child])
+                    .collect::<Vec<_>>();
+                (parent, children)
+            }
+            None => (None, Vec::new()),
+        };
+        self.set_hierarchy_components(entity, parent, children);
+        Ok(())
+    }
+
+    fn set_hierarchy_components(
+        &mut self,
+        entity: Entity,
+        parent: Option<Entity>,
+        children: Vec<Entity>,
+    ) {
+        let mut entry = match self.ecs.entry(entity) {
+            Some(entry) => entry,
+            None => return,
+        };
+        match parent {
+            Some(parent) => entry.add_component(Parent(parent)),
+            None => entry.remove_component::<Parent>(),
+        }
+        if children.is_empty() {
+            entry.remove_component::<Children>();
+        } else {
+            entry.add_component(Children(children));
+        }
+    }
+
+    pub(crate) fn sync_descendant_rigid_bodies(&mut self, entity: Entity) -> Result<()> {
+        for entity in self.descendants(entity) {
+            let has_rigid_body = self
+                .ecs
+                .entry_ref(entity)?
+                .get_component::<RigidBody>()
+                .is_ok();
+            if has_rigid_body {
+                self.sync_rigid_body_to_transform(entity)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{register_component, testing::spawn_root, Joint, SceneGraph};
+    use dragonglass_dependencies::rapier3d::geometry::ColliderBuilder;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(crate = "dragonglass_dependencies::serde")]
+    struct Health(u32);
+
+    fn children_names(world: &World, entity: Entity) -> Result<Vec<String>> {
+        world
+            .children(entity)
+            .into_iter()
+            .map(|child| {
+                Ok(world
+                    .ecs
+                    .entry_ref(child)?
+                    .get_component::<Name>()?
+                    .0
+                    .clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn entity_node_follows_graph_edits() -> Result<()> {
+        let mut world = World::new()?;
+        let root = spawn_root(&mut world, "Root")?;
+        let child = world.spawn_child(root, "Child")?;
+        assert!(world.entity_node(child).is_some());
+
+        world.despawn(root)?;
+        let respawned = spawn_root(&mut world, "Respawned")?;
+        assert!(world.entity_node(child).is_none());
+
+        // Nodes can also be moved without going through the world
+        let (_, node_index) = world.entity_node(respawned).unwrap();
+        world.scene.graphs.insert(0, SceneGraph::default());
+        assert_eq!(world.entity_node(respawned), Some((1, node_index)));
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_copies_registered_components() -> Result<()> {
+        register_component::<Health>("test_health")?;
+        let mut world = World::new()?;
+        let entity = spawn_root(&mut world, "Player")?;
+        world.ecs.entry(entity).unwrap().add_component(Health(7));
+
+        let copy = world.duplicate(entity)?;
+
+        let entry = world.ecs.entry_ref(copy)?;
+        assert_eq!(entry.get_component::<Health>()?, &Health(7));
+        assert_eq!(entry.get_component::<Name>()?.0, "Player");
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_copies_descendants_as_next_sibling() -> Result<()> {
+        let mut world = World::new()?;
+        let root = spawn_root(&mut world, "Root")?;
+        let first = world.spawn_child(root, "First")?;
+        world.spawn_child(first, "Grandchild")?;
+        world.spawn_child(root, "Second")?;
+
+        let copy = world.duplicate(first)?;
+
+        assert_eq!(
+            children_names(&world, root)?,
+            vec!["First", "First", "Second"]
+        );
+        assert_eq!(world.children(root)[1], copy);
+        assert_eq!(children_names(&world, copy)?, vec!["Grandchild"]);
+        assert_eq!(world.parent_entity(copy), Some(root));
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_retargets_skins_to_copied_joints() -> Result<()> {
+        let mut world = World::new()?;
+        let outside = spawn_root(&mut world, "Outside")?;
+        let character = spawn_root(&mut world, "Character")?;
+        let mesh = world.spawn_child(character, "Mesh")?;
+        let bone = world.spawn_child(character, "Bone")?;
+        let joints = [bone, outside]
+            .iter()
+            .map(|target| Joint {
+                target: *target,
+                inverse_bind_matrix: glm::Mat4::identity(),
+            })
+            .collect();
+        world.ecs.entry(mesh).unwrap().add_component(Skin {
+            name: "Skin".to_string(),
+            joints,
+        });
+
+        let copy = world.duplicate(character)?;
+
+        let copied_children = world.children(copy);
+        let entry = world.ecs.entry_ref(copied_children[0])?;
+        let skin = entry.get_component::<Skin>()?;
+        assert_eq!(skin.joints[0].target, copied_children[1]);
+        assert_eq!(skin.joints[1].target, outside);
+        let entry = world.ecs.entry_ref(mesh)?;
+        assert_eq!(entry.get_component::<Skin>()?.joints[0].target, bone);
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_recreates_rigid_bodies() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_root(&mut world, "Crate")?;
+        world.add_rigid_body(entity, RigidBodyType::Dynamic)?;
+        world.attach_collider(entity, ColliderBuilder::cuboid(1.0, 1.0, 1.0).build())?;
+
+        let copy = world.duplicate(entity)?;
+
+        let (source_body, copy_body, copy_colliders) = {
+            let source = world.ecs.entry_ref(entity)?;
+            let copy = world.ecs.entry_ref(copy)?;
+            let copy_rigid_body = copy.get_component::<RigidBody>()?;
+            (
+                source.get_component::<RigidBody>()?.handle,
+                copy_rigid_body.handle,
+                copy_rigid_body.colliders.clone(),
+            )
+        };
+        assert_ne!(source_body, copy_body);
+        assert_eq!(copy_colliders.len(), 1);
+        assert_eq!(world.physics.collider_entity(copy_colliders[0]), Some(copy));
+        Ok(())
+    }
+
+    #[test]
+    fn despawning_a_duplicate_releases_its_physics() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_root(&mut world, "Crate")?;
+        world.add_rigid_body(entity, RigidBodyType::Dynamic)?;
+        world.attach_collider(entity, ColliderBuilder::ball(1.0).build())?;
+        let copy = world.duplicate(entity)?;
+
+        world.despawn(copy)?;
+
+        assert!(!world.physics.user_data.contains_key(&copy));
+        assert!(world.physics.user_data.contains_key(&entity));
+        assert!(world.physics.entities.iter().flatten().all(|e| *e != copy));
+        assert_eq!(world.physics.bodies.len(), 1);
+        assert_eq!(world.physics.colliders.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,1081 @@
+use crate::{
+    Entity, Material, Name, PhysicsJoint, RagdollSnapshot, RigidBodySettings, RigidBodySnapshot,
+    Skin, Transform, World, WorldSnapshot,
+};
+use dragonglass_dependencies::{
+    anyhow::{Context, Result},
+    legion::{EntityStore, IntoQuery},
+    serde::Serialize,
+    serde_json::{self, Map, Value},
+};
+use std::{
+    any::Any,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Maps the entities commands were created with to the entities
+/// that replaced them when an undo or redo recreated them
+#[derive(Default, Debug)]
+pub struct EntityMap(HashMap<Entity, Entity>);
+
+impl EntityMap {
+    pub fn resolve(&self, entity: Entity) -> Entity {
+        self.0.get(&entity).copied().unwrap_or(entity)
+    }
+
+    pub fn insert(&mut self, original: Entity, current: Entity) {
+        let previous = self.resolve(original);
+        for value in self.0.values_mut() {
+            if *value == previous {
+                *value = current;
+            }
+        }
+        self.0.insert(previous, current);
+        self.0.insert(original, current);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// An undoable edit to the world
+pub trait Command: Any {
+    fn name(&self) -> String;
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()>;
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()>;
+
+    /// Folds a command that was executed right after this one into it,
+    /// such as the steps of a drag. Returns false if the commands cannot merge.
+    fn merge(&mut self, _next: &dyn Command) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Executed commands that can be undone and redone
+pub struct History {
+    /// The most commands kept, the oldest are dropped first
+    pub limit: usize,
+    undo_stack: Vec<Box<dyn Command>>,
+    redo_stack: Vec<Box<dyn Command>>,
+    entities: EntityMap,
+    sealed: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            limit: 256,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            entities: EntityMap::default(),
+            sealed: true,
+        }
+    }
+
+    /// Executes a command and records it, merging it into the previous command if possible
+    pub fn execute(&mut self, world: &mut World, mut command: impl Command) -> Result<()> {
+        command.execute(world, &mut self.entities)?;
+        self.redo_stack.clear();
+        if !self.sealed {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if last.merge(&command) {
+                    return Ok(());
+                }
+            }
+        }
+        self.undo_stack.push(Box::new(command));
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.remove(0);
+        }
+        self.sealed = false;
+        Ok(())
+    }
+
+    /// Executes a command as its own entry, without merging it into the previous command
+    /// or letting the next command merge into it, such as a rename confirmed from a dialog
+    pub fn execute_sealed(&mut self, world: &mut World, command: impl Command) -> Result<()> {
+        self.seal();
+        self.execute(world, command)?;
+        self.seal();
+        Ok(())
+    }
+
+    /// Stops the next command from merging into the previous one,
+    /// such as when a drag ends
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    pub fn undo(&mut self, world: &mut World) -> Result<bool> {
+        self.seal();
+        let mut command = match self.undo_stack.pop() {
+            Some(command) => command,
+            None => return Ok(false),
+        };
+        command
+            .undo(world, &mut self.entities)
+            .with_context(|| format!("Failed to undo '{}'!", command.name()))?;
+        self.redo_stack.push(command);
+        Ok(true)
+    }
+
+    pub fn redo(&mut self, world: &mut World) -> Result<bool> {
+        self.seal();
+        let mut command = match self.redo_stack.pop() {
+            Some(command) => command,
+            None => return Ok(false),
+        };
+        command
+            .execute(world, &mut self.entities)
+            .with_context(|| format!("Failed to redo '{}'!", command.name()))?;
+        self.undo_stack.push(command);
+        Ok(true)
+    }
+
+    /// Undoes or redoes commands until `undo_len` commands remain undoable
+    pub fn jump_to(&mut self, world: &mut World, undo_len: usize) -> Result<()> {
+        while self.undo_stack.len() > undo_len && self.undo(world)? {}
+        while self.undo_stack.len() < undo_len && self.redo(world)? {}
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// The names of the undoable commands, oldest first
+    pub fn undo_names(&self) -> Vec<String> {
+        self.undo_stack
+            .iter()
+            .map(|command| command.name())
+            .collect()
+    }
+
+    /// The names of the redoable commands, next redo first
+    pub fn redo_names(&self) -> Vec<String> {
+        self.redo_stack
+            .iter()
+            .rev()
+            .map(|command| command.name())
+            .collect()
+    }
+
+    /// Returns the most recently executed command, if it is of the given type
+    pub fn last_command<T: Command>(&self) -> Option<&T> {
+        self.undo_stack.last()?.as_any().downcast_ref::<T>()
+    }
+
+    /// Returns the entity that currently stands in for an entity a command was created with
+    pub fn resolve(&self, entity: Entity) -> Entity {
+        self.entities.resolve(entity)
+    }
+
+    /// Forgets all commands, such as when a new world is loaded
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.entities.clear();
+        self.sealed = true;
+    }
+}
+
+/// Replaces an entity's local transform
+pub struct SetTransform {
+    pub entity: Entity,
+    pub before: Transform,
+    pub after: Transform,
+}
+
+impl SetTransform {
+    pub fn new(world: &World, entity: Entity, after: Transform) -> Result<Self> {
+        let before = *world.ecs.entry_ref(entity)?.get_component::<Transform>()?;
+        Ok(Self {
+            entity,
+            before,
+            after,
+        })
+    }
+}
+
+impl Command for SetTransform {
+    fn name(&self) -> String {
+        "Transform".to_string()
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        apply_transform(world, entities.resolve(self.entity), self.after)
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        apply_transform(world, entities.resolve(self.entity), self.before)
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<SetTransform>() {
+            Some(next) if next.entity == self.entity => {
+                self.after = next.after;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Replaces the local transforms of several entities at once, such as when moving a selection
+pub struct SetTransforms {
+    pub transforms: Vec<SetTransform>,
+}
+
+impl SetTransforms {
+    pub fn new(world: &World, transforms: Vec<(Entity, Transform)>) -> Result<Self> {
+        let transforms = transforms
+            .into_iter()
+            .map(|(entity, after)| SetTransform::new(world, entity, after))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { transforms })
+    }
+}
+
+impl Command for SetTransforms {
+    fn name(&self) -> String {
+        format!("Transform {} Entities", self.transforms.len())
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        for transform in self.transforms.iter_mut() {
+            transform.execute(world, entities)?;
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        for transform in self.transforms.iter_mut().rev() {
+            transform.undo(world, entities)?;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        let next = match next.as_any().downcast_ref::<SetTransforms>() {
+            Some(next) => next,
+            None => return false,
+        };
+        let same_entities = self.transforms.len() == next.transforms.len()
+            && self
+                .transforms
+                .iter()
+                .zip(next.transforms.iter())
+                .all(|(transform, next)| transform.entity == next.entity);
+        if !same_entities {
+            return false;
+        }
+        for (transform, next) in self.transforms.iter_mut().zip(next.transforms.iter()) {
+            transform.after = next.after;
+        }
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn apply_transform(world: &mut World, entity: Entity, transform: Transform) -> Result<()> {
+    world
+        .ecs
+        .entry(entity)
+        .context("Failed to set transform, the entity does not exist!")?
+        .add_component(transform);
+    world.sync_descendant_rigid_bodies(entity)
+}
+
+/// Adds, replaces, or removes a component by its registry key.
+/// Only components with a registered json conversion can be edited.
+pub struct SetComponent {
+    pub entity: Entity,
+    pub key: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl SetComponent {
+    pub fn new(world: &World, entity: Entity, key: &str, after: Option<Value>) -> Result<Self> {
+        Ok(Self {
+            entity,
+            key: key.to_string(),
+            before: world.component_value(entity, key)?,
+            after,
+        })
+    }
+
+    pub fn from_component(
+        world: &World,
+        entity: Entity,
+        key: &str,
+        component: &impl Serialize,
+    ) -> Result<Self> {
+        Self::new(world, entity, key, Some(serde_json::to_value(component)?))
+    }
+
+    pub fn remove(world: &World, entity: Entity, key: &str) -> Result<Self> {
+        Self::new(world, entity, key, None)
+    }
+}
+
+impl Command for SetComponent {
+    fn name(&self) -> String {
+        match (&self.before, &self.after) {
+            (None, Some(_)) => format!("Add {}", self.key),
+            (Some(_), None) => format!("Remove {}", self.key),
+            _ => format!("Set {}", self.key),
+        }
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        world.set_component_value(entities.resolve(self.entity), &self.key, self.after.clone())
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        world.set_component_value(
+            entities.resolve(self.entity),
+            &self.key,
+            self.before.clone(),
+        )
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<SetComponent>() {
+            Some(next)
+                if next.entity == self.entity
+                    && next.key == self.key
+                    && self.after.is_some()
+                    && next.after.is_some() =>
+            {
+                self.after = next.after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Changes the type and mass of an entity's rigid body
+pub struct SetRigidBody {
+    pub entity: Entity,
+    pub before: RigidBodySettings,
+    pub after: RigidBodySettings,
+}
+
+impl SetRigidBody {
+    pub fn new(world: &World, entity: Entity, after: RigidBodySettings) -> Result<Self> {
+        let before = world
+            .rigid_body_settings(entity)?
+            .context("Failed to edit rigid body, the entity has no rigid body!")?;
+        Ok(Self {
+            entity,
+            before,
+            after,
+        })
+    }
+}
+
+impl Command for SetRigidBody {
+    fn name(&self) -> String {
+        "Set rigid_body".to_string()
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        world.set_rigid_body_settings(entities.resolve(self.entity), &self.after)
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        world.set_rigid_body_settings(entities.resolve(self.entity), &self.before)
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<SetRigidBody>() {
+            Some(next) if next.entity == self.entity => {
+                self.after = next.after;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Replaces one of the world's materials
+pub struct SetMaterial {
+    pub index: usize,
+    pub before: Material,
+    pub after: Material,
+}
+
+impl SetMaterial {
+    pub fn new(world: &World, index: usize, after: Material) -> Result<Self> {
+        Ok(Self {
+            index,
+            before: world.material_at_index(index)?.clone(),
+            after,
+        })
+    }
+}
+
+impl Command for SetMaterial {
+    fn name(&self) -> String {
+        format!("Material '{}'", self.after.name)
+    }
+
+    fn execute(&mut self, world: &mut World, _entities: &mut EntityMap) -> Result<()> {
+        apply_material(world, self.index, self.after.clone())
+    }
+
+    fn undo(&mut self, world: &mut World, _entities: &mut EntityMap) -> Result<()> {
+        apply_material(world, self.index, self.before.clone())
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<SetMaterial>() {
+            Some(next) if next.index == self.index => {
+                self.after = next.after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn apply_material(world: &mut World, index: usize, material: Material) -> Result<()> {
+    *world
+        .materials
+        .get_mut(index)
+        .context("Failed to set material, the index is out of range!")? = material;
+    Ok(())
+}
+
+/// Creates a named entity, as the last child of the parent
+/// or as a root of the default scenegraph
+pub struct SpawnEntity {
+    pub parent: Option<Entity>,
+    pub name: String,
+    entity: Option<Entity>,
+}
+
+impl SpawnEntity {
+    pub fn new(parent: Option<Entity>, name: &str) -> Self {
+        Self {
+            parent,
+            name: name.to_string(),
+            entity: None,
+        }
+    }
+}
+
+impl Command for SpawnEntity {
+    fn name(&self) -> String {
+        format!("Spawn {}", self.name)
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        let entity = match self.parent {
+            Some(parent) => world.spawn_child(entities.resolve(parent), &self.name)?,
+            None => {
+                let entity = world
+                    .ecs
+                    .push((Transform::default(), Name(self.name.to_string())));
+                world.scene.default_scenegraph_mut()?.add_node(entity);
+                entity
+            }
+        };
+        match self.entity {
+            Some(original) => entities.insert(original, entity),
+            None => self.entity = Some(entity),
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        match self.entity {
+            Some(entity) => world.despawn(entities.resolve(entity)),
+            None => Ok(()),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Removes an entity and its descendants.
+/// Undoing restores all of their registered components along with their rigid bodies,
+/// joints, and ragdolls, including joints on other entities that targeted them.
+pub struct DespawnEntity {
+    pub entity: Entity,
+    snapshot: Option<DespawnSnapshot>,
+}
+
+struct DespawnSnapshot {
+    /// The despawned entities, parents before children
+    entities: Vec<Entity>,
+    /// Each entity's parent, as an index into `entities`
+    parents: Vec<Option<usize>>,
+    components: Vec<Map<String, Value>>,
+    rigid_bodies: Vec<Option<RigidBodySnapshot>>,
+    ragdolls: Vec<Option<RagdollSnapshot>>,
+    /// Joints on the despawned entities and joints targeting them
+    joints: Vec<(Entity, PhysicsJoint)>,
+    parent: Option<Entity>,
+    graph_index: usize,
+    sibling_index: Option<usize>,
+}
+
+impl DespawnEntity {
+    pub fn new(entity: Entity) -> Self {
+        Self {
+            entity,
+            snapshot: None,
+        }
+    }
+}
+
+impl Command for DespawnEntity {
+    fn name(&self) -> String {
+        "Despawn".to_string()
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        let entity = entities.resolve(self.entity);
+        let descendants = world.descendants(entity);
+        let mut parents = Vec::with_capacity(descendants.len());
+        let mut components = Vec::with_capacity(descendants.len());
+        let mut rigid_bodies = Vec::with_capacity(descendants.len());
+        let mut ragdolls = Vec::with_capacity(descendants.len());
+        for descendant in descendants.iter() {
+            parents.push(match *descendant == entity {
+                true => None,
+                false => world
+                    .parent_entity(*descendant)
+                    .and_then(|parent| descendants.iter().position(|entity| *entity == parent)),
+            });
+            let mut values = world.component_values(*descendant)?;
+            for key in World::REBUILT_COMPONENTS.iter() {
+                values.remove(*key);
+            }
+            components.push(values);
+            rigid_bodies.push(world.rigid_body_snapshot(*descendant)?);
+            ragdolls.push(world.ragdoll_snapshot(*descendant)?);
+        }
+        let joints = <(Entity, &PhysicsJoint)>::query()
+            .iter(&world.ecs)
+            .filter(|(entity, joint)| {
+                descendants.contains(entity) || descendants.contains(&joint.target)
+            })
+            .map(|(entity, joint)| (*entity, joint.clone()))
+            .collect::<Vec<_>>();
+
+        self.snapshot = Some(DespawnSnapshot {
+            entities: descendants,
+            parents,
+            components,
+            rigid_bodies,
+            ragdolls,
+            joints,
+            parent: world.parent_entity(entity),
+            graph_index: world
+                .entity_node(entity)
+                .map(|(graph_index, _)| graph_index)
+                .unwrap_or_default(),
+            sibling_index: sibling_index(world, entity),
+        });
+        world.despawn(entity)
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        let snapshot = self
+            .snapshot
+            .as_ref()
+            .context("The despawned entity was never recorded!")?;
+        let parent = snapshot.parent.map(|parent| entities.resolve(parent));
+        let mut restored: Vec<Entity> = Vec::with_capacity(snapshot.entities.len());
+        for node_parent in snapshot.parents.iter() {
+            let node_parent = match node_parent {
+                Some(index) => Some(restored[*index]),
+                None => parent,
+            };
+            restored.push(world.spawn_entity(node_parent)?);
+        }
+        for (original, entity) in snapshot.entities.iter().zip(restored.iter()) {
+            entities.insert(*original, *entity);
+        }
+        let root = restored[0];
+
+        if parent.is_none() && snapshot.graph_index < world.scene.graphs.len() {
+            if let Some((graph_index, node_index)) = world.entity_node(root) {
+                if graph_index != snapshot.graph_index {
+                    world.move_subtree(graph_index, node_index, snapshot.graph_index)?;
+                }
+            }
+        }
+        if let Some(sibling_index) = snapshot.sibling_index {
+            world.set_sibling_index(root, sibling_index)?;
+        }
+
+        for (index, entity) in restored.iter().enumerate() {
+            for (key, value) in snapshot.components[index].iter() {
+                world.set_component_value(*entity, key, Some(value.clone()))?;
+            }
+            // Skins may target the despawned entities, which were recreated with new ids
+            if let Ok(skin) = world.ecs.entry_mut(*entity)?.get_component_mut::<Skin>() {
+                for joint in skin.joints.iter_mut() {
+                    joint.target = entities.resolve(joint.target);
+                }
+            }
+            if let Some(rigid_body) = snapshot.rigid_bodies[index].as_ref() {
+                world.restore_rigid_body(*entity, rigid_body)?;
+            }
+        }
+        for (index, entity) in restored.iter().enumerate() {
+            if let Some(ragdoll) = snapshot.ragdolls[index].as_ref() {
+                world.restore_ragdoll(*entity, ragdoll, |target| entities.resolve(target))?;
+            }
+        }
+        for (entity, joint) in snapshot.joints.iter() {
+            let mut joint = joint.clone();
+            joint.target = entities.resolve(joint.target);
+            joint.handle = None;
+            world.add_joint(entities.resolve(*entity), joint)?;
+        }
+        world.sync_descendant_rigid_bodies(root)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Moves an entity and its descendants under a new parent,
+/// or to the root of its scenegraph if no parent is given
+pub struct Reparent {
+    pub entity: Entity,
+    pub new_parent: Option<Entity>,
+    pub keep_world_transform: bool,
+    previous: Option<(Option<Entity>, Option<usize>, Transform)>,
+}
+
+impl Reparent {
+    pub fn new(entity: Entity, new_parent: Option<Entity>, keep_world_transform: bool) -> Self {
+        Self {
+            entity,
+            new_parent,
+            keep_world_transform,
+            previous: None,
+        }
+    }
+}
+
+impl Command for Reparent {
+    fn name(&self) -> String {
+        "Reparent".to_string()
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        let entity = entities.resolve(self.entity);
+        let transform = *world.ecs.entry_ref(entity)?.get_component::<Transform>()?;
+        self.previous = Some((
+            world.parent_entity(entity),
+            sibling_index(world, entity),
+            transform,
+        ));
+        let new_parent = self.new_parent.map(|parent| entities.resolve(parent));
+        world.reparent(entity, new_parent, self.keep_world_transform)
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        let (parent, sibling_index, transform) = self
+            .previous
+            .context("The reparented entity was never recorded!")?;
+        let entity = entities.resolve(self.entity);
+        let parent = parent.map(|parent| entities.resolve(parent));
+        world.reparent(entity, parent, false)?;
+        if let Some(sibling_index) = sibling_index {
+            world.set_sibling_index(entity, sibling_index)?;
+        }
+        apply_transform(world, entity, transform)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Creates an instance of a prefab file, as the last child of the parent
+/// or as a root of the default scenegraph
+pub struct InstantiatePrefab {
+    pub path: PathBuf,
+    pub parent: Option<Entity>,
+    /// The instance's entities when it was first created, parents before children
+    entities: Vec<Entity>,
+}
+
+impl InstantiatePrefab {
+    pub fn new(path: impl AsRef<Path>, parent: Option<Entity>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            parent,
+            entities: Vec::new(),
+        }
+    }
+
+    /// The root of the instance, once the command has been executed
+    pub fn entity(&self) -> Option<Entity> {
+        self.entities.first().copied()
+    }
+}
+
+impl Command for InstantiatePrefab {
+    fn name(&self) -> String {
+        let name = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        format!("Instantiate {}", name)
+    }
+
+    fn execute(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        let parent = self.parent.map(|parent| entities.resolve(parent));
+        let root = world.instantiate_prefab(&self.path, parent)?;
+        let instance = world.descendants(root);
+        if self.entities.is_empty() {
+            self.entities = instance;
+        } else {
+            for (original, entity) in self.entities.iter().zip(instance) {
+                entities.insert(*original, entity);
+            }
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) -> Result<()> {
+        match self.entity() {
+            Some(entity) => world.despawn(entities.resolve(entity)),
+            None => Ok(()),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Updates every instance of a prefab from its file, keeping the fields each instance overrides.
+/// A reload can change, spawn, and despawn entities across the world,
+/// so undoing restores a snapshot of the world taken before it.
+pub struct ReloadPrefab {
+    pub path: String,
+    before: Option<WorldSnapshot>,
+}
+
+impl ReloadPrefab {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            before: None,
+        }
+    }
+}
+
+impl Command for ReloadPrefab {
+    fn name(&self) -> String {
+        let name = Path::new(&self.path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        format!("Reload {}", name)
+    }
+
+    fn execute(&mut self, world: &mut World, _entities: &mut EntityMap) -> Result<()> {
+        let before = world.snapshot()?;
+        world.reload_prefab(&self.path)?;
+        self.before = Some(before);
+        Ok(())
+    }
+
+    fn undo(&mut self, world: &mut World, _entities: &mut EntityMap) -> Result<()> {
+        let before = self
+            .before
+            .as_ref()
+            .context("The reloaded prefab was never recorded!")?;
+        world.restore(before)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The entity's position among its siblings, or among the roots of its scenegraph
+fn sibling_index(world: &World, entity: Entity) -> Option<usize> {
+    match world.parent_entity(entity) {
+        Some(parent) => world
+            .children(parent)
+            .iter()
+            .position(|child| *child == entity),
+        None => {
+            let (graph_index, node_index) = world.entity_node(entity)?;
+            world.scene.graphs[graph_index]
+                .roots()
+                .iter()
+                .position(|root| *root == node_index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::spawn_root, Joint, JointKind, Prefab, Ragdoll, RagdollBuilder, RigidBody,
+    };
+    use dragonglass_dependencies::{
+        nalgebra_glm as glm,
+        rapier3d::{dynamics::RigidBodyType, geometry::ColliderBuilder},
+    };
+
+    fn name_of(world: &World, entity: Entity) -> Result<String> {
+        Ok(world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<Name>()?
+            .0
+            .clone())
+    }
+
+    #[test]
+    fn undoing_a_despawn_restores_skins_against_the_restored_entities() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let character = spawn_root(&mut world, "Character")?;
+        let mesh = world.spawn_child(character, "Mesh")?;
+        let bone = world.spawn_child(character, "Bone")?;
+        world.ecs.entry(mesh).unwrap().add_component(Skin {
+            name: "Skin".to_string(),
+            joints: vec![Joint {
+                target: bone,
+                inverse_bind_matrix: glm::Mat4::identity(),
+            }],
+        });
+
+        history.execute(&mut world, DespawnEntity::new(character))?;
+        history.undo(&mut world)?;
+
+        let character = history.resolve(character);
+        let children = world.children(character);
+        assert_eq!(name_of(&world, character)?, "Character");
+        assert_eq!(children, vec![history.resolve(mesh), history.resolve(bone)]);
+        let entry = world.ecs.entry_ref(children[0])?;
+        assert_eq!(entry.get_component::<Skin>()?.joints[0].target, children[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn prefab_instantiation_and_reloads_can_be_undone() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let source = spawn_root(&mut world, "Crate")?;
+        world.spawn_child(source, "Lid")?;
+        let directory = std::env::temp_dir().join("dragonglass_history_tests");
+        std::fs::create_dir_all(&directory)?;
+        let path = directory.join("crate.prefab");
+        Prefab::from_entity(&world, source)?.save(&path)?;
+
+        history.execute_sealed(&mut world, InstantiatePrefab::new(&path, None))?;
+        let instance = history
+            .last_command::<InstantiatePrefab>()
+            .and_then(InstantiatePrefab::entity)
+            .unwrap();
+        assert_eq!(world.children(instance).len(), 1);
+
+        world.ecs.entry_mut(source)?.get_component_mut::<Name>()?.0 = "Box".to_string();
+        Prefab::from_entity(&world, source)?.save(&path)?;
+        let path = path.display().to_string();
+        history.execute_sealed(&mut world, ReloadPrefab::new(&path))?;
+        assert_eq!(name_of(&world, instance)?, "Box");
+
+        history.undo(&mut world)?;
+        assert_eq!(name_of(&world, instance)?, "Crate");
+        history.undo(&mut world)?;
+        assert!(!world.ecs.contains(instance));
+
+        history.redo(&mut world)?;
+        let instance = history.resolve(instance);
+        assert_eq!(name_of(&world, instance)?, "Box");
+        assert_eq!(world.children(instance).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn undoing_a_despawn_restores_root_order() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let first = spawn_root(&mut world, "First")?;
+        let second = spawn_root(&mut world, "Second")?;
+        let third = spawn_root(&mut world, "Third")?;
+        world.spawn_child(second, "Child")?;
+
+        history.execute(&mut world, DespawnEntity::new(second))?;
+        history.undo(&mut world)?;
+
+        let graph = world.scene.default_scenegraph_mut()?;
+        let roots = graph
+            .roots()
+            .into_iter()
+            .map(|root| graph[root])
+            .collect::<Vec<_>>();
+        let expected = [first, history.resolve(second), third];
+        assert_eq!(&roots[roots.len() - 3..], &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn undoing_a_despawn_restores_joints_targeting_it() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let anchor = spawn_root(&mut world, "Anchor")?;
+        let door = spawn_root(&mut world, "Door")?;
+        for entity in [anchor, door] {
+            world.add_rigid_body(entity, RigidBodyType::Dynamic)?;
+            world.attach_collider(entity, ColliderBuilder::cuboid(1.0, 1.0, 1.0).build())?;
+        }
+        world.add_joint(door, PhysicsJoint::new(JointKind::Fixed, anchor))?;
+
+        history.execute(&mut world, DespawnEntity::new(anchor))?;
+        assert!(world
+            .ecs
+            .entry_ref(door)?
+            .get_component::<PhysicsJoint>()
+            .is_err());
+        history.undo(&mut world)?;
+
+        let anchor = history.resolve(anchor);
+        let entry = world.ecs.entry_ref(door)?;
+        let joint = entry.get_component::<PhysicsJoint>()?;
+        assert_eq!(joint.target, anchor);
+        assert!(joint.handle.is_some());
+        assert_eq!(world.physics.impulse_joints.len(), 1);
+        let colliders = world
+            .ecs
+            .entry_ref(anchor)?
+            .get_component::<RigidBody>()?
+            .colliders
+            .clone();
+        assert_eq!(world.physics.collider_entity(colliders[0]), Some(anchor));
+        Ok(())
+    }
+
+    #[test]
+    fn undoing_a_despawn_restores_ragdolls() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let hips = spawn_root(&mut world, "Hips")?;
+        let thigh = world.spawn_child(hips, "Thigh")?;
+        let skinned_entity = spawn_root(&mut world, "Body")?;
+        world
+            .ecs
+            .entry(skinned_entity)
+            .unwrap()
+            .add_component(Skin {
+                name: "Leg".to_string(),
+                joints: [hips, thigh]
+                    .iter()
+                    .map(|target| Joint {
+                        target: *target,
+                        inverse_bind_matrix: glm::Mat4::identity(),
+                    })
+                    .collect(),
+            });
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+
+        history.execute(&mut world, DespawnEntity::new(skinned_entity))?;
+        assert_eq!(world.physics.bodies.len(), 0);
+        history.undo(&mut world)?;
+
+        let skinned_entity = history.resolve(skinned_entity);
+        let entry = world.ecs.entry_ref(skinned_entity)?;
+        let ragdoll = entry.get_component::<Ragdoll>()?;
+        assert_eq!(ragdoll.bones.len(), 2);
+        assert_eq!(ragdoll.bones[0].target, hips);
+        assert_eq!(world.physics.bodies.len(), 2);
+        assert_eq!(world.physics.impulse_joints.len(), 1);
+        assert_eq!(
+            world.physics.collider_entity(ragdoll.bones[1].collider),
+            Some(thigh)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sealed_commands_do_not_merge() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let entity = spawn_root(&mut world, "Crate")?;
+
+        for name in ["Box", "Barrel"] {
+            let command =
+                SetComponent::from_component(&world, entity, "name", &Name(name.to_string()))?;
+            history.execute_sealed(&mut world, command)?;
+        }
+        history.undo(&mut world)?;
+
+        assert_eq!(history.undo_names().len(), 1);
+        assert_eq!(name_of(&world, entity)?, "Box");
+        Ok(())
+    }
+
+    #[test]
+    fn unsealed_component_edits_merge() -> Result<()> {
+        let mut world = World::new()?;
+        let mut history = History::new();
+        let entity = spawn_root(&mut world, "Crate")?;
+
+        for name in ["B", "Bo", "Box"] {
+            let command =
+                SetComponent::from_component(&world, entity, "name", &Name(name.to_string()))?;
+            history.execute(&mut world, command)?;
+        }
+        history.undo(&mut world)?;
+
+        assert!(!history.can_undo());
+        assert_eq!(name_of(&world, entity)?, "Crate");
+        Ok(())
+    }
+}
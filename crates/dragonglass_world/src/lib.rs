@@ -1,11 +1,32 @@
+mod assets;
+mod debug;
+mod document;
+mod gizmo;
 mod gltf;
+mod hierarchy;
+mod history;
 mod physics;
+mod plugins;
+mod prefab;
+mod query;
+mod ragdoll;
+mod registry;
+mod schedule;
+mod selection;
+mod snapshot;
+#[cfg(test)]
+mod testing;
+mod version;
 mod world;
 
-pub use self::{gltf::*, physics::*, world::*};
+pub use self::{
+    assets::*, debug::*, document::*, gizmo::*, gltf::*, hierarchy::*, history::*, physics::*,
+    plugins::*, prefab::*, query::*, ragdoll::*, registry::*, schedule::*, selection::*,
+    snapshot::*, version::*, world::*,
+};
 
 pub use dragonglass_dependencies::legion::EntityStore;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub struct Name(pub String);
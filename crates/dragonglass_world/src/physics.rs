@@ -1,5 +1,13 @@
-use dragonglass_dependencies::rapier3d::prelude::{
-    ImpulseJointSet, MultibodyJointSet, RigidBodyHandle,
+use crate::Entity;
+use dragonglass_dependencies::{
+    log,
+    nalgebra::{Isometry3, Point3, Unit},
+    nalgebra_glm as glm,
+    rapier3d::prelude::{
+        FixedJointBuilder, GenericJoint, GenericJointBuilder, ImpulseJointHandle, ImpulseJointSet,
+        JointAxesMask, JointAxis, MultibodyJointHandle, MultibodyJointSet, PrismaticJointBuilder,
+        RevoluteJointBuilder, RigidBodyHandle, RigidBodyType, SharedShape, SphericalJointBuilder,
+    },
 };
 pub use dragonglass_dependencies::{
     rapier3d::{
@@ -12,6 +20,7 @@ pub use dragonglass_dependencies::{
     },
     serde::{Deserialize, Serialize},
 };
+use std::collections::HashMap;
 
 pub type Handle = rapier3d::dynamics::RigidBodyHandle;
 pub type ColliderHandle = rapier3d::geometry::ColliderHandle;
@@ -21,6 +30,12 @@ pub type ColliderHandle = rapier3d::geometry::ColliderHandle;
 pub struct RigidBody {
     pub handle: Handle,
     pub colliders: Vec<ColliderHandle>,
+    /// The global scale the colliders were last built with
+    pub scale: glm::Vec3,
+    /// The global position last exchanged between the entity's transform and the body,
+    /// used to tell when the transform was edited outside of the physics simulation
+    #[serde(skip)]
+    pub synced_position: Option<Isometry3<f32>>,
 }
 
 impl RigidBody {
@@ -28,8 +43,232 @@ impl RigidBody {
         Self {
             handle,
             colliders: Vec::new(),
+            scale: glm::vec3(1.0, 1.0, 1.0),
+            synced_position: None,
+        }
+    }
+}
+
+/// The editable properties of an entity's rigid body
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RigidBodySettings {
+    pub body_type: RigidBodyType,
+    /// The total mass of the body's colliders
+    pub mass: f32,
+}
+
+/// The unscaled shape and offset of a collider,
+/// used to rebuild the collider when its entity's scale changes
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct ColliderBase {
+    pub shape: SharedShape,
+    pub translation: glm::Vec3,
+}
+
+pub fn scaled_shape(shape: &SharedShape, scale: &glm::Vec3) -> SharedShape {
+    let scale_point = |point: &Point3<f32>| Point3::from(point.coords.component_mul(scale));
+    if let Some(ball) = shape.as_ball() {
+        return SharedShape::ball(ball.radius * scale.max());
+    }
+    if let Some(cuboid) = shape.as_cuboid() {
+        let half_extents = cuboid.half_extents.component_mul(scale);
+        return SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z);
+    }
+    if let Some(capsule) = shape.as_capsule() {
+        return SharedShape::capsule(
+            scale_point(&capsule.segment.a),
+            scale_point(&capsule.segment.b),
+            capsule.radius * scale.x.max(scale.z),
+        );
+    }
+    if let Some(cylinder) = shape.as_cylinder() {
+        return SharedShape::cylinder(
+            cylinder.half_height * scale.y,
+            cylinder.radius * scale.x.max(scale.z),
+        );
+    }
+    if let Some(trimesh) = shape.as_trimesh() {
+        let vertices = trimesh.vertices().iter().map(scale_point).collect();
+        return SharedShape::trimesh(vertices, trimesh.indices().to_vec());
+    }
+    if let Some(polyhedron) = shape.as_convex_polyhedron() {
+        let points = polyhedron
+            .points()
+            .iter()
+            .map(scale_point)
+            .collect::<Vec<_>>();
+        if let Some(hull) = SharedShape::convex_hull(&points) {
+            return hull;
         }
     }
+    log::warn!("Collider shape does not support scaling. Leaving it unscaled...");
+    shape.clone()
+}
+
+/// A joint connecting an entity's rigid body to the rigid body of another entity.
+/// The component is the source of truth for the joint, so it can be rebuilt
+/// in the physics world after deserialization.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct PhysicsJoint {
+    pub kind: JointKind,
+    pub target: Entity,
+    pub local_anchor: glm::Vec3,
+    pub target_local_anchor: glm::Vec3,
+    pub contacts_enabled: bool,
+    pub multibody: bool,
+    pub handle: Option<JointHandle>,
+}
+
+impl PhysicsJoint {
+    pub fn new(kind: JointKind, target: Entity) -> Self {
+        Self {
+            kind,
+            target,
+            local_anchor: glm::Vec3::zeros(),
+            target_local_anchor: glm::Vec3::zeros(),
+            contacts_enabled: false,
+            multibody: false,
+            handle: None,
+        }
+    }
+
+    pub fn with_anchors(mut self, local_anchor: glm::Vec3, target_local_anchor: glm::Vec3) -> Self {
+        self.local_anchor = local_anchor;
+        self.target_local_anchor = target_local_anchor;
+        self
+    }
+
+    pub fn multibody(mut self) -> Self {
+        self.multibody = true;
+        self
+    }
+
+    /// Builds the rapier joint data. The target is the first body of the joint.
+    pub fn generic_joint(&self) -> GenericJoint {
+        let anchor1 = Point3::from(self.target_local_anchor);
+        let anchor2 = Point3::from(self.local_anchor);
+        let mut joint: GenericJoint = match &self.kind {
+            JointKind::Fixed => FixedJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .build()
+                .into(),
+            JointKind::Revolute {
+                axis,
+                limits,
+                motor,
+            } => {
+                let mut builder = RevoluteJointBuilder::new(Unit::new_normalize(*axis))
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = limits {
+                    builder = builder.limits(*limits);
+                }
+                if let Some(motor) = motor {
+                    builder = builder
+                        .motor(
+                            motor.target_position,
+                            motor.target_velocity,
+                            motor.stiffness,
+                            motor.damping,
+                        )
+                        .motor_max_force(motor.max_force);
+                }
+                builder.build().into()
+            }
+            JointKind::Prismatic {
+                axis,
+                limits,
+                motor,
+            } => {
+                let mut builder = PrismaticJointBuilder::new(Unit::new_normalize(*axis))
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = limits {
+                    builder = builder.limits(*limits);
+                }
+                if let Some(motor) = motor {
+                    // Unlike the revolute builder, the prismatic builder names this `set_motor`
+                    builder = builder
+                        .set_motor(
+                            motor.target_position,
+                            motor.target_velocity,
+                            motor.stiffness,
+                            motor.damping,
+                        )
+                        .motor_max_force(motor.max_force);
+                }
+                builder.build().into()
+            }
+            JointKind::Spherical {
+                twist_limits,
+                swing_limits,
+            } => {
+                let mut builder = SphericalJointBuilder::new()
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = twist_limits {
+                    builder = builder.limits(JointAxis::AngX, *limits);
+                }
+                if let Some(limits) = swing_limits {
+                    builder = builder
+                        .limits(JointAxis::AngY, *limits)
+                        .limits(JointAxis::AngZ, *limits);
+                }
+                builder.build().into()
+            }
+            JointKind::Rope { max_distance } => GenericJointBuilder::new(JointAxesMask::empty())
+                .coupled_axes(JointAxesMask::LIN_AXES)
+                .limits(JointAxis::X, [0.0, *max_distance])
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .build(),
+        };
+        joint.set_contacts_enabled(self.contacts_enabled);
+        joint
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub enum JointKind {
+    Fixed,
+    Revolute {
+        axis: glm::Vec3,
+        limits: Option<[f32; 2]>,
+        motor: Option<JointMotorSettings>,
+    },
+    Prismatic {
+        axis: glm::Vec3,
+        limits: Option<[f32; 2]>,
+        motor: Option<JointMotorSettings>,
+    },
+    Spherical {
+        twist_limits: Option<[f32; 2]>,
+        swing_limits: Option<[f32; 2]>,
+    },
+    Rope {
+        max_distance: f32,
+    },
+}
+
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct JointMotorSettings {
+    pub target_position: f32,
+    pub target_velocity: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+    pub max_force: f32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub enum JointHandle {
+    Impulse(ImpulseJointHandle),
+    Multibody(MultibodyJointHandle),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -47,6 +286,18 @@ pub struct WorldPhysics {
     pub multibody_joints: MultibodyJointSet,
     pub query_pipeline: QueryPipeline,
     pub ccd_solver: CCDSolver,
+    pub collider_bases: HashMap<ColliderHandle, ColliderBase>,
+    /// The collider user data assigned to each entity.
+    /// These are not serialized, and are rebuilt from the rigid body components after loading.
+    #[serde(skip)]
+    pub user_data: HashMap<Entity, u128>,
+    /// Entities referenced by collider user data, offset by one
+    /// so that colliders with no user data map to no entity
+    #[serde(skip)]
+    pub entities: Vec<Option<Entity>>,
+    /// User data released by removed entities, reused before new user data is allocated
+    #[serde(skip)]
+    pub free_user_data: Vec<u128>,
     #[serde(skip)]
     pub pipeline: PhysicsPipeline,
 }
@@ -71,6 +322,10 @@ impl WorldPhysics {
             multibody_joints: MultibodyJointSet::new(),
             query_pipeline: QueryPipeline::default(),
             ccd_solver: CCDSolver::new(),
+            collider_bases: HashMap::new(),
+            user_data: HashMap::new(),
+            entities: Vec::new(),
+            free_user_data: Vec::new(),
             pipeline: PhysicsPipeline::new(),
         }
     }
@@ -84,6 +339,94 @@ impl WorldPhysics {
             &mut self.multibody_joints,
             true,
         );
+        let colliders = &self.colliders;
+        self.collider_bases
+            .retain(|handle, _| colliders.get(*handle).is_some());
+    }
+
+    /// Returns the user data that maps a collider back to an entity
+    pub fn entity_user_data(&mut self, entity: Entity) -> u128 {
+        if let Some(user_data) = self.user_data.get(&entity) {
+            return *user_data;
+        }
+        let user_data = match self.free_user_data.pop() {
+            Some(user_data) => {
+                self.entities[user_data as usize - 1] = Some(entity);
+                user_data
+            }
+            None => {
+                self.entities.push(Some(entity));
+                self.entities.len() as u128
+            }
+        };
+        self.user_data.insert(entity, user_data);
+        user_data
+    }
+
+    /// Releases the entity's user data so it can be reused by another entity.
+    /// Colliders still tagged with it will no longer map to the entity.
+    pub fn release_entity_user_data(&mut self, entity: Entity) {
+        if let Some(user_data) = self.user_data.remove(&entity) {
+            self.entities[user_data as usize - 1] = None;
+            self.free_user_data.push(user_data);
+        }
+    }
+
+    /// Forgets the user data of every entity
+    pub fn clear_entity_user_data(&mut self) {
+        self.user_data.clear();
+        self.entities.clear();
+        self.free_user_data.clear();
+    }
+
+    /// Rebuilds a collider's shape and offset from its unscaled base
+    pub fn rescale_collider(&mut self, handle: ColliderHandle, scale: &glm::Vec3) {
+        let base = match self.collider_bases.get(&handle) {
+            Some(base) => base,
+            None => return,
+        };
+        if let Some(collider) = self.colliders.get_mut(handle) {
+            collider.set_shape(scaled_shape(&base.shape, scale));
+            if let Some(position) = collider.position_wrt_parent().copied() {
+                let mut position = position;
+                position.translation.vector = base.translation.component_mul(scale);
+                collider.set_position_wrt_parent(position);
+            }
+        }
+    }
+
+    pub fn collider_entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        let user_data = self.colliders.get(handle)?.user_data;
+        if user_data == 0 {
+            return None;
+        }
+        self.entities.get(user_data as usize - 1).copied().flatten()
+    }
+
+    pub fn insert_joint(
+        &mut self,
+        body1: RigidBodyHandle,
+        body2: RigidBodyHandle,
+        joint: &PhysicsJoint,
+    ) -> Option<JointHandle> {
+        let data = joint.generic_joint();
+        if joint.multibody {
+            self.multibody_joints
+                .insert(body1, body2, data, true)
+                .map(JointHandle::Multibody)
+        } else {
+            let handle = self.impulse_joints.insert(body1, body2, data, true);
+            Some(JointHandle::Impulse(handle))
+        }
+    }
+
+    pub fn remove_joint(&mut self, handle: JointHandle) {
+        match handle {
+            JointHandle::Impulse(handle) => {
+                self.impulse_joints.remove(handle, true);
+            }
+            JointHandle::Multibody(handle) => self.multibody_joints.remove(handle, true),
+        }
     }
 
     pub fn set_gravity(&mut self, gravity: Vector3<f32>) {
@@ -115,3 +458,264 @@ impl WorldPhysics {
             .update(&self.islands, &self.bodies, &self.colliders);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::spawn_body, Transform, World};
+    use dragonglass_dependencies::{
+        anyhow::Result, legion::EntityStore, rapier3d::geometry::ColliderBuilder,
+    };
+
+    fn joint_handle(world: &World, entity: Entity) -> Option<JointHandle> {
+        world
+            .ecs
+            .entry_ref(entity)
+            .ok()?
+            .get_component::<PhysicsJoint>()
+            .ok()?
+            .handle
+    }
+
+    #[test]
+    fn add_joint_inserts_impulse_joint() -> Result<()> {
+        let mut world = World::new()?;
+        let anchor = spawn_body(
+            &mut world,
+            "anchor",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let body = spawn_body(
+            &mut world,
+            "body",
+            glm::vec3(0.0, -1.0, 0.0),
+            RigidBodyType::Dynamic,
+        )?;
+
+        world.add_joint(body, PhysicsJoint::new(JointKind::Fixed, anchor))?;
+
+        assert!(matches!(
+            joint_handle(&world, body),
+            Some(JointHandle::Impulse(_))
+        ));
+        assert_eq!(world.physics.impulse_joints.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn add_joint_replaces_existing_joint() -> Result<()> {
+        let mut world = World::new()?;
+        let anchor = spawn_body(
+            &mut world,
+            "anchor",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let body = spawn_body(
+            &mut world,
+            "body",
+            glm::vec3(0.0, -1.0, 0.0),
+            RigidBodyType::Dynamic,
+        )?;
+
+        world.add_joint(body, PhysicsJoint::new(JointKind::Fixed, anchor))?;
+        world.add_joint(
+            body,
+            PhysicsJoint::new(JointKind::Rope { max_distance: 2.0 }, anchor),
+        )?;
+
+        assert_eq!(world.physics.impulse_joints.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn add_joint_requires_target_rigid_body() -> Result<()> {
+        let mut world = World::new()?;
+        let target = world.ecs.push((Transform::default(),));
+        let body = spawn_body(
+            &mut world,
+            "body",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+
+        let result = world.add_joint(body, PhysicsJoint::new(JointKind::Fixed, target));
+
+        assert!(result.is_err());
+        assert!(joint_handle(&world, body).is_none());
+        assert_eq!(world.physics.impulse_joints.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn add_multibody_joint_inserts_multibody_joint() -> Result<()> {
+        let mut world = World::new()?;
+        let anchor = spawn_body(
+            &mut world,
+            "anchor",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let body = spawn_body(
+            &mut world,
+            "body",
+            glm::vec3(0.0, -1.0, 0.0),
+            RigidBodyType::Dynamic,
+        )?;
+
+        let joint = PhysicsJoint::new(
+            JointKind::Revolute {
+                axis: glm::Vec3::z(),
+                limits: Some([-1.0, 1.0]),
+                motor: None,
+            },
+            anchor,
+        )
+        .multibody();
+        world.add_joint(body, joint)?;
+
+        assert!(matches!(
+            joint_handle(&world, body),
+            Some(JointHandle::Multibody(_))
+        ));
+        assert_eq!(world.physics.impulse_joints.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn multibody_joints_cannot_form_loops() -> Result<()> {
+        let mut world = World::new()?;
+        let first = spawn_body(
+            &mut world,
+            "first",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let second = spawn_body(
+            &mut world,
+            "second",
+            glm::vec3(0.0, -1.0, 0.0),
+            RigidBodyType::Dynamic,
+        )?;
+
+        world.add_joint(
+            second,
+            PhysicsJoint::new(JointKind::Fixed, first).multibody(),
+        )?;
+        let result = world.add_joint(
+            first,
+            PhysicsJoint::new(JointKind::Fixed, second).multibody(),
+        );
+
+        assert!(result.is_err());
+        assert!(joint_handle(&world, first).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn remove_joint_removes_physics_joint() -> Result<()> {
+        let mut world = World::new()?;
+        let anchor = spawn_body(
+            &mut world,
+            "anchor",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let body = spawn_body(
+            &mut world,
+            "body",
+            glm::vec3(0.0, -1.0, 0.0),
+            RigidBodyType::Dynamic,
+        )?;
+        world.add_joint(body, PhysicsJoint::new(JointKind::Fixed, anchor))?;
+
+        world.remove_joint(body)?;
+
+        assert!(world
+            .ecs
+            .entry_ref(body)?
+            .get_component::<PhysicsJoint>()
+            .is_err());
+        assert_eq!(world.physics.impulse_joints.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn generic_joint_uses_target_as_first_anchor() {
+        let target = World::default().ecs.push((Transform::default(),));
+        let joint = PhysicsJoint::new(JointKind::Fixed, target)
+            .with_anchors(glm::vec3(1.0, 0.0, 0.0), glm::vec3(0.0, 2.0, 0.0));
+
+        let generic_joint = joint.generic_joint();
+
+        assert_eq!(generic_joint.local_anchor1(), Point3::new(0.0, 2.0, 0.0));
+        assert_eq!(generic_joint.local_anchor2(), Point3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn generic_joint_locks_limited_axes() {
+        let target = World::default().ecs.push((Transform::default(),));
+        let joint = PhysicsJoint::new(JointKind::Rope { max_distance: 3.0 }, target);
+
+        let generic_joint = joint.generic_joint();
+
+        assert!(generic_joint.limit_axes.contains(JointAxesMask::X));
+        assert_eq!(generic_joint.limits(JointAxis::X).map(|l| l.max), Some(3.0));
+    }
+
+    #[test]
+    fn entity_user_data_is_reused_after_release() -> Result<()> {
+        let mut world = World::new()?;
+        let first = world.ecs.push((Transform::default(),));
+        let second = world.ecs.push((Transform::default(),));
+        let physics = &mut world.physics;
+
+        let first_user_data = physics.entity_user_data(first);
+        assert_eq!(physics.entity_user_data(first), first_user_data);
+        physics.release_entity_user_data(first);
+        let second_user_data = physics.entity_user_data(second);
+
+        assert_eq!(second_user_data, first_user_data);
+        assert_eq!(physics.entities.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn despawned_colliders_map_to_no_entity() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_body(
+            &mut world,
+            "body",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let collider = world.attach_collider(entity, ColliderBuilder::ball(1.0).build())?;
+        assert_eq!(world.physics.collider_entity(collider), Some(entity));
+
+        world.despawn(entity)?;
+
+        assert!(world.physics.user_data.is_empty());
+        assert_eq!(world.physics.free_user_data.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn collider_user_data_is_rebuilt_after_loading() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_body(
+            &mut world,
+            "body",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let collider = world.attach_collider(entity, ColliderBuilder::ball(1.0).build())?;
+        // Colliders saved before user data was assigned are untagged
+        world.physics.colliders.get_mut(collider).unwrap().user_data = 0;
+
+        let loaded = World::from_bytes(&world.as_bytes()?)?;
+
+        assert_eq!(loaded.physics.collider_entity(collider), Some(entity));
+        Ok(())
+    }
+}
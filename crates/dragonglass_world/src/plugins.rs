@@ -0,0 +1,152 @@
+use crate::{DeltaTime, FixedDeltaTime, Plugin, Scheduler, Stage, Transform, World};
+use dragonglass_dependencies::{
+    anyhow::Result, legion::EntityStore, nalgebra_glm as glm, petgraph::graph::NodeIndex,
+};
+
+/// Steps the physics simulation during fixed updates
+/// and syncs rigid bodies with their entities
+#[derive(Default)]
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn name(&self) -> &str {
+        "physics"
+    }
+
+    fn build(&self, scheduler: &mut Scheduler) -> Result<()> {
+        scheduler.add_world_system(Stage::FixedUpdate, "physics", |world, resources| {
+            let delta_time = resources
+                .get::<FixedDeltaTime>()
+                .map(|delta_time| delta_time.0)
+                .unwrap_or_default();
+            world.tick(delta_time)
+        });
+        Ok(())
+    }
+}
+
+/// Controls which animation the animation plugin plays
+#[derive(Debug, Copy, Clone)]
+pub struct AnimationState {
+    pub index: Option<usize>,
+    pub speed: f32,
+}
+
+impl Default for AnimationState {
+    fn default() -> Self {
+        Self {
+            index: None,
+            speed: 1.0,
+        }
+    }
+}
+
+/// Plays the animation selected by the `AnimationState` resource
+#[derive(Default)]
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn name(&self) -> &str {
+        "animation"
+    }
+
+    fn build(&self, scheduler: &mut Scheduler) -> Result<()> {
+        scheduler.resources.insert(AnimationState::default());
+        scheduler.add_world_system(Stage::Update, "animation", |world, resources| {
+            let state = match resources.get::<AnimationState>() {
+                Some(state) => *state,
+                None => return Ok(()),
+            };
+            let delta_time = resources
+                .get::<DeltaTime>()
+                .map(|delta_time| delta_time.0)
+                .unwrap_or_default();
+            match state.index {
+                Some(index) if index < world.animations.len() => {
+                    world.animate(index, state.speed * delta_time)
+                }
+                _ => Ok(()),
+            }
+        });
+        Ok(())
+    }
+}
+
+/// The entity's transform in world space, written by the transform plugin
+#[derive(Debug, Copy, Clone)]
+pub struct GlobalTransform(pub glm::Mat4);
+
+/// Writes the `GlobalTransform` of every entity in the scene after updates,
+/// so systems can read world space transforms with a legion query
+#[derive(Default)]
+pub struct TransformPlugin;
+
+impl Plugin for TransformPlugin {
+    fn name(&self) -> &str {
+        "transform"
+    }
+
+    fn build(&self, scheduler: &mut Scheduler) -> Result<()> {
+        scheduler.add_world_system(Stage::PostUpdate, "transform_propagation", |world, _| {
+            world.propagate_transforms()
+        });
+        Ok(())
+    }
+}
+
+/// Adds the physics, animation, and transform plugins
+#[derive(Default)]
+pub struct DefaultPlugins;
+
+impl Plugin for DefaultPlugins {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn build(&self, scheduler: &mut Scheduler) -> Result<()> {
+        scheduler
+            .add_plugin(PhysicsPlugin)?
+            .add_plugin(AnimationPlugin)?
+            .add_plugin(TransformPlugin)?;
+        Ok(())
+    }
+}
+
+impl World {
+    /// Writes each entity's world space transform to its `GlobalTransform` component
+    pub fn propagate_transforms(&mut self) -> Result<()> {
+        let mut global_transforms = Vec::new();
+        for graph in self.scene.graphs.iter() {
+            let mut stack = graph
+                .roots()
+                .into_iter()
+                .map(|root| (root, glm::Mat4::identity()))
+                .collect::<Vec<(NodeIndex, glm::Mat4)>>();
+            while let Some((node_index, parent_transform)) = stack.pop() {
+                let entity = graph[node_index];
+                let local_transform = match self.ecs.entry_ref(entity)?.get_component::<Transform>()
+                {
+                    Ok(transform) => transform.matrix(),
+                    Err(_) => glm::Mat4::identity(),
+                };
+                let global_transform = parent_transform * local_transform;
+                global_transforms.push((entity, global_transform));
+                for child in graph.children(node_index) {
+                    stack.push((child, global_transform));
+                }
+            }
+        }
+
+        for (entity, global_transform) in global_transforms.into_iter() {
+            let mut entry = match self.ecs.entry(entity) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            match entry.get_component_mut::<GlobalTransform>() {
+                Ok(existing) => existing.0 = global_transform,
+                Err(_) => entry.add_component(GlobalTransform(global_transform)),
+            }
+        }
+        Ok(())
+    }
+}
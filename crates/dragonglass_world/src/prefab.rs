@@ -0,0 +1,830 @@
+use crate::{
+    Entity, History, Joint, ReloadPrefab, RigidBody, RigidBodySnapshot, Skin, World,
+    COMPONENT_REGISTRY,
+};
+use dragonglass_dependencies::{
+    anyhow::{bail, Context, Result},
+    legion::{EntityStore, IntoQuery},
+    log, nalgebra_glm as glm,
+    serde::{Deserialize, Serialize},
+    serde_json::{self, Map, Value},
+};
+use std::{collections::HashMap, path::Path, time::SystemTime};
+
+/// A serialized entity subtree that can be instantiated into a world
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct Prefab {
+    /// The root is first and parents come before their children
+    pub nodes: Vec<PrefabNode>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct PrefabNode {
+    pub parent: Option<usize>,
+    pub components: Map<String, Value>,
+    /// Rigid bodies hold physics handles, so the body is stored separately and recreated
+    #[serde(default)]
+    pub rigid_body: Option<RigidBodySnapshot>,
+    #[serde(default)]
+    pub skin: Option<PrefabSkin>,
+}
+
+/// A skin whose joints refer to nodes of the same prefab
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct PrefabSkin {
+    pub name: String,
+    pub joints: Vec<PrefabJoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct PrefabJoint {
+    pub node: usize,
+    pub inverse_bind_matrix: glm::Mat4,
+}
+
+impl Prefab {
+    pub fn from_entity(world: &World, entity: Entity) -> Result<Self> {
+        let entities = world.descendants(entity);
+        let mut nodes = Vec::with_capacity(entities.len());
+        for (index, entity) in entities.iter().enumerate() {
+            let parent = match index {
+                0 => None,
+                _ => world
+                    .parent_entity(*entity)
+                    .and_then(|parent| entities.iter().position(|entity| *entity == parent)),
+            };
+            let skin = match world.ecs.entry_ref(*entity)?.get_component::<Skin>() {
+                Ok(skin) => Some(PrefabSkin::new(skin, &entities)?),
+                Err(_) => None,
+            };
+            nodes.push(PrefabNode {
+                parent,
+                components: world.prefab_component_values(*entity)?,
+                rigid_body: world.rigid_body_snapshot(*entity)?,
+                skin,
+            });
+        }
+        Ok(Self { nodes })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read prefab '{}'!", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse prefab '{}'!", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl PrefabSkin {
+    /// Stores a skin's joints as node indices, failing if a joint is outside the prefab's entities
+    pub fn new(skin: &Skin, entities: &[Entity]) -> Result<Self> {
+        let joints = skin
+            .joints
+            .iter()
+            .map(|joint| {
+                let node = entities
+                    .iter()
+                    .position(|entity| *entity == joint.target)
+                    .with_context(|| {
+                        format!(
+                            "Failed to store skin '{}' in a prefab, a joint targets an entity outside of it!",
+                            skin.name
+                        )
+                    })?;
+                Ok(PrefabJoint {
+                    node,
+                    inverse_bind_matrix: joint.inverse_bind_matrix,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            name: skin.name.clone(),
+            joints,
+        })
+    }
+
+    /// Creates the skin for the entities spawned from the prefab's nodes
+    pub fn to_skin(&self, entities: &HashMap<usize, Entity>) -> Result<Skin> {
+        let joints = self
+            .joints
+            .iter()
+            .map(|joint| {
+                Ok(Joint {
+                    target: *entities.get(&joint.node).with_context(|| {
+                        format!("Skin '{}' targets a missing prefab node!", self.name)
+                    })?,
+                    inverse_bind_matrix: joint.inverse_bind_matrix,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Skin {
+            name: self.name.clone(),
+            joints,
+        })
+    }
+}
+
+/// Links an entity to the prefab node it was instantiated from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct PrefabInstance {
+    pub path: String,
+    pub node: usize,
+    /// The node's components when the entity was last synced with the prefab,
+    /// used to tell which fields the instance overrides
+    #[serde(with = "json_text")]
+    pub base: Map<String, Value>,
+}
+
+impl PrefabInstance {
+    /// Returns the paths of the fields that differ from the prefab, such as `transform/translation/0`
+    pub fn overrides(&self, current: &Map<String, Value>) -> Vec<String> {
+        let mut overrides = Vec::new();
+        diff_paths(
+            &Value::Object(self.base.clone()),
+            &Value::Object(current.clone()),
+            String::new(),
+            &mut overrides,
+        );
+        overrides
+    }
+}
+
+/// Stores json values as text, since bincode cannot deserialize self describing values
+mod json_text {
+    use dragonglass_dependencies::{
+        serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer},
+        serde_json::{self, Map, Value},
+    };
+
+    pub fn serialize<S: Serializer>(
+        values: &Map<String, Value>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let text = serde_json::to_string(values).map_err(S::Error::custom)?;
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Map<String, Value>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        serde_json::from_str(&text).map_err(D::Error::custom)
+    }
+}
+
+/// Tracks when prefab files were last modified,
+/// so instances are only reloaded after their prefab changes on disk
+#[derive(Default)]
+pub struct PrefabWatcher {
+    timestamps: HashMap<String, SystemTime>,
+}
+
+impl PrefabWatcher {
+    /// Records the prefab's current modification time
+    pub fn track(&mut self, path: &str) {
+        if let Some(modified) = modified_time(path) {
+            self.timestamps.insert(path.to_string(), modified);
+        }
+    }
+
+    /// Returns the world's prefabs whose files were modified since they were last seen.
+    /// Prefabs seen for the first time are tracked without being reported.
+    pub fn changed_paths(&mut self, world: &World) -> Vec<String> {
+        world
+            .prefab_paths()
+            .into_iter()
+            .filter(|path| {
+                let modified = match modified_time(path) {
+                    Some(modified) => modified,
+                    None => return false,
+                };
+                matches!(
+                    self.timestamps.insert(path.clone(), modified),
+                    Some(previous) if previous != modified
+                )
+            })
+            .collect()
+    }
+
+    /// Reloads the instances of every prefab that changed on disk as undoable commands.
+    /// A prefab that fails to load is reported and retried when it next changes.
+    pub fn reload_changed(&mut self, world: &mut World, history: &mut History) {
+        for path in self.changed_paths(world) {
+            if let Err(error) = history.execute_sealed(world, ReloadPrefab::new(&path)) {
+                log::error!("Failed to reload prefab '{}': {:?}", path, error);
+            }
+        }
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn diff_paths(base: &Value, current: &Value, path: String, paths: &mut Vec<String>) {
+    let child_path = |key: &str| match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}/{}", path, key),
+    };
+    match (base, current) {
+        (Value::Object(base), Value::Object(current)) => {
+            for key in base
+                .keys()
+                .chain(current.keys().filter(|key| !base.contains_key(*key)))
+            {
+                match (base.get(key), current.get(key)) {
+                    (Some(base), Some(current)) => {
+                        diff_paths(base, current, child_path(key), paths)
+                    }
+                    _ => paths.push(child_path(key)),
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(current)) if base.len() == current.len() => {
+            for (index, (base, current)) in base.iter().zip(current.iter()).enumerate() {
+                diff_paths(base, current, child_path(&index.to_string()), paths);
+            }
+        }
+        _ if base != current => paths.push(path),
+        _ => {}
+    }
+}
+
+/// Takes the prefab's new value wherever the instance still matches the old prefab value,
+/// keeping the instance's overridden fields
+fn merge_values(prefab: &Value, base: &Value, current: &Value) -> Value {
+    if base == current {
+        return prefab.clone();
+    }
+    match (prefab, base, current) {
+        (Value::Object(prefab), Value::Object(base), Value::Object(current)) => {
+            let mut merged = Map::new();
+            for (key, current_value) in current.iter() {
+                match (prefab.get(key), base.get(key)) {
+                    (Some(prefab_value), Some(base_value)) => {
+                        merged.insert(
+                            key.clone(),
+                            merge_values(prefab_value, base_value, current_value),
+                        );
+                    }
+                    // Removed from the prefab and not overridden
+                    (None, Some(base_value)) if base_value == current_value => {}
+                    _ => {
+                        merged.insert(key.clone(), current_value.clone());
+                    }
+                }
+            }
+            for (key, prefab_value) in prefab.iter() {
+                if !current.contains_key(key) && !base.contains_key(key) {
+                    merged.insert(key.clone(), prefab_value.clone());
+                }
+            }
+            Value::Object(merged)
+        }
+        (Value::Array(prefab), Value::Array(base), Value::Array(current))
+            if prefab.len() == base.len() && base.len() == current.len() =>
+        {
+            Value::Array(
+                prefab
+                    .iter()
+                    .zip(base.iter())
+                    .zip(current.iter())
+                    .map(|((prefab, base), current)| merge_values(prefab, base, current))
+                    .collect(),
+            )
+        }
+        _ => current.clone(),
+    }
+}
+
+impl World {
+    /// Returns the entity's registered components keyed by their registry keys
+    pub fn component_values(&self, entity: Entity) -> Result<Map<String, Value>> {
+        self.read_component_values(entity, false)
+    }
+
+    /// Returns the entity's components that can be stored in prefabs
+    pub fn prefab_component_values(&self, entity: Entity) -> Result<Map<String, Value>> {
+        self.read_component_values(entity, true)
+    }
+
+    fn read_component_values(
+        &self,
+        entity: Entity,
+        prefab_only: bool,
+    ) -> Result<Map<String, Value>> {
+        let registry = COMPONENT_REGISTRY
+            .read()
+            .expect("Failed to access component registry!");
+        let entry = self.ecs.entry_ref(entity)?;
+        let mut components = Map::new();
+        for (key, value) in registry.values.iter() {
+            if prefab_only && !value.prefab {
+                continue;
+            }
+            if let Some(component) = (value.read)(&entry)
+                .with_context(|| format!("Failed to read component '{}'!", key))?
+            {
+                components.insert(key.to_string(), component);
+            }
+        }
+        Ok(components)
+    }
+
+    /// Replaces the entity's prefab components with the given values
+    pub fn set_prefab_component_values(
+        &mut self,
+        entity: Entity,
+        components: &Map<String, Value>,
+    ) -> Result<()> {
+        let registry = COMPONENT_REGISTRY
+            .read()
+            .expect("Failed to access component registry!");
+        let values = &registry.values;
+        let mut entry = self
+            .ecs
+            .entry(entity)
+            .context("Failed to set components, the entity does not exist!")?;
+        for (key, value) in values.iter().filter(|(_, value)| value.prefab) {
+            match components.get(key) {
+                Some(component) => (value.write)(&mut entry, component.clone())
+                    .with_context(|| format!("Failed to write component '{}'!", key))?,
+                None => (value.remove)(&mut entry),
+            }
+        }
+        let skipped = components.keys().filter(|key| {
+            !values
+                .get(*key)
+                .map(|value| value.prefab)
+                .unwrap_or_default()
+        });
+        for key in skipped {
+            log::warn!(
+                "Skipping component '{}', it cannot be stored in prefabs",
+                key
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns one of the entity's registered components by its registry key
+    pub fn component_value(&self, entity: Entity, key: &str) -> Result<Option<Value>> {
+        let registry = COMPONENT_REGISTRY
+            .read()
+            .expect("Failed to access component registry!");
+        let values = &registry.values;
+        let value = values
+            .get(key)
+            .with_context(|| format!("The component '{}' is not registered!", key))?;
+        (value.read)(&self.ecs.entry_ref(entity)?)
+    }
+
+    /// Sets or removes one of the entity's registered components by its registry key
+    pub fn set_component_value(
+        &mut self,
+        entity: Entity,
+        key: &str,
+        component: Option<Value>,
+    ) -> Result<()> {
+        let registry = COMPONENT_REGISTRY
+            .read()
+            .expect("Failed to access component registry!");
+        let values = &registry.values;
+        let value = values
+            .get(key)
+            .with_context(|| format!("The component '{}' is not registered!", key))?;
+        let mut entry = self
+            .ecs
+            .entry(entity)
+            .context("Failed to set component, the entity does not exist!")?;
+        match component {
+            Some(component) => (value.write)(&mut entry, component)
+                .with_context(|| format!("Failed to write component '{}'!", key)),
+            None => {
+                (value.remove)(&mut entry);
+                Ok(())
+            }
+        }
+    }
+
+    /// Saves an entity and its descendants as a prefab,
+    /// turning them into an instance of it
+    pub fn save_prefab(&mut self, entity: Entity, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let prefab = Prefab::from_entity(self, entity)?;
+        prefab.save(path)?;
+        let path = path.display().to_string();
+        for (node, entity) in self.descendants(entity).into_iter().enumerate() {
+            let base = prefab.nodes[node].components.clone();
+            self.ecs
+                .entry(entity)
+                .context("Failed to link entity to its prefab!")?
+                .add_component(PrefabInstance {
+                    path: path.clone(),
+                    node,
+                    base,
+                });
+        }
+        log::info!("Saved prefab '{}'", path);
+        Ok(())
+    }
+
+    /// Creates an instance of a prefab as the last child of the parent,
+    /// or as a root of the default scenegraph if no parent is given
+    pub fn instantiate_prefab(
+        &mut self,
+        path: impl AsRef<Path>,
+        parent: Option<Entity>,
+    ) -> Result<Entity> {
+        let path = path.as_ref();
+        let prefab = Prefab::load(path)?;
+        let entities = self
+            .spawn_prefab(&prefab, parent)
+            .with_context(|| format!("Failed to instantiate prefab '{}'!", path.display()))?;
+        let path = path.display().to_string();
+        for (index, entity) in entities.iter().enumerate() {
+            self.link_prefab_node(*entity, &path, index, &prefab.nodes[index])?;
+        }
+        Ok(entities[0])
+    }
+
+    /// Creates the prefab's entities without linking them to a prefab file,
+    /// returning them in the same order as the prefab's nodes
+    pub fn spawn_prefab(&mut self, prefab: &Prefab, parent: Option<Entity>) -> Result<Vec<Entity>> {
+        if prefab.nodes.is_empty() {
+            bail!("The prefab has no nodes!");
+        }
+        let mut entities: Vec<Entity> = Vec::with_capacity(prefab.nodes.len());
+        for node in prefab.nodes.iter() {
+            let node_parent = match node.parent {
+                Some(parent) => Some(
+                    *entities
+                        .get(parent)
+                        .context("Prefab nodes must come after their parents!")?,
+                ),
+                None => parent,
+            };
+            let entity = self.spawn_prefab_node(node, node_parent)?;
+            entities.push(entity);
+        }
+        // Skins are added once every node exists, since their joints can target any node
+        let spawned = entities
+            .iter()
+            .copied()
+            .enumerate()
+            .collect::<HashMap<_, _>>();
+        for (node, entity) in prefab.nodes.iter().zip(entities.iter()) {
+            self.restore_prefab_node_links(*entity, node, &spawned)?;
+        }
+        Ok(entities)
+    }
+
+    fn spawn_prefab_node(&mut self, node: &PrefabNode, parent: Option<Entity>) -> Result<Entity> {
+        let entity = self.spawn_entity(parent)?;
+        self.set_prefab_component_values(entity, &node.components)?;
+        Ok(entity)
+    }
+
+    /// Recreates the node's rigid body and skin unless the entity already has them.
+    /// Instances keep their own physics and skins when a prefab is reloaded,
+    /// since these are not merged field by field.
+    fn restore_prefab_node_links(
+        &mut self,
+        entity: Entity,
+        node: &PrefabNode,
+        entities: &HashMap<usize, Entity>,
+    ) -> Result<()> {
+        let (has_rigid_body, has_skin) = {
+            let entry = self.ecs.entry_ref(entity)?;
+            (
+                entry.get_component::<RigidBody>().is_ok(),
+                entry.get_component::<Skin>().is_ok(),
+            )
+        };
+        if let (Some(snapshot), false) = (node.rigid_body.as_ref(), has_rigid_body) {
+            self.restore_rigid_body(entity, snapshot)?;
+        }
+        if let (Some(skin), false) = (node.skin.as_ref(), has_skin) {
+            let skin = skin.to_skin(entities)?;
+            self.ecs
+                .entry(entity)
+                .context("Failed to add prefab skin, the entity does not exist!")?
+                .add_component(skin);
+        }
+        Ok(())
+    }
+
+    fn link_prefab_node(
+        &mut self,
+        entity: Entity,
+        path: &str,
+        index: usize,
+        node: &PrefabNode,
+    ) -> Result<()> {
+        self.ecs
+            .entry(entity)
+            .context("Failed to link entity to its prefab!")?
+            .add_component(PrefabInstance {
+                path: path.to_string(),
+                node: index,
+                base: node.components.clone(),
+            });
+        Ok(())
+    }
+
+    /// Returns the paths of every prefab instantiated in the world
+    pub fn prefab_paths(&self) -> Vec<String> {
+        let mut paths = <&PrefabInstance>::query()
+            .iter(&self.ecs)
+            .map(|instance| instance.path.clone())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Updates every instance of a prefab from its file,
+    /// keeping the fields each instance overrides.
+    /// Returns the number of instances updated.
+    pub fn reload_prefab(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let prefab = Prefab::load(path)?;
+        let path = path.display().to_string();
+
+        let roots = <(Entity, &PrefabInstance)>::query()
+            .iter(&self.ecs)
+            .filter(|(_, instance)| instance.path == path && instance.node == 0)
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+
+        for root in roots.iter() {
+            let mut nodes = HashMap::new();
+            for entity in self.descendants(*root) {
+                let entry = self.ecs.entry_ref(entity)?;
+                if let Ok(instance) = entry.get_component::<PrefabInstance>() {
+                    if instance.path == path {
+                        nodes.entry(instance.node).or_insert(entity);
+                    }
+                }
+            }
+
+            let mut entities = HashMap::new();
+            for (index, node) in prefab.nodes.iter().enumerate() {
+                let entity = match nodes.remove(&index) {
+                    Some(entity) => {
+                        self.merge_prefab_node(entity, node)?;
+                        entity
+                    }
+                    None => {
+                        let parent = node
+                            .parent
+                            .and_then(|parent| entities.get(&parent))
+                            .copied();
+                        if parent.is_none() {
+                            continue;
+                        }
+                        let entity = self.spawn_prefab_node(node, parent)?;
+                        self.link_prefab_node(entity, &path, index, node)?;
+                        entity
+                    }
+                };
+                entities.insert(index, entity);
+            }
+            for (index, entity) in entities.iter() {
+                self.restore_prefab_node_links(*entity, &prefab.nodes[*index], &entities)?;
+            }
+
+            // Nodes removed from the prefab
+            for (_, entity) in nodes.into_iter() {
+                if self.ecs.contains(entity) {
+                    self.despawn(entity)?;
+                }
+            }
+        }
+
+        log::info!(
+            "Reloaded prefab '{}' into {} instance(s)",
+            path,
+            roots.len()
+        );
+        Ok(roots.len())
+    }
+
+    fn merge_prefab_node(&mut self, entity: Entity, node: &PrefabNode) -> Result<()> {
+        let base = match self
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<PrefabInstance>()
+        {
+            Ok(instance) => Value::Object(instance.base.clone()),
+            Err(_) => return Ok(()),
+        };
+        let current = Value::Object(self.prefab_component_values(entity)?);
+        let prefab = Value::Object(node.components.clone());
+        if let Value::Object(merged) = merge_values(&prefab, &base, &current) {
+            self.set_prefab_component_values(entity, &merged)?;
+        }
+        let mut entry = self.ecs.entry_mut(entity)?;
+        entry.get_component_mut::<PrefabInstance>()?.base = node.components.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::spawn_root, Name, Transform};
+    use dragonglass_dependencies::{
+        bincode,
+        rapier3d::{dynamics::RigidBodyType, geometry::ColliderBuilder},
+    };
+    use std::path::PathBuf;
+
+    fn skinned_character(world: &mut World) -> Result<(Entity, Entity, Entity)> {
+        let character = spawn_root(world, "Character")?;
+        let mesh = world.spawn_child(character, "Mesh")?;
+        let bone = world.spawn_child(character, "Bone")?;
+        world.ecs.entry(mesh).unwrap().add_component(Skin {
+            name: "Skin".to_string(),
+            joints: vec![Joint {
+                target: bone,
+                inverse_bind_matrix: glm::Mat4::identity(),
+            }],
+        });
+        Ok((character, mesh, bone))
+    }
+
+    fn prefab_path(name: &str) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join("dragonglass_prefab_tests");
+        std::fs::create_dir_all(&directory)?;
+        Ok(directory.join(name).with_extension("prefab"))
+    }
+
+    fn name_of(world: &World, entity: Entity) -> Result<String> {
+        Ok(world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<Name>()?
+            .0
+            .clone())
+    }
+
+    #[test]
+    fn reloading_updates_instances_and_keeps_their_overrides() -> Result<()> {
+        let mut world = World::new()?;
+        let source = spawn_root(&mut world, "Crate")?;
+        let lid = world.spawn_child(source, "Lid")?;
+        let path = prefab_path("reload_overrides")?;
+        Prefab::from_entity(&world, source)?.save(&path)?;
+        let overridden = world.instantiate_prefab(&path, None)?;
+        let untouched = world.instantiate_prefab(&path, None)?;
+        world
+            .ecs
+            .entry_mut(overridden)?
+            .get_component_mut::<Name>()?
+            .0 = "Renamed".to_string();
+
+        world.ecs.entry_mut(source)?.get_component_mut::<Name>()?.0 = "Box".to_string();
+        world.ecs.entry_mut(lid)?.get_component_mut::<Name>()?.0 = "Top".to_string();
+        world
+            .ecs
+            .entry_mut(source)?
+            .get_component_mut::<Transform>()?
+            .translation = glm::vec3(1.0, 2.0, 3.0);
+        Prefab::from_entity(&world, source)?.save(&path)?;
+
+        assert_eq!(world.reload_prefab(&path)?, 2);
+
+        assert_eq!(name_of(&world, overridden)?, "Renamed");
+        assert_eq!(name_of(&world, untouched)?, "Box");
+        for instance in [overridden, untouched] {
+            let entry = world.ecs.entry_ref(instance)?;
+            let transform = entry.get_component::<Transform>()?;
+            assert_eq!(transform.translation, glm::vec3(1.0, 2.0, 3.0));
+            let children = world.children(instance);
+            assert_eq!(children.len(), 1);
+            assert_eq!(name_of(&world, children[0])?, "Top");
+        }
+        let values = world.prefab_component_values(overridden)?;
+        let entry = world.ecs.entry_ref(overridden)?;
+        let instance = entry.get_component::<PrefabInstance>()?;
+        assert_eq!(instance.overrides(&values), vec!["name"]);
+        Ok(())
+    }
+
+    #[test]
+    fn overrides_compare_against_the_typed_base() -> Result<()> {
+        let mut base = Map::new();
+        base.insert("name".to_string(), serde_json::json!("Crate"));
+        base.insert("light".to_string(), serde_json::json!({ "intensity": 1.0 }));
+        let instance = PrefabInstance {
+            path: "crate.prefab".to_string(),
+            node: 0,
+            base: base.clone(),
+        };
+
+        let mut current = base;
+        current.insert("light".to_string(), serde_json::json!({ "intensity": 2.0 }));
+
+        assert_eq!(instance.overrides(&current), vec!["light/intensity"]);
+        Ok(())
+    }
+
+    #[test]
+    fn prefab_instances_round_trip_through_bincode() -> Result<()> {
+        let mut base = Map::new();
+        base.insert("name".to_string(), serde_json::json!("Crate"));
+        let instance = PrefabInstance {
+            path: "crate.prefab".to_string(),
+            node: 2,
+            base,
+        };
+
+        let bytes = bincode::serialize(&instance)?;
+        let restored: PrefabInstance = bincode::deserialize(&bytes)?;
+
+        assert_eq!(restored.node, 2);
+        assert_eq!(restored.base, instance.base);
+        Ok(())
+    }
+
+    #[test]
+    fn prefabs_store_rigid_bodies_and_skins() -> Result<()> {
+        let mut world = World::new()?;
+        let (character, _, _) = skinned_character(&mut world)?;
+        world.add_rigid_body(character, RigidBodyType::Dynamic)?;
+        world.attach_collider(character, ColliderBuilder::ball(1.0).build())?;
+        let text = serde_json::to_string(&Prefab::from_entity(&world, character)?)?;
+        let prefab: Prefab = serde_json::from_str(&text)?;
+
+        let entities = world.spawn_prefab(&prefab, None)?;
+
+        let colliders = world
+            .ecs
+            .entry_ref(entities[0])?
+            .get_component::<RigidBody>()?
+            .colliders
+            .clone();
+        assert_eq!(colliders.len(), 1);
+        assert_eq!(
+            world.physics.collider_entity(colliders[0]),
+            Some(entities[0])
+        );
+        let entry = world.ecs.entry_ref(entities[1])?;
+        let skin = entry.get_component::<Skin>()?;
+        assert_eq!(skin.name, "Skin");
+        assert_eq!(skin.joints[0].target, entities[2]);
+        Ok(())
+    }
+
+    #[test]
+    fn prefabs_reject_skins_with_joints_outside_the_prefab() -> Result<()> {
+        let mut world = World::new()?;
+        let (_, mesh, _) = skinned_character(&mut world)?;
+
+        assert!(Prefab::from_entity(&world, mesh).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unregistered_component_keys_are_rejected() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_root(&mut world, "Crate")?;
+
+        assert!(world.component_value(entity, "missing").is_err());
+        assert!(world
+            .set_component_value(entity, "missing", Some(serde_json::json!(1)))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn registry_excludes_physics_and_hierarchy_from_prefab_values() {
+        let registry = COMPONENT_REGISTRY.read().unwrap();
+        for key in [
+            "parent",
+            "children",
+            "skin",
+            "rigid_body",
+            "prefab_instance",
+        ] {
+            assert!(!registry.values[key].prefab, "{}", key);
+        }
+        for key in ["name", "transform", "mesh"] {
+            assert!(registry.values[key].prefab, "{}", key);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{BoundingBox, Entity, World};
+use dragonglass_dependencies::{
+    nalgebra::{Isometry3, Point3},
+    nalgebra_glm as glm,
+    rapier3d::{
+        geometry::{ColliderHandle, Shape, AABB},
+        prelude::{QueryFilter, Ray},
+    },
+};
+
+#[derive(Debug, Copy, Clone)]
+pub struct RayHit {
+    pub entity: Option<Entity>,
+    pub collider: ColliderHandle,
+    pub point: glm::Vec3,
+    pub normal: glm::Vec3,
+    pub distance: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ShapeHit {
+    pub entity: Option<Entity>,
+    pub collider: ColliderHandle,
+    /// The distance travelled along the cast velocity before impact
+    pub time_of_impact: f32,
+    pub point: glm::Vec3,
+    pub normal: glm::Vec3,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct PointProjection {
+    pub entity: Option<Entity>,
+    pub collider: ColliderHandle,
+    pub point: glm::Vec3,
+    pub is_inside: bool,
+}
+
+impl World {
+    pub fn collider_entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.physics.collider_entity(handle)
+    }
+
+    pub fn cast_ray_nearest(
+        &self,
+        ray: &Ray,
+        max_distance: f32,
+        filter: QueryFilter,
+    ) -> Option<RayHit> {
+        self.physics
+            .query_pipeline
+            .cast_ray_and_get_normal(
+                &self.physics.bodies,
+                &self.physics.colliders,
+                ray,
+                max_distance,
+                true,
+                filter,
+            )
+            .map(|(collider, intersection)| RayHit {
+                entity: self.collider_entity(collider),
+                collider,
+                point: ray.point_at(intersection.toi).coords,
+                normal: intersection.normal,
+                distance: intersection.toi,
+            })
+    }
+
+    /// Returns every hit along the ray, sorted from nearest to farthest
+    pub fn cast_ray_all(&self, ray: &Ray, max_distance: f32, filter: QueryFilter) -> Vec<RayHit> {
+        let mut hits = Vec::new();
+        self.physics.query_pipeline.intersections_with_ray(
+            &self.physics.bodies,
+            &self.physics.colliders,
+            ray,
+            max_distance,
+            true,
+            filter,
+            |collider, intersection| {
+                hits.push(RayHit {
+                    entity: self.collider_entity(collider),
+                    collider,
+                    point: ray.point_at(intersection.toi).coords,
+                    normal: intersection.normal,
+                    distance: intersection.toi,
+                });
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Sweeps a shape from a position along a velocity, returning the first hit
+    pub fn cast_shape(
+        &self,
+        position: &Isometry3<f32>,
+        velocity: &glm::Vec3,
+        shape: &dyn Shape,
+        max_time_of_impact: f32,
+        filter: QueryFilter,
+    ) -> Option<ShapeHit> {
+        self.physics
+            .query_pipeline
+            .cast_shape(
+                &self.physics.bodies,
+                &self.physics.colliders,
+                position,
+                velocity,
+                shape,
+                max_time_of_impact,
+                filter,
+            )
+            .map(|(collider, toi)| {
+                let collider_position = self.physics.colliders[collider].position();
+                ShapeHit {
+                    entity: self.collider_entity(collider),
+                    collider,
+                    time_of_impact: toi.toi,
+                    point: (collider_position * toi.witness2).coords,
+                    normal: collider_position * toi.normal2.into_inner(),
+                }
+            })
+    }
+
+    pub fn project_point(
+        &self,
+        point: &glm::Vec3,
+        solid: bool,
+        filter: QueryFilter,
+    ) -> Option<PointProjection> {
+        self.physics
+            .query_pipeline
+            .project_point(
+                &self.physics.bodies,
+                &self.physics.colliders,
+                &Point3::from(*point),
+                solid,
+                filter,
+            )
+            .map(|(collider, projection)| PointProjection {
+                entity: self.collider_entity(collider),
+                collider,
+                point: projection.point.coords,
+                is_inside: projection.is_inside,
+            })
+    }
+
+    /// Returns the entities whose collider bounds overlap the bounding box
+    pub fn entities_intersecting_aabb(&self, bounding_box: &BoundingBox) -> Vec<Entity> {
+        let aabb = AABB::new(
+            Point3::from(bounding_box.min),
+            Point3::from(bounding_box.max),
+        );
+        let mut entities = Vec::new();
+        self.physics
+            .query_pipeline
+            .colliders_with_aabb_intersecting_aabb(&aabb, |collider| {
+                if let Some(entity) = self.collider_entity(*collider) {
+                    if !entities.contains(&entity) {
+                        entities.push(entity);
+                    }
+                }
+                true
+            });
+        entities
+    }
+
+    /// Returns the entities whose colliders overlap the shape
+    pub fn entities_intersecting_shape(
+        &self,
+        position: &Isometry3<f32>,
+        shape: &dyn Shape,
+        filter: QueryFilter,
+    ) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        self.physics.query_pipeline.intersections_with_shape(
+            &self.physics.bodies,
+            &self.physics.colliders,
+            position,
+            shape,
+            filter,
+            |collider| {
+                if let Some(entity) = self.collider_entity(collider) {
+                    if !entities.contains(&entity) {
+                        entities.push(entity);
+                    }
+                }
+                true
+            },
+        );
+        entities
+    }
+}
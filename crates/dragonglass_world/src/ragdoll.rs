@@ -0,0 +1,812 @@
+use crate::{
+    Entity, JointHandle, JointKind, MeshRender, Name, PhysicsJoint, Skin, Transform, World,
+};
+use dragonglass_dependencies::{
+    anyhow::{bail, Context, Result},
+    legion::{EntityStore, IntoQuery},
+    nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion},
+    nalgebra_glm as glm,
+    rapier3d::{
+        dynamics::{
+            GenericJoint, RigidBody as PhysicsBody, RigidBodyBuilder, RigidBodyHandle,
+            RigidBodyType,
+        },
+        geometry::{Collider, ColliderBuilder, ColliderHandle, InteractionGroups},
+    },
+    serde::{Deserialize, Serialize},
+};
+
+/// Drives the joints of a skinned entity from physics bodies.
+/// While inactive, the bodies follow the animated pose kinematically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct Ragdoll {
+    pub bones: Vec<RagdollBone>,
+    pub active: bool,
+    /// 0.0 is fully animated, 1.0 is fully simulated
+    pub blend: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct RagdollBone {
+    pub target: Entity,
+    pub parent: Option<usize>,
+    pub body: RigidBodyHandle,
+    pub collider: ColliderHandle,
+    pub joint: Option<JointHandle>,
+    /// The target's pose before blending, which active ragdolls blend from
+    #[serde(default)]
+    pub animated: Option<Transform>,
+    /// The pose last blended onto the target,
+    /// so a pose written since then is known to come from an animation
+    #[serde(default)]
+    pub blended: Option<Transform>,
+}
+
+/// Copies of a ragdoll's bodies, colliders, and joints,
+/// used to recreate the ragdoll after its entity is despawned
+#[derive(Clone)]
+pub struct RagdollSnapshot {
+    pub bones: Vec<RagdollBoneSnapshot>,
+    pub active: bool,
+    pub blend: f32,
+}
+
+#[derive(Clone)]
+pub struct RagdollBoneSnapshot {
+    pub target: Entity,
+    pub parent: Option<usize>,
+    pub body: PhysicsBody,
+    pub collider: Collider,
+    pub joint: Option<GenericJoint>,
+    /// Whether the joint belongs to a multibody rather than being an impulse joint
+    pub multibody: bool,
+}
+
+pub struct RagdollBuilder {
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub density: f32,
+    pub collision_groups: InteractionGroups,
+    pub twist_limits: [f32; 2],
+    pub swing_limits: [f32; 2],
+    pub hinge_limits: [f32; 2],
+    /// Bones with names containing any of these use a revolute joint instead of a spherical joint
+    pub hinge_names: Vec<String>,
+}
+
+impl Default for RagdollBuilder {
+    fn default() -> Self {
+        Self {
+            min_radius: 0.02,
+            max_radius: 0.25,
+            density: 1.0,
+            collision_groups: InteractionGroups::all(),
+            twist_limits: [-20_f32.to_radians(), 20_f32.to_radians()],
+            swing_limits: [-45_f32.to_radians(), 45_f32.to_radians()],
+            hinge_limits: [0.0, 130_f32.to_radians()],
+            hinge_names: ["knee", "elbow", "shin", "calf", "forearm"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl RagdollBuilder {
+    pub fn build(&self, world: &mut World, skinned_entity: Entity) -> Result<()> {
+        if world
+            .ecs
+            .entry_ref(skinned_entity)?
+            .get_component::<Ragdoll>()
+            .is_ok()
+        {
+            bail!("Entity already has a ragdoll!");
+        }
+
+        let (joint_targets, bound_vertices) = {
+            let entry = world.ecs.entry_ref(skinned_entity)?;
+            let skin = entry.get_component::<Skin>()?;
+            let bound_vertices = match entry.get_component::<MeshRender>() {
+                Ok(mesh_render) => bound_vertices(world, skin, &mesh_render.name),
+                Err(_) => vec![Vec::new(); skin.joints.len()],
+            };
+            let joint_targets = skin
+                .joints
+                .iter()
+                .map(|joint| joint.target)
+                .collect::<Vec<_>>();
+            (joint_targets, bound_vertices)
+        };
+
+        // Parents must be created before their children
+        let mut order = (0..joint_targets.len()).collect::<Vec<_>>();
+        let depths = joint_targets
+            .iter()
+            .map(|target| world.entity_depth(*target))
+            .collect::<Vec<_>>();
+        order.sort_by_key(|index| depths[*index]);
+
+        let global_transforms = joint_targets
+            .iter()
+            .map(|target| world.entity_global_transform(*target))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut bones: Vec<RagdollBone> = Vec::new();
+        let mut bone_indices = vec![0; joint_targets.len()];
+        for joint_index in order.into_iter() {
+            let target = joint_targets[joint_index];
+            let transform = global_transforms[joint_index];
+            let isometry = transform.as_isometry();
+
+            let children = joint_targets
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| world.parent_entity(**child) == Some(target))
+                .map(|(index, _)| global_transforms[index].translation)
+                .collect::<Vec<_>>();
+
+            let (segment_end, radius) =
+                self.segment(&isometry, &children, &bound_vertices[joint_index]);
+            let half_height = (glm::length(&segment_end) / 2.0 - radius).max(0.0);
+            let collider_position = Isometry3::from_parts(
+                Translation3::from(segment_end / 2.0),
+                UnitQuaternion::rotation_between(&glm::Vec3::y(), &segment_end)
+                    .unwrap_or_else(UnitQuaternion::identity),
+            );
+
+            let body = world.physics.bodies.insert(
+                RigidBodyBuilder::new(RigidBodyType::KinematicPositionBased)
+                    .position(isometry)
+                    .build(),
+            );
+            let user_data = world.physics.entity_user_data(target);
+            let collider = world.physics.colliders.insert_with_parent(
+                ColliderBuilder::capsule_y(half_height, radius)
+                    .position(collider_position)
+                    .user_data(user_data)
+                    .density(self.density)
+                    .collision_groups(self.collision_groups)
+                    .build(),
+                body,
+                &mut world.physics.bodies,
+            );
+
+            let parent = world
+                .ancestors(target)
+                .into_iter()
+                .find_map(|ancestor| joint_targets.iter().position(|t| *t == ancestor))
+                .map(|parent_joint_index| bone_indices[parent_joint_index]);
+
+            let joint = match parent {
+                Some(parent) => {
+                    let parent_bone: &RagdollBone = &bones[parent];
+                    let parent_isometry = world
+                        .physics
+                        .bodies
+                        .get(parent_bone.body)
+                        .context("Failed to find ragdoll parent body!")?
+                        .position();
+                    let anchor = parent_isometry
+                        .inverse_transform_point(&Point3::from(transform.translation));
+                    let name = world
+                        .ecs
+                        .entry_ref(target)?
+                        .get_component::<Name>()
+                        .map(|name| name.0.to_lowercase())
+                        .unwrap_or_default();
+                    let physics_joint =
+                        PhysicsJoint::new(self.joint_kind(&name), parent_bone.target)
+                            .with_anchors(glm::Vec3::zeros(), anchor.coords);
+                    world
+                        .physics
+                        .insert_joint(parent_bone.body, body, &physics_joint)
+                }
+                None => None,
+            };
+
+            bone_indices[joint_index] = bones.len();
+            bones.push(RagdollBone {
+                target,
+                parent,
+                body,
+                collider,
+                joint,
+                animated: None,
+                blended: None,
+            });
+        }
+
+        world
+            .ecs
+            .entry(skinned_entity)
+            .context("Failed to find entity!")?
+            .add_component(Ragdoll {
+                bones,
+                active: false,
+                blend: 0.0,
+            });
+
+        Ok(())
+    }
+
+    fn joint_kind(&self, name: &str) -> JointKind {
+        if self.hinge_names.iter().any(|hinge| name.contains(hinge)) {
+            JointKind::Revolute {
+                axis: glm::Vec3::x(),
+                limits: Some(self.hinge_limits),
+                motor: None,
+            }
+        } else {
+            JointKind::Spherical {
+                twist_limits: Some(self.twist_limits),
+                swing_limits: Some(self.swing_limits),
+            }
+        }
+    }
+
+    /// Returns the end of the bone segment in the bone's local space and the capsule radius
+    fn segment(
+        &self,
+        isometry: &Isometry3<f32>,
+        children: &[glm::Vec3],
+        bound_vertices: &[glm::Vec3],
+    ) -> (glm::Vec3, f32) {
+        let segment_end = if !children.is_empty() {
+            let center =
+                children.iter().fold(glm::Vec3::zeros(), |sum, c| sum + c) / children.len() as f32;
+            isometry
+                .inverse_transform_point(&Point3::from(center))
+                .coords
+        } else if !bound_vertices.is_empty() {
+            // Leaf bones extend to the farthest vertex they influence
+            let center = bound_vertices
+                .iter()
+                .fold(glm::Vec3::zeros(), |sum, v| sum + v)
+                / bound_vertices.len() as f32;
+            let direction = glm::normalize(&center);
+            let length = bound_vertices
+                .iter()
+                .map(|vertex| glm::dot(vertex, &direction))
+                .fold(0.0, f32::max);
+            direction * length
+        } else {
+            glm::Vec3::y() * self.min_radius * 2.0
+        };
+
+        let length = glm::length(&segment_end);
+        let radius = if bound_vertices.is_empty() || length <= f32::EPSILON {
+            length * 0.25
+        } else {
+            let axis = segment_end / length;
+            let total_distance = bound_vertices
+                .iter()
+                .map(|vertex| glm::length(&(vertex - axis * glm::dot(vertex, &axis))))
+                .sum::<f32>();
+            total_distance / bound_vertices.len() as f32
+        };
+
+        let radius = glm::clamp_scalar(radius, self.min_radius, self.max_radius.min(length / 2.0));
+        (segment_end, radius)
+    }
+}
+
+/// Collects the vertices most influenced by each joint, in the joint's local space
+fn bound_vertices(world: &World, skin: &Skin, mesh_name: &str) -> Vec<Vec<glm::Vec3>> {
+    let mut vertices = vec![Vec::new(); skin.joints.len()];
+    let mesh = match world.geometry.meshes.get(mesh_name) {
+        Some(mesh) => mesh,
+        None => return vertices,
+    };
+    for primitive in mesh.primitives.iter() {
+        let range = primitive.first_vertex..primitive.first_vertex + primitive.number_of_vertices;
+        for vertex in world.geometry.vertices[range].iter() {
+            let (strongest, weight) = (0..4)
+                .map(|index| (vertex.joint_0[index] as usize, vertex.weight_0[index]))
+                .fold((0, 0.0), |best, candidate| {
+                    if candidate.1 > best.1 {
+                        candidate
+                    } else {
+                        best
+                    }
+                });
+            if weight < 0.5 || strongest >= skin.joints.len() {
+                continue;
+            }
+            let local = skin.joints[strongest].inverse_bind_matrix
+                * glm::vec4(vertex.position.x, vertex.position.y, vertex.position.z, 1.0);
+            vertices[strongest].push(local.xyz());
+        }
+    }
+    vertices
+}
+
+impl World {
+    pub fn set_ragdoll_active(&mut self, entity: Entity, active: bool) -> Result<()> {
+        let mut entry = self.ecs.entry(entity).context("Failed to find entity!")?;
+        let ragdoll = entry.get_component_mut::<Ragdoll>()?;
+        ragdoll.active = active;
+        let body_type = if active {
+            RigidBodyType::Dynamic
+        } else {
+            RigidBodyType::KinematicPositionBased
+        };
+        for bone in ragdoll.bones.iter() {
+            if let Some(body) = self.physics.bodies.get_mut(bone.body) {
+                body.set_body_type(body_type);
+                body.wake_up(true);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_ragdoll_blend(&mut self, entity: Entity, blend: f32) -> Result<()> {
+        self.ecs
+            .entry_mut(entity)?
+            .get_component_mut::<Ragdoll>()?
+            .blend = glm::clamp_scalar(blend, 0.0, 1.0);
+        Ok(())
+    }
+
+    /// Copies the entity's ragdoll, if it has one
+    pub fn ragdoll_snapshot(&self, entity: Entity) -> Result<Option<RagdollSnapshot>> {
+        let entry = self.ecs.entry_ref(entity)?;
+        let ragdoll = match entry.get_component::<Ragdoll>() {
+            Ok(ragdoll) => ragdoll,
+            Err(_) => return Ok(None),
+        };
+        let mut bones = Vec::with_capacity(ragdoll.bones.len());
+        for bone in ragdoll.bones.iter() {
+            let joint = match bone.joint {
+                Some(JointHandle::Impulse(handle)) => self
+                    .physics
+                    .impulse_joints
+                    .get(handle)
+                    .map(|joint| joint.data),
+                Some(JointHandle::Multibody(handle)) => self
+                    .physics
+                    .multibody_joints
+                    .get(handle)
+                    .and_then(|(multibody, link)| multibody.link(link))
+                    .map(|link| link.joint.data),
+                None => None,
+            };
+            bones.push(RagdollBoneSnapshot {
+                target: bone.target,
+                parent: bone.parent,
+                body: self
+                    .physics
+                    .bodies
+                    .get(bone.body)
+                    .context("Failed to copy ragdoll, a bone body no longer exists!")?
+                    .clone(),
+                collider: self
+                    .physics
+                    .colliders
+                    .get(bone.collider)
+                    .context("Failed to copy ragdoll, a bone collider no longer exists!")?
+                    .clone(),
+                joint,
+                multibody: matches!(bone.joint, Some(JointHandle::Multibody(_))),
+            });
+        }
+        Ok(Some(RagdollSnapshot {
+            bones,
+            active: ragdoll.active,
+            blend: ragdoll.blend,
+        }))
+    }
+
+    /// Recreates a ragdoll from a snapshot, with `resolve` mapping
+    /// the snapshot's bone targets to the entities that currently stand in for them
+    pub fn restore_ragdoll(
+        &mut self,
+        entity: Entity,
+        snapshot: &RagdollSnapshot,
+        resolve: impl Fn(Entity) -> Entity,
+    ) -> Result<()> {
+        let mut bones: Vec<RagdollBone> = Vec::with_capacity(snapshot.bones.len());
+        for bone in snapshot.bones.iter() {
+            let target = resolve(bone.target);
+            let body = self.physics.bodies.insert(bone.body.clone());
+            let mut collider = bone.collider.clone();
+            collider.user_data = self.physics.entity_user_data(target);
+            let collider =
+                self.physics
+                    .colliders
+                    .insert_with_parent(collider, body, &mut self.physics.bodies);
+            let joint = match (bone.parent, bone.joint) {
+                (Some(parent), Some(data)) => {
+                    let parent_body = bones
+                        .get(parent)
+                        .context("Ragdoll bones must come after their parents!")?
+                        .body;
+                    if bone.multibody {
+                        self.physics
+                            .multibody_joints
+                            .insert(parent_body, body, data, true)
+                            .map(JointHandle::Multibody)
+                    } else {
+                        let handle =
+                            self.physics
+                                .impulse_joints
+                                .insert(parent_body, body, data, true);
+                        Some(JointHandle::Impulse(handle))
+                    }
+                }
+                _ => None,
+            };
+            bones.push(RagdollBone {
+                target,
+                parent: bone.parent,
+                body,
+                collider,
+                joint,
+                animated: None,
+                blended: None,
+            });
+        }
+        self.ecs
+            .entry(entity)
+            .context("Failed to restore ragdoll, the entity does not exist!")?
+            .add_component(Ragdoll {
+                bones,
+                active: snapshot.active,
+                blend: snapshot.blend,
+            });
+        Ok(())
+    }
+
+    pub fn remove_ragdoll(&mut self, entity: Entity) -> Result<()> {
+        let mut entry = self.ecs.entry(entity).context("Failed to find entity!")?;
+        let ragdoll = entry.get_component::<Ragdoll>()?.clone();
+        entry.remove_component::<Ragdoll>();
+        for bone in ragdoll.bones.into_iter() {
+            self.physics.remove_rigid_body(bone.body);
+        }
+        Ok(())
+    }
+
+    /// Blends the joint transforms of active ragdolls towards their simulated pose.
+    /// Inactive ragdolls have their bodies moved to follow the animated pose instead.
+    ///
+    /// Blending always starts from the animated pose, rather than the previous frame's blend,
+    /// so a partial blend holds steady instead of creeping towards the simulated pose.
+    pub fn sync_ragdolls(&mut self) -> Result<()> {
+        let mut query = <(Entity, &Ragdoll)>::query();
+        let ragdolls = query
+            .iter(&self.ecs)
+            .map(|(entity, ragdoll)| (*entity, ragdoll.clone()))
+            .collect::<Vec<_>>();
+        for (entity, mut ragdoll) in ragdolls.into_iter() {
+            for bone in ragdoll.bones.iter_mut() {
+                let current = *self
+                    .ecs
+                    .entry_ref(bone.target)?
+                    .get_component::<Transform>()?;
+
+                if !ragdoll.active {
+                    bone.animated = Some(current);
+                    bone.blended = None;
+                    let isometry = self.entity_global_transform(bone.target)?.as_isometry();
+                    if let Some(body) = self.physics.bodies.get_mut(bone.body) {
+                        body.set_next_kinematic_position(isometry);
+                    }
+                    continue;
+                }
+
+                let body_isometry = match self.physics.bodies.get(bone.body) {
+                    Some(body) => *body.position(),
+                    None => continue,
+                };
+
+                // Anything other than last frame's blend was written by an animation
+                let animated = match (bone.animated, bone.blended) {
+                    (Some(animated), Some(blended)) if same_pose(&current, &blended) => animated,
+                    _ => current,
+                };
+                let parent_matrix = match self.parent_entity(bone.target) {
+                    Some(parent) => self.entity_global_transform_matrix(parent)?,
+                    None => glm::Mat4::identity(),
+                };
+                let simulated_global = Transform::new(
+                    body_isometry.translation.vector,
+                    *body_isometry.rotation.quaternion(),
+                    glm::vec3(1.0, 1.0, 1.0),
+                );
+                let simulated =
+                    Transform::from(glm::inverse(&parent_matrix) * simulated_global.matrix());
+
+                let mut blended = animated;
+                blended.translation =
+                    glm::mix(&animated.translation, &simulated.translation, ragdoll.blend);
+                blended.rotation =
+                    glm::quat_slerp(&animated.rotation, &simulated.rotation, ragdoll.blend);
+                *self
+                    .ecs
+                    .entry_mut(bone.target)?
+                    .get_component_mut::<Transform>()? = blended;
+                bone.animated = Some(animated);
+                bone.blended = Some(blended);
+            }
+            *self.ecs.entry_mut(entity)?.get_component_mut::<Ragdoll>()? = ragdoll;
+        }
+        Ok(())
+    }
+}
+
+fn same_pose(first: &Transform, second: &Transform) -> bool {
+    first.translation == second.translation
+        && first.rotation == second.rotation
+        && first.scale == second.scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Joint;
+
+    /// Spawns a hip, thigh, and shin chain bound to a skinned entity
+    fn spawn_leg(world: &mut World) -> Result<(Entity, Vec<Entity>)> {
+        let hips = world
+            .ecs
+            .push((Transform::default(), Name("Hips".to_string())));
+        world.scene.default_scenegraph_mut()?.add_node(hips);
+        world.sync_hierarchy()?;
+        let thigh = world.spawn_child(hips, "Thigh")?;
+        let shin = world.spawn_child(thigh, "Shin")?;
+        for entity in [thigh, shin] {
+            world
+                .ecs
+                .entry_mut(entity)?
+                .get_component_mut::<Transform>()?
+                .translation = glm::vec3(0.0, -1.0, 0.0);
+        }
+
+        let joints = [hips, thigh, shin]
+            .iter()
+            .map(|target| Joint {
+                target: *target,
+                inverse_bind_matrix: glm::Mat4::identity(),
+            })
+            .collect();
+        let skinned_entity = world.ecs.push((
+            Transform::default(),
+            Skin {
+                name: "Leg".to_string(),
+                joints,
+            },
+        ));
+        Ok((skinned_entity, vec![hips, thigh, shin]))
+    }
+
+    fn ragdoll(world: &World, entity: Entity) -> Result<Ragdoll> {
+        Ok(world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<Ragdoll>()?
+            .clone())
+    }
+
+    #[test]
+    fn build_creates_a_bone_per_joint() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, targets) = spawn_leg(&mut world)?;
+
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+
+        let ragdoll = ragdoll(&world, skinned_entity)?;
+        assert!(!ragdoll.active);
+        assert_eq!(ragdoll.blend, 0.0);
+        assert_eq!(
+            ragdoll
+                .bones
+                .iter()
+                .map(|bone| bone.target)
+                .collect::<Vec<_>>(),
+            targets
+        );
+        assert_eq!(world.physics.bodies.len(), 3);
+        assert_eq!(world.physics.colliders.len(), 3);
+        for bone in ragdoll.bones.iter() {
+            let body = world.physics.bodies.get(bone.body).unwrap();
+            assert_eq!(body.body_type(), RigidBodyType::KinematicPositionBased);
+            assert_eq!(
+                world.physics.collider_entity(bone.collider),
+                Some(bone.target)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn build_joins_bones_to_their_parents() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, _) = spawn_leg(&mut world)?;
+
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+
+        let ragdoll = ragdoll(&world, skinned_entity)?;
+        let parents = ragdoll
+            .bones
+            .iter()
+            .map(|bone| bone.parent)
+            .collect::<Vec<_>>();
+        assert_eq!(parents, vec![None, Some(0), Some(1)]);
+        assert!(ragdoll.bones[0].joint.is_none());
+        assert!(ragdoll.bones[1..]
+            .iter()
+            .all(|bone| matches!(bone.joint, Some(JointHandle::Impulse(_)))));
+        assert_eq!(world.physics.impulse_joints.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn build_uses_hinges_for_named_bones() {
+        let builder = RagdollBuilder::default();
+
+        assert!(matches!(
+            builder.joint_kind("left_shin"),
+            JointKind::Revolute { .. }
+        ));
+        assert!(matches!(
+            builder.joint_kind("left_thigh"),
+            JointKind::Spherical { .. }
+        ));
+    }
+
+    #[test]
+    fn build_rejects_existing_ragdoll() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, _) = spawn_leg(&mut world)?;
+        let builder = RagdollBuilder::default();
+        builder.build(&mut world, skinned_entity)?;
+
+        assert!(builder.build(&mut world, skinned_entity).is_err());
+        assert_eq!(world.physics.bodies.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn segment_points_at_child_joints() {
+        let builder = RagdollBuilder::default();
+
+        let (segment_end, radius) =
+            builder.segment(&Isometry3::identity(), &[glm::vec3(0.0, -1.0, 0.0)], &[]);
+
+        assert_eq!(segment_end, glm::vec3(0.0, -1.0, 0.0));
+        assert_eq!(radius, 0.25);
+    }
+
+    #[test]
+    fn partial_blend_holds_steady_between_frames() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, targets) = spawn_leg(&mut world)?;
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+        world.sync_ragdolls()?;
+        world.set_ragdoll_active(skinned_entity, true)?;
+        world.set_ragdoll_blend(skinned_entity, 0.5)?;
+
+        let shin = targets[2];
+        let animated = world
+            .ecs
+            .entry_ref(shin)?
+            .get_component::<Transform>()?
+            .translation;
+        let shin_body = ragdoll(&world, skinned_entity)?.bones[2].body;
+        let mut isometry = *world.physics.bodies.get(shin_body).unwrap().position();
+        isometry.translation.vector += glm::vec3(2.0, 0.0, 0.0);
+        world
+            .physics
+            .bodies
+            .get_mut(shin_body)
+            .unwrap()
+            .set_position(isometry, true);
+
+        let translation = |world: &World| -> Result<glm::Vec3> {
+            Ok(world
+                .ecs
+                .entry_ref(shin)?
+                .get_component::<Transform>()?
+                .translation)
+        };
+        world.sync_ragdolls()?;
+        let first = translation(&world)?;
+        for _ in 0..10 {
+            world.sync_ragdolls()?;
+        }
+        let last = translation(&world)?;
+
+        assert!(glm::distance(&first, &animated) > 0.5);
+        assert!(glm::distance(&first, &last) < 1e-4);
+        Ok(())
+    }
+
+    #[test]
+    fn blending_follows_a_new_animated_pose() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, targets) = spawn_leg(&mut world)?;
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+        world.set_ragdoll_active(skinned_entity, true)?;
+        world.sync_ragdolls()?;
+
+        // An animation writing a new pose replaces the stored one
+        let shin = targets[2];
+        let posed = glm::vec3(0.0, -3.0, 0.0);
+        world
+            .ecs
+            .entry_mut(shin)?
+            .get_component_mut::<Transform>()?
+            .translation = posed;
+        world.sync_ragdolls()?;
+
+        let bone = ragdoll(&world, skinned_entity)?.bones[2].clone();
+        assert_eq!(bone.animated.map(|pose| pose.translation), Some(posed));
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_restores_multibody_joints() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, _) = spawn_leg(&mut world)?;
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+
+        // Swap the shin's impulse joint for a multibody joint
+        let mut ragdoll_component = ragdoll(&world, skinned_entity)?;
+        let (parent_body, body) = (
+            ragdoll_component.bones[1].body,
+            ragdoll_component.bones[2].body,
+        );
+        let data = match ragdoll_component.bones[2].joint {
+            Some(JointHandle::Impulse(handle)) => {
+                world
+                    .physics
+                    .impulse_joints
+                    .remove(handle, true)
+                    .unwrap()
+                    .data
+            }
+            _ => panic!("Expected an impulse joint!"),
+        };
+        ragdoll_component.bones[2].joint = world
+            .physics
+            .multibody_joints
+            .insert(parent_body, body, data, true)
+            .map(JointHandle::Multibody);
+        *world
+            .ecs
+            .entry_mut(skinned_entity)?
+            .get_component_mut::<Ragdoll>()? = ragdoll_component;
+
+        let snapshot = world.ragdoll_snapshot(skinned_entity)?.unwrap();
+        assert!(snapshot.bones[2].multibody);
+        world.remove_ragdoll(skinned_entity)?;
+        world.restore_ragdoll(skinned_entity, &snapshot, |target| target)?;
+
+        let restored = ragdoll(&world, skinned_entity)?;
+        assert!(matches!(
+            restored.bones[1].joint,
+            Some(JointHandle::Impulse(_))
+        ));
+        assert!(matches!(
+            restored.bones[2].joint,
+            Some(JointHandle::Multibody(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn remove_ragdoll_removes_bodies() -> Result<()> {
+        let mut world = World::new()?;
+        let (skinned_entity, _) = spawn_leg(&mut world)?;
+        RagdollBuilder::default().build(&mut world, skinned_entity)?;
+
+        world.remove_ragdoll(skinned_entity)?;
+
+        assert!(ragdoll(&world, skinned_entity).is_err());
+        assert_eq!(world.physics.bodies.len(), 0);
+        assert_eq!(world.physics.impulse_joints.len(), 0);
+        Ok(())
+    }
+}
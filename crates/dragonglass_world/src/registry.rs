@@ -0,0 +1,131 @@
+use crate::{
+    Camera, CameraBookmarks, Children, Light, MeshRender, Name, Parent, PhysicsJoint,
+    PrefabInstance, Ragdoll, RigidBody, Skin, Transform, ENTITY_SERIALIZER,
+};
+use dragonglass_dependencies::{
+    anyhow::Result,
+    lazy_static::lazy_static,
+    legion::{
+        serialize::set_entity_serializer,
+        storage::Component,
+        world::{Entry, EntryRef},
+        Registry,
+    },
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::{self, Value},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+lazy_static! {
+    pub static ref COMPONENT_REGISTRY: Arc<RwLock<ComponentRegistry>> = {
+        let mut registry = ComponentRegistry::default();
+        registry.register::<Name>("name");
+        registry.register::<Transform>("transform");
+        registry.register::<Camera>("camera");
+        registry.register::<MeshRender>("mesh");
+        registry.register::<Light>("light");
+        // These reference other entities or physics handles
+        registry.register_excluded_from_prefabs::<Parent>("parent");
+        registry.register_excluded_from_prefabs::<Children>("children");
+        registry.register_excluded_from_prefabs::<CameraBookmarks>("camera_bookmarks");
+        registry.register_excluded_from_prefabs::<Skin>("skin");
+        registry.register_excluded_from_prefabs::<RigidBody>("rigid_body");
+        registry.register_excluded_from_prefabs::<PhysicsJoint>("physics_joint");
+        registry.register_excluded_from_prefabs::<Ragdoll>("ragdoll");
+        registry.register_excluded_from_prefabs::<PrefabInstance>("prefab_instance");
+        Arc::new(RwLock::new(registry))
+    };
+}
+
+pub fn register_component<T: Component + Serialize + DeserializeOwned>(key: &str) -> Result<()> {
+    COMPONENT_REGISTRY
+        .write()
+        .expect("Failed to access component registry!")
+        .register::<T>(key);
+    Ok(())
+}
+
+/// The components that can be saved, keyed by the names used in world files and prefabs
+#[derive(Default)]
+pub struct ComponentRegistry {
+    /// Serializes the ecs
+    pub registry: Registry<String>,
+    /// Converts each component to and from a json value, under the same keys
+    pub values: HashMap<String, ComponentValue>,
+}
+
+impl ComponentRegistry {
+    pub fn register<T: Component + Serialize + DeserializeOwned>(&mut self, key: &str) {
+        self.insert::<T>(key, ComponentValue::of::<T>());
+    }
+
+    /// Registers a component that cannot be stored in a prefab's components
+    pub fn register_excluded_from_prefabs<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        key: &str,
+    ) {
+        self.insert::<T>(key, ComponentValue::of::<T>().excluded_from_prefabs());
+    }
+
+    fn insert<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        key: &str,
+        value: ComponentValue,
+    ) {
+        self.registry.register::<T>(key.to_string());
+        self.values.insert(key.to_string(), value);
+    }
+}
+
+/// Converts a registered component to and from a json value
+#[derive(Copy, Clone)]
+pub struct ComponentValue {
+    pub read: fn(&EntryRef) -> Result<Option<Value>>,
+    pub write: fn(&mut Entry, Value) -> Result<()>,
+    pub remove: fn(&mut Entry),
+    /// Components that reference other entities or physics handles
+    /// cannot be stored in prefab components
+    pub prefab: bool,
+}
+
+impl ComponentValue {
+    pub fn of<T: Component + Serialize + DeserializeOwned>() -> Self {
+        Self {
+            read: read_component_value::<T>,
+            write: write_component_value::<T>,
+            remove: remove_component_value::<T>,
+            prefab: true,
+        }
+    }
+
+    pub fn excluded_from_prefabs(mut self) -> Self {
+        self.prefab = false;
+        self
+    }
+}
+
+fn read_component_value<T: Component + Serialize>(entry: &EntryRef) -> Result<Option<Value>> {
+    match entry.get_component::<T>() {
+        Ok(component) => Ok(Some(set_entity_serializer(&*ENTITY_SERIALIZER, || {
+            serde_json::to_value(component)
+        })?)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn write_component_value<T: Component + DeserializeOwned>(
+    entry: &mut Entry,
+    value: Value,
+) -> Result<()> {
+    let component: T =
+        set_entity_serializer(&*ENTITY_SERIALIZER, || serde_json::from_value(value))?;
+    entry.add_component(component);
+    Ok(())
+}
+
+fn remove_component_value<T: Component>(entry: &mut Entry) {
+    entry.remove_component::<T>();
+}
@@ -0,0 +1,293 @@
+use crate::World;
+use dragonglass_dependencies::{
+    anyhow::{bail, Context, Result},
+    legion::{
+        systems::{Builder, ParallelRunnable, Runnable},
+        Resources, Schedule,
+    },
+};
+use std::collections::HashMap;
+
+/// The stages a frame is divided into, in the order they run
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Stage {
+    PreUpdate,
+    /// Runs zero or more times per frame at the scheduler's fixed time step
+    FixedUpdate,
+    Update,
+    PostUpdate,
+    RenderPrep,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::PreUpdate,
+        Stage::FixedUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::RenderPrep,
+    ];
+}
+
+/// The time in seconds since the last frame
+#[derive(Default, Debug, Copy, Clone)]
+pub struct DeltaTime(pub f32);
+
+/// The time in seconds simulated by each fixed update
+#[derive(Default, Debug, Copy, Clone)]
+pub struct FixedDeltaTime(pub f32);
+
+/// A system with access to the whole world, for work outside the ecs such as physics
+pub type WorldSystem = Box<dyn FnMut(&mut World, &mut Resources) -> Result<()>>;
+
+/// A reusable bundle of systems and resources
+pub trait Plugin {
+    fn name(&self) -> &str;
+    fn build(&self, scheduler: &mut Scheduler) -> Result<()>;
+}
+
+pub struct Scheduler {
+    pub resources: Resources,
+    pub fixed_time_step: f32,
+    /// Limits how many fixed updates a single slow frame can trigger
+    pub max_fixed_steps: usize,
+    /// When false the fixed-update and update stages only run for requested steps,
+    /// so editors can pause the simulation while editing
+    pub simulating: bool,
+    pending_steps: usize,
+    accumulator: f32,
+    builders: HashMap<Stage, Builder>,
+    schedules: HashMap<Stage, Schedule>,
+    world_systems: HashMap<Stage, Vec<(String, WorldSystem)>>,
+    plugins: Vec<String>,
+    started: bool,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            resources: Resources::default(),
+            fixed_time_step: 1.0 / 60.0,
+            max_fixed_steps: 8,
+            simulating: true,
+            pending_steps: 0,
+            accumulator: 0.0,
+            builders: HashMap::new(),
+            schedules: HashMap::new(),
+            world_systems: HashMap::new(),
+            plugins: Vec::new(),
+            started: false,
+        }
+    }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin) -> Result<&mut Self> {
+        if self.has_plugin(plugin.name()) {
+            bail!("The plugin '{}' has already been added!", plugin.name());
+        }
+        plugin.build(self)?;
+        self.plugins.push(plugin.name().to_string());
+        Ok(self)
+    }
+
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.plugins.iter().any(|plugin| plugin == name)
+    }
+
+    /// Adds a legion system that runs on the ecs during a stage
+    pub fn add_system(
+        &mut self,
+        stage: Stage,
+        system: impl ParallelRunnable + 'static,
+    ) -> Result<&mut Self> {
+        self.builder(stage)?.add_system(system);
+        Ok(self)
+    }
+
+    /// Adds a legion system that runs on the main thread during a stage
+    pub fn add_thread_local(
+        &mut self,
+        stage: Stage,
+        system: impl Runnable + 'static,
+    ) -> Result<&mut Self> {
+        self.builder(stage)?.add_thread_local(system);
+        Ok(self)
+    }
+
+    /// Adds a system with access to the whole world.
+    /// World systems run after the stage's legion systems, in the order they were added.
+    pub fn add_world_system(
+        &mut self,
+        stage: Stage,
+        name: &str,
+        system: impl FnMut(&mut World, &mut Resources) -> Result<()> + 'static,
+    ) -> &mut Self {
+        self.world_systems
+            .entry(stage)
+            .or_default()
+            .push((name.to_string(), Box::new(system)));
+        self
+    }
+
+    fn builder(&mut self, stage: Stage) -> Result<&mut Builder> {
+        if self.started {
+            bail!("Legion systems must be added before the scheduler first runs!");
+        }
+        Ok(self.builders.entry(stage).or_insert_with(Schedule::builder))
+    }
+
+    /// Advances a paused simulation by one fixed step on the next update
+    pub fn step(&mut self) {
+        self.pending_steps += 1;
+    }
+
+    /// Runs the pre-update, fixed-update, and update stages.
+    /// While not simulating only the pre-update stage runs, unless a step was requested.
+    pub fn update(&mut self, world: &mut World, delta_time: f32) -> Result<()> {
+        self.resources.insert(DeltaTime(delta_time));
+        self.run_stage(Stage::PreUpdate, world)?;
+
+        self.resources.insert(FixedDeltaTime(self.fixed_time_step));
+
+        if !self.simulating {
+            self.accumulator = 0.0;
+            if self.pending_steps == 0 {
+                return Ok(());
+            }
+            self.pending_steps -= 1;
+            self.resources.insert(DeltaTime(self.fixed_time_step));
+            self.run_stage(Stage::FixedUpdate, world)?;
+            return self.run_stage(Stage::Update, world);
+        }
+        self.pending_steps = 0;
+
+        self.accumulator += delta_time;
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_time_step && steps < self.max_fixed_steps {
+            self.run_stage(Stage::FixedUpdate, world)?;
+            self.accumulator -= self.fixed_time_step;
+            steps += 1;
+        }
+        if steps == self.max_fixed_steps {
+            // Drop the remaining time instead of trying to catch up
+            self.accumulator = 0.0;
+        }
+
+        self.run_stage(Stage::Update, world)
+    }
+
+    /// Runs the post-update stage
+    pub fn post_update(&mut self, world: &mut World) -> Result<()> {
+        self.run_stage(Stage::PostUpdate, world)
+    }
+
+    /// Runs the render-prep stage
+    pub fn render_prep(&mut self, world: &mut World) -> Result<()> {
+        self.run_stage(Stage::RenderPrep, world)
+    }
+
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World) -> Result<()> {
+        if !self.started {
+            self.build_schedules();
+        }
+        if let Some(schedule) = self.schedules.get_mut(&stage) {
+            schedule.execute(&mut world.ecs, &mut self.resources);
+        }
+        if let Some(systems) = self.world_systems.get_mut(&stage) {
+            for (name, system) in systems.iter_mut() {
+                system(world, &mut self.resources)
+                    .with_context(|| format!("System '{}' failed during {:?}!", name, stage))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn build_schedules(&mut self) {
+        for (stage, mut builder) in self.builders.drain() {
+            self.schedules.insert(stage, builder.build());
+        }
+        self.started = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for an app resource, such as input, inserted before each update
+    struct Presses(u32);
+
+    #[derive(Default)]
+    struct Seen(Vec<u32>);
+
+    fn recording_scheduler(stage: Stage) -> Scheduler {
+        let mut scheduler = Scheduler::new();
+        scheduler.resources.insert(Seen::default());
+        scheduler.add_world_system(stage, "record", |_, resources| {
+            let presses = resources.get::<Presses>().map(|presses| presses.0);
+            if let (Some(presses), Some(mut seen)) = (presses, resources.get_mut::<Seen>()) {
+                seen.0.push(presses);
+            }
+            Ok(())
+        });
+        scheduler
+    }
+
+    fn seen(scheduler: &Scheduler) -> Vec<u32> {
+        scheduler
+            .resources
+            .get::<Seen>()
+            .map(|seen| seen.0.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn systems_read_resources_inserted_before_update() -> Result<()> {
+        let mut world = World::new()?;
+        let mut scheduler = recording_scheduler(Stage::Update);
+
+        scheduler.resources.insert(Presses(1));
+        scheduler.update(&mut world, 0.0)?;
+        scheduler.resources.insert(Presses(2));
+        scheduler.update(&mut world, 0.0)?;
+
+        assert_eq!(seen(&scheduler), vec![1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_updates_run_per_elapsed_step() -> Result<()> {
+        let mut world = World::new()?;
+        let mut scheduler = recording_scheduler(Stage::FixedUpdate);
+        scheduler.resources.insert(Presses(0));
+
+        scheduler.update(&mut world, scheduler.fixed_time_step * 2.5)?;
+        assert_eq!(seen(&scheduler).len(), 2);
+
+        scheduler.update(&mut world, scheduler.fixed_time_step * 0.75)?;
+        assert_eq!(seen(&scheduler).len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn paused_schedulers_only_run_requested_steps() -> Result<()> {
+        let mut world = World::new()?;
+        let mut scheduler = recording_scheduler(Stage::FixedUpdate);
+        scheduler.resources.insert(Presses(0));
+        scheduler.simulating = false;
+
+        scheduler.update(&mut world, 1.0)?;
+        assert!(seen(&scheduler).is_empty());
+
+        scheduler.step();
+        scheduler.update(&mut world, 1.0)?;
+        scheduler.update(&mut world, 1.0)?;
+        assert_eq!(seen(&scheduler).len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{BoundingBox, Entity, MeshRender, Transform, Viewport, World};
+use dragonglass_dependencies::{anyhow::Result, legion::EntityStore, nalgebra_glm as glm};
+
+/// How picked entities combine with an existing selection
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SelectionMode {
+    Replace,
+    Add,
+    Toggle,
+}
+
+/// The entities selected in an editor, in the order they were selected.
+/// The most recently selected entity is the primary one,
+/// which the gizmo is placed on and the inspector shows.
+#[derive(Default, Debug, Clone)]
+pub struct Selection {
+    entities: Vec<Entity>,
+}
+
+impl Selection {
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn primary(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    /// Selects only the given entity
+    pub fn set(&mut self, entity: Entity) {
+        self.entities.clear();
+        self.entities.push(entity);
+    }
+
+    /// Adds an entity to the selection and makes it the primary entity
+    pub fn add(&mut self, entity: Entity) {
+        self.entities.retain(|selected| *selected != entity);
+        self.entities.push(entity);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        self.entities.retain(|selected| *selected != entity);
+    }
+
+    pub fn toggle(&mut self, entity: Entity) {
+        if self.contains(entity) {
+            self.remove(entity);
+        } else {
+            self.add(entity);
+        }
+    }
+
+    pub fn apply(&mut self, entities: &[Entity], mode: SelectionMode) {
+        if mode == SelectionMode::Replace {
+            self.entities.clear();
+        }
+        for entity in entities.iter() {
+            match mode {
+                SelectionMode::Replace | SelectionMode::Add => self.add(*entity),
+                SelectionMode::Toggle => self.toggle(*entity),
+            }
+        }
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&Entity) -> bool) {
+        self.entities.retain(keep);
+    }
+
+    /// Replaces each selected entity, such as with the entities that undo and redo recreated
+    pub fn map(&mut self, mut replace: impl FnMut(Entity) -> Entity) {
+        let mut entities = Vec::new();
+        for entity in self.entities.drain(..).map(&mut replace) {
+            if !entities.contains(&entity) {
+                entities.push(entity);
+            }
+        }
+        self.entities = entities;
+    }
+
+    /// The selected entities that have no selected ancestor.
+    /// Moving these moves the rest of the selection with them.
+    pub fn roots(&self, world: &World) -> Vec<Entity> {
+        self.entities
+            .iter()
+            .copied()
+            .filter(|entity| {
+                !world
+                    .ancestors(*entity)
+                    .iter()
+                    .any(|ancestor| self.contains(*ancestor))
+            })
+            .collect()
+    }
+
+    /// The world space bounds of the selected entities.
+    /// Entities without a mesh contribute their origin.
+    pub fn bounding_box(&self, world: &World) -> Result<Option<BoundingBox>> {
+        if self.entities.is_empty() {
+            return Ok(None);
+        }
+        let mut bounding_box = BoundingBox::new_invalid();
+        for entity in self.entities.iter() {
+            match world.entity_bounding_box(*entity)? {
+                Some(entity_bounding_box) => bounding_box.fit_box(&entity_bounding_box),
+                None => bounding_box.fit_point(world.entity_global_transform(*entity)?.translation),
+            }
+        }
+        Ok(Some(bounding_box))
+    }
+}
+
+/// The global transforms of a group of entities when a drag of one of them began,
+/// used to move the rest of the group along with it
+pub struct GroupDrag {
+    pivot_start: glm::Mat4,
+    members: Vec<GroupMember>,
+}
+
+struct GroupMember {
+    entity: Entity,
+    global_start: glm::Mat4,
+    parent_global: glm::Mat4,
+}
+
+impl GroupDrag {
+    /// Records the members of the selection that should follow the pivot.
+    /// The pivot's selected ancestors are left out, since moving them would move the pivot too.
+    pub fn new(world: &World, pivot: Entity, selection: &Selection) -> Result<Self> {
+        let pivot_ancestors = world.ancestors(pivot);
+        let mut members = Vec::new();
+        for entity in selection.roots(world).into_iter() {
+            if entity == pivot || pivot_ancestors.contains(&entity) {
+                continue;
+            }
+            let parent_global = match world.parent_entity(entity) {
+                Some(parent) => world.entity_global_transform_matrix(parent)?,
+                None => glm::Mat4::identity(),
+            };
+            members.push(GroupMember {
+                entity,
+                global_start: world.entity_global_transform_matrix(entity)?,
+                parent_global,
+            });
+        }
+        Ok(Self {
+            pivot_start: world.entity_global_transform_matrix(pivot)?,
+            members,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The local transforms that move each member the way the pivot moved
+    pub fn transforms(&self, pivot_global: &glm::Mat4) -> Vec<(Entity, Transform)> {
+        let delta = pivot_global * glm::inverse(&self.pivot_start);
+        self.members
+            .iter()
+            .map(|member| {
+                let global = delta * member.global_start;
+                let local = glm::inverse(&member.parent_global) * global;
+                (member.entity, Transform::from(local))
+            })
+            .collect()
+    }
+}
+
+impl World {
+    /// The entity's mesh bounds in world space, if it has a mesh
+    pub fn entity_bounding_box(&self, entity: Entity) -> Result<Option<BoundingBox>> {
+        let mesh_bounding_box = {
+            let entry = self.ecs.entry_ref(entity)?;
+            let mesh_render = match entry.get_component::<MeshRender>() {
+                Ok(mesh_render) => mesh_render,
+                Err(_) => return Ok(None),
+            };
+            match self.geometry.meshes.get(&mesh_render.name) {
+                Some(mesh) => mesh.bounding_box(),
+                None => return Ok(None),
+            }
+        };
+
+        let model =
+            self.entity_model_matrix(entity, self.entity_global_transform_matrix(entity)?)?;
+        let mut bounding_box = BoundingBox::new_invalid();
+        for corner in box_corners(&mesh_bounding_box).iter() {
+            bounding_box.fit_point((model * glm::vec4(corner.x, corner.y, corner.z, 1.0)).xyz());
+        }
+        Ok(Some(bounding_box))
+    }
+
+    /// Returns the entities whose projected bounds overlap a rectangle on screen.
+    /// The rectangle is in pixels from the top left of the viewport.
+    /// Entities without a mesh are picked by their origin, and the active camera is never picked.
+    pub fn entities_in_screen_rect(
+        &self,
+        min: glm::Vec2,
+        max: glm::Vec2,
+        viewport: Viewport,
+        projection: &glm::Mat4,
+        view: &glm::Mat4,
+    ) -> Result<Vec<Entity>> {
+        let active_camera = self.active_camera().ok();
+        let view_projection = projection * view;
+        let project = |point: &glm::Vec3| -> Option<glm::Vec2> {
+            let clip = view_projection * glm::vec4(point.x, point.y, point.z, 1.0);
+            if clip.w <= 0.0 {
+                return None;
+            }
+            let ndc = clip.xy() / clip.w;
+            Some(glm::vec2(
+                (ndc.x + 1.0) * 0.5 * viewport.width,
+                (1.0 - ndc.y) * 0.5 * viewport.height,
+            ))
+        };
+
+        let mut entities = Vec::new();
+        for graph in self.scene.graphs.iter() {
+            for node_index in graph.0.node_indices() {
+                let entity = graph[node_index];
+                if Some(entity) == active_camera {
+                    continue;
+                }
+
+                let points = match self.entity_bounding_box(entity)? {
+                    Some(bounding_box) => box_corners(&bounding_box).to_vec(),
+                    None => vec![self.entity_global_transform(entity)?.translation],
+                };
+                let projected = points.iter().filter_map(project).collect::<Vec<_>>();
+                if projected.is_empty() {
+                    continue;
+                }
+
+                let screen_min = projected
+                    .iter()
+                    .fold(glm::vec2(f32::MAX, f32::MAX), |a, b| glm::min2(&a, b));
+                let screen_max = projected
+                    .iter()
+                    .fold(glm::vec2(f32::MIN, f32::MIN), |a, b| glm::max2(&a, b));
+                let overlaps = screen_min.x <= max.x
+                    && screen_max.x >= min.x
+                    && screen_min.y <= max.y
+                    && screen_max.y >= min.y;
+                if overlaps {
+                    entities.push(entity);
+                }
+            }
+        }
+        Ok(entities)
+    }
+}
+
+fn box_corners(bounding_box: &BoundingBox) -> [glm::Vec3; 8] {
+    let (min, max) = (bounding_box.min, bounding_box.max);
+    [
+        glm::vec3(min.x, min.y, min.z),
+        glm::vec3(max.x, min.y, min.z),
+        glm::vec3(min.x, max.y, min.z),
+        glm::vec3(max.x, max.y, min.z),
+        glm::vec3(min.x, min.y, max.z),
+        glm::vec3(max.x, min.y, max.z),
+        glm::vec3(min.x, max.y, max.z),
+        glm::vec3(max.x, max.y, max.z),
+    ]
+}
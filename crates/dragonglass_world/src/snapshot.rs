@@ -0,0 +1,335 @@
+use crate::{Entity, RigidBody, World};
+use dragonglass_dependencies::{
+    anyhow::Result,
+    legion::{EntityStore, IntoQuery},
+    nalgebra_glm as glm,
+    rapier3d::dynamics::RigidBodyType,
+    serde_json::Value,
+};
+use std::collections::{BTreeSet, HashSet};
+
+/// Differences smaller than this are ignored when comparing physics states
+pub const PHYSICS_DIFF_EPSILON: f32 = 1e-5;
+
+/// A serialized copy of a world.
+/// Entities keep their ids when the snapshot is restored in the same process.
+#[derive(Debug, Clone)]
+pub struct WorldSnapshot {
+    bytes: Vec<u8>,
+}
+
+impl WorldSnapshot {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Deserializes the snapshot into a new world
+    pub fn to_world(&self) -> Result<World> {
+        World::from_bytes(&self.bytes)
+    }
+}
+
+/// The changes between two worlds
+#[derive(Default, Debug, Clone)]
+pub struct WorldDiff {
+    pub added_entities: Vec<Entity>,
+    pub removed_entities: Vec<Entity>,
+    pub changed_components: Vec<ComponentChange>,
+    pub changed_bodies: Vec<BodyChange>,
+}
+
+impl WorldDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.changed_components.is_empty()
+            && self.changed_bodies.is_empty()
+    }
+
+    /// Returns the component changes made to an entity
+    pub fn entity_changes(&self, entity: Entity) -> impl Iterator<Item = &ComponentChange> {
+        self.changed_components
+            .iter()
+            .filter(move |change| change.entity == entity)
+    }
+}
+
+/// A registered component that was added, removed, or modified on an entity
+/// present in both worlds
+#[derive(Debug, Clone)]
+pub struct ComponentChange {
+    pub entity: Entity,
+    pub key: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// A rigid body whose simulation state changed
+#[derive(Debug, Clone)]
+pub struct BodyChange {
+    pub entity: Entity,
+    pub before: BodyState,
+    pub after: BodyState,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BodyState {
+    pub body_type: RigidBodyType,
+    pub translation: glm::Vec3,
+    pub rotation: glm::Quat,
+    pub linear_velocity: glm::Vec3,
+    pub angular_velocity: glm::Vec3,
+    pub sleeping: bool,
+}
+
+impl BodyState {
+    /// Compares two states, ignoring differences below `PHYSICS_DIFF_EPSILON`
+    pub fn approx_eq(&self, other: &BodyState) -> bool {
+        let close = |a: &glm::Vec3, b: &glm::Vec3| (a - b).amax() <= PHYSICS_DIFF_EPSILON;
+        self.body_type == other.body_type
+            && self.sleeping == other.sleeping
+            && close(&self.translation, &other.translation)
+            && (self.rotation.coords - other.rotation.coords).amax() <= PHYSICS_DIFF_EPSILON
+            && close(&self.linear_velocity, &other.linear_velocity)
+            && close(&self.angular_velocity, &other.angular_velocity)
+    }
+}
+
+impl World {
+    /// Captures the world using the same serialization as `as_bytes`
+    pub fn snapshot(&self) -> Result<WorldSnapshot> {
+        Ok(WorldSnapshot::from_bytes(self.as_bytes()?))
+    }
+
+    /// Replaces the world with a snapshot
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> Result<()> {
+        *self = snapshot.to_world()?;
+        Ok(())
+    }
+
+    /// Returns every entity in the ecs
+    pub fn entities(&self) -> Vec<Entity> {
+        <Entity>::query().iter(&self.ecs).copied().collect()
+    }
+
+    /// Returns the simulation state of the entity's rigid body, if it has one
+    pub fn body_state(&self, entity: Entity) -> Option<BodyState> {
+        let entry = self.ecs.entry_ref(entity).ok()?;
+        let handle = entry.get_component::<RigidBody>().ok()?.handle;
+        let body = self.physics.bodies.get(handle)?;
+        Some(BodyState {
+            body_type: body.body_type(),
+            translation: *body.translation(),
+            rotation: body.rotation().into_inner(),
+            linear_velocity: *body.linvel(),
+            angular_velocity: *body.angvel(),
+            sleeping: body.is_sleeping(),
+        })
+    }
+
+    /// Returns the changes needed to turn this world into the other world.
+    /// Entities are matched by id, so the worlds should share an origin,
+    /// such as a world and a restored snapshot of it.
+    pub fn diff(&self, other: &World) -> Result<WorldDiff> {
+        let before = self.entities().into_iter().collect::<HashSet<_>>();
+        let after = other.entities().into_iter().collect::<HashSet<_>>();
+
+        let mut diff = WorldDiff {
+            added_entities: after.difference(&before).copied().collect(),
+            removed_entities: before.difference(&after).copied().collect(),
+            ..Default::default()
+        };
+
+        for entity in before.intersection(&after).copied() {
+            let before_components = self.component_values(entity)?;
+            let after_components = other.component_values(entity)?;
+            let keys = before_components
+                .keys()
+                .chain(after_components.keys())
+                .collect::<BTreeSet<_>>();
+            for key in keys {
+                let (before_value, after_value) =
+                    (before_components.get(key), after_components.get(key));
+                if before_value != after_value {
+                    diff.changed_components.push(ComponentChange {
+                        entity,
+                        key: key.to_string(),
+                        before: before_value.cloned(),
+                        after: after_value.cloned(),
+                    });
+                }
+            }
+
+            if let (Some(before_state), Some(after_state)) =
+                (self.body_state(entity), other.body_state(entity))
+            {
+                if !before_state.approx_eq(&after_state) {
+                    diff.changed_bodies.push(BodyChange {
+                        entity,
+                        before: before_state,
+                        after: after_state,
+                    });
+                }
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Returns the changes made to the world since the snapshot was taken
+    pub fn diff_snapshot(&self, snapshot: &WorldSnapshot) -> Result<WorldDiff> {
+        snapshot.to_world()?.diff(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::spawn_body, Light, Name, Transform};
+
+    fn name_of(world: &World, entity: Entity) -> Result<String> {
+        Ok(world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<Name>()?
+            .0
+            .clone())
+    }
+
+    fn move_body(world: &mut World, entity: Entity, translation: glm::Vec3) -> Result<()> {
+        let handle = world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<RigidBody>()?
+            .handle;
+        let body = world.physics.bodies.get_mut(handle).unwrap();
+        body.set_translation(translation, true);
+        body.set_linvel(glm::vec3(0.0, 1.0, 0.0), true);
+        Ok(())
+    }
+
+    #[test]
+    fn restoring_a_snapshot_undoes_changes() -> Result<()> {
+        let mut world = World::new()?;
+        let parent = spawn_body(
+            &mut world,
+            "Parent",
+            glm::vec3(1.0, 2.0, 3.0),
+            RigidBodyType::Dynamic,
+        )?;
+        let child = world.spawn_child(parent, "Child")?;
+        let state = world.body_state(parent).unwrap();
+        let snapshot = world.snapshot()?;
+
+        world
+            .ecs
+            .entry_mut(parent)?
+            .get_component_mut::<Transform>()?
+            .translation = glm::vec3(4.0, 5.0, 6.0);
+        world.ecs.entry_mut(child)?.get_component_mut::<Name>()?.0 = "Renamed".to_string();
+        world.reparent(child, None, false)?;
+        move_body(&mut world, parent, glm::vec3(7.0, 8.0, 9.0))?;
+        let spawned = world.spawn_child(parent, "Spawned")?;
+        assert!(!world.diff_snapshot(&snapshot)?.is_empty());
+
+        world.restore(&snapshot)?;
+
+        let transform = *world.ecs.entry_ref(parent)?.get_component::<Transform>()?;
+        assert_eq!(transform.translation, glm::vec3(1.0, 2.0, 3.0));
+        assert_eq!(name_of(&world, child)?, "Child");
+        assert_eq!(world.parent_entity(child), Some(parent));
+        assert_eq!(world.children(parent), vec![child]);
+        assert!(world.body_state(parent).unwrap().approx_eq(&state));
+        assert!(!world.ecs.contains(spawned));
+        assert!(world.diff_snapshot(&snapshot)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn diff_reports_entity_and_component_changes() -> Result<()> {
+        let mut world = World::new()?;
+        let kept = spawn_body(
+            &mut world,
+            "Kept",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let removed = world.spawn_child(kept, "Removed")?;
+        let mut other = world.snapshot()?.to_world()?;
+
+        other.despawn(removed)?;
+        let added = other.spawn_child(kept, "Added")?;
+        other.ecs.entry_mut(kept)?.get_component_mut::<Name>()?.0 = "Changed".to_string();
+        other
+            .ecs
+            .entry(kept)
+            .unwrap()
+            .add_component(Light::default());
+        move_body(&mut other, kept, glm::vec3(0.0, 1.0, 0.0))?;
+
+        let diff = world.diff(&other)?;
+
+        assert_eq!(diff.added_entities, vec![added]);
+        assert_eq!(diff.removed_entities, vec![removed]);
+        let changes = diff
+            .entity_changes(kept)
+            .map(|change| (change.key.as_str(), change))
+            .collect::<std::collections::HashMap<_, _>>();
+        let name = changes["name"];
+        assert_eq!(name.before, Some(Value::from("Kept")));
+        assert_eq!(name.after, Some(Value::from("Changed")));
+        assert!(changes["light"].before.is_none());
+        assert!(changes["light"].after.is_some());
+        assert!(changes.contains_key("children"));
+        assert_eq!(diff.changed_bodies.len(), 1);
+        let body = &diff.changed_bodies[0];
+        assert_eq!(body.entity, kept);
+        assert_eq!(body.before.translation, glm::Vec3::zeros());
+        assert_eq!(body.after.translation, glm::vec3(0.0, 1.0, 0.0));
+        assert!(world.diff(&world.snapshot()?.to_world()?)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn body_state_ignores_entities_without_bodies() -> Result<()> {
+        let mut world = World::new()?;
+        let body = spawn_body(
+            &mut world,
+            "Body",
+            glm::vec3(0.0, 2.0, 0.0),
+            RigidBodyType::Fixed,
+        )?;
+        let child = world.spawn_child(body, "Child")?;
+
+        let state = world.body_state(body).unwrap();
+        assert_eq!(state.body_type, RigidBodyType::Fixed);
+        assert_eq!(state.translation, glm::vec3(0.0, 2.0, 0.0));
+        assert_eq!(state.linear_velocity, glm::Vec3::zeros());
+        assert!(world.body_state(child).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn restoring_a_corrupted_snapshot_keeps_the_world() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_body(
+            &mut world,
+            "Body",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        let bytes = world.snapshot()?.as_bytes().to_vec();
+        let corrupted = WorldSnapshot::from_bytes(bytes[..bytes.len() / 2].to_vec());
+
+        assert!(world.restore(&corrupted).is_err());
+        assert!(world.diff_snapshot(&corrupted).is_err());
+
+        assert_eq!(name_of(&world, entity)?, "Body");
+        assert!(world.body_state(entity).is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{Entity, Name, Transform, World};
+use dragonglass_dependencies::{
+    anyhow::Result, nalgebra_glm as glm, rapier3d::dynamics::RigidBodyType,
+};
+
+/// Creates a named entity as the last root of the default scenegraph
+pub fn spawn_root(world: &mut World, name: &str) -> Result<Entity> {
+    let entity = world
+        .ecs
+        .push((Transform::default(), Name(name.to_string())));
+    world.scene.default_scenegraph_mut()?.add_node(entity);
+    Ok(entity)
+}
+
+/// Creates a named root entity at the translation, with a rigid body of the given type
+pub fn spawn_body(
+    world: &mut World,
+    name: &str,
+    translation: glm::Vec3,
+    body_type: RigidBodyType,
+) -> Result<Entity> {
+    let entity = world.ecs.push((
+        Transform {
+            translation,
+            ..Default::default()
+        },
+        Name(name.to_string()),
+    ));
+    world.scene.default_scenegraph_mut()?.add_node(entity);
+    world.add_rigid_body(entity, body_type)?;
+    Ok(entity)
+}
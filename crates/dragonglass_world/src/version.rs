@@ -0,0 +1,197 @@
+use dragonglass_dependencies::{
+    anyhow::{bail, Context, Result},
+    lazy_static::lazy_static,
+    log,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+/// Identifies a versioned `.dga` world file
+pub const WORLD_FILE_MAGIC: [u8; 4] = *b"DGA\0";
+
+/// The version of the world payload written by this build
+pub const WORLD_FILE_VERSION: u32 = 1;
+
+/// Files written before the header was introduced are treated as this version.
+/// Their physics layout predates collider user data and joints,
+/// so they are rejected unless a migration from this version is registered.
+pub const LEGACY_WORLD_FILE_VERSION: u32 = 0;
+
+const HEADER_LENGTH: usize = WORLD_FILE_MAGIC.len() + std::mem::size_of::<u32>();
+
+/// Upgrades a world payload by exactly one version
+pub type WorldMigration = fn(Vec<u8>) -> Result<Vec<u8>>;
+
+lazy_static! {
+    /// Migrations keyed by the version they upgrade from
+    pub static ref WORLD_MIGRATIONS: Arc<RwLock<HashMap<u32, WorldMigration>>> = {
+        Arc::new(RwLock::new(HashMap::new()))
+    };
+}
+
+/// Registers a migration that upgrades payloads from `from_version` to `from_version + 1`
+pub fn register_world_migration(from_version: u32, migration: WorldMigration) -> Result<()> {
+    if from_version >= WORLD_FILE_VERSION {
+        bail!(
+            "Cannot register a migration from version {}, the current world file version is {}!",
+            from_version,
+            WORLD_FILE_VERSION
+        );
+    }
+    let mut migrations = WORLD_MIGRATIONS
+        .write()
+        .expect("Failed to access world migrations!");
+    migrations.insert(from_version, migration);
+    Ok(())
+}
+
+/// Prepends the world file header to a payload
+pub fn write_world_header(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload.len());
+    bytes.extend_from_slice(&WORLD_FILE_MAGIC);
+    bytes.extend_from_slice(&WORLD_FILE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Splits a world file into its version and payload
+pub fn read_world_header(bytes: &[u8]) -> Result<(u32, &[u8])> {
+    if !bytes.starts_with(&WORLD_FILE_MAGIC) {
+        log::warn!("World file has no header, it was saved before the format was versioned");
+        return Ok((LEGACY_WORLD_FILE_VERSION, bytes));
+    }
+    if bytes.len() < HEADER_LENGTH {
+        bail!("World file is truncated, its header is incomplete!");
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&bytes[WORLD_FILE_MAGIC.len()..HEADER_LENGTH]);
+    Ok((u32::from_le_bytes(version), &bytes[HEADER_LENGTH..]))
+}
+
+/// Upgrades a payload from its version to the current world file version
+pub fn migrate_world_payload(version: u32, payload: &[u8]) -> Result<Vec<u8>> {
+    if version > WORLD_FILE_VERSION {
+        bail!(
+            "World file version {} is newer than the supported version {}!",
+            version,
+            WORLD_FILE_VERSION
+        );
+    }
+    let migrations = WORLD_MIGRATIONS
+        .read()
+        .expect("Failed to access world migrations!");
+    let mut payload = payload.to_vec();
+    for from_version in version..WORLD_FILE_VERSION {
+        let migration = match migrations.get(&from_version) {
+            Some(migration) => migration,
+            None if from_version == LEGACY_WORLD_FILE_VERSION => bail!(
+                "Unsupported pre-versioning save! \
+                 World files without a header must be re-exported from their source assets."
+            ),
+            None => bail!(
+                "World file version {} is unsupported, no migration to version {} is registered!",
+                from_version,
+                from_version + 1
+            ),
+        };
+        payload = migration(payload).with_context(|| {
+            format!(
+                "Failed to migrate world file from version {} to version {}!",
+                from_version,
+                from_version + 1
+            )
+        })?;
+        log::info!(
+            "Migrated world file from version {} to version {}",
+            from_version,
+            from_version + 1
+        );
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{World, ENTITY_SERIALIZER};
+    use dragonglass_dependencies::{bincode, legion::serialize::set_entity_serializer};
+
+    /// A world payload as it was written before the header was introduced
+    fn headerless_fixture() -> Result<Vec<u8>> {
+        let mut world = World::new()?;
+        world.add_default_light()?;
+        Ok(set_entity_serializer(&*ENTITY_SERIALIZER, || {
+            bincode::serialize(&world)
+        })?)
+    }
+
+    #[test]
+    fn headers_round_trip() -> Result<()> {
+        let bytes = write_world_header(&[1, 2, 3]);
+
+        let (version, payload) = read_world_header(&bytes)?;
+
+        assert!(bytes.starts_with(&WORLD_FILE_MAGIC));
+        assert_eq!(version, WORLD_FILE_VERSION);
+        assert_eq!(payload, &[1, 2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_headers_are_rejected() {
+        assert!(read_world_header(&WORLD_FILE_MAGIC).is_err());
+    }
+
+    #[test]
+    fn headerless_files_are_legacy() -> Result<()> {
+        let fixture = headerless_fixture()?;
+
+        let (version, payload) = read_world_header(&fixture)?;
+
+        assert_eq!(version, LEGACY_WORLD_FILE_VERSION);
+        assert_eq!(payload, fixture.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn headerless_files_are_rejected() -> Result<()> {
+        let fixture = headerless_fixture()?;
+
+        let error = match World::from_bytes(&fixture) {
+            Ok(_) => panic!("Loaded a headerless world file!"),
+            Err(error) => error,
+        };
+
+        assert!(error
+            .to_string()
+            .contains("Unsupported pre-versioning save"));
+        Ok(())
+    }
+
+    #[test]
+    fn versioned_files_round_trip() -> Result<()> {
+        let mut world = World::new()?;
+        world.add_default_light()?;
+
+        let loaded = World::from_bytes(&world.as_bytes()?)?;
+
+        assert_eq!(loaded.ecs.len(), world.ecs.len());
+        assert_eq!(loaded.scene.name, world.scene.name);
+        Ok(())
+    }
+
+    #[test]
+    fn newer_files_are_rejected() {
+        assert!(migrate_world_payload(WORLD_FILE_VERSION + 1, &[]).is_err());
+    }
+
+    #[test]
+    fn current_payloads_are_not_migrated() -> Result<()> {
+        let payload = migrate_world_payload(WORLD_FILE_VERSION, &[4, 5, 6])?;
+
+        assert_eq!(payload, vec![4, 5, 6]);
+        Ok(())
+    }
+}
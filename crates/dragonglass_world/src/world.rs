@@ -1,4 +1,8 @@
-use crate::{Name, RigidBody, WorldPhysics};
+use crate::{
+    migrate_world_payload, read_world_header, write_world_header, AssetGenerations, ColliderBase,
+    Name, NodeCache, Parent, PhysicsJoint, Ragdoll, RigidBody, RigidBodySettings, WorldFormat,
+    WorldPhysics, COMPONENT_REGISTRY,
+};
 use dragonglass_dependencies::{
     anyhow::{bail, Context, Result},
     bincode,
@@ -9,17 +13,17 @@ use dragonglass_dependencies::{
         DynamicImage, GenericImageView, ImageBuffer, ImageFormat, Pixel, RgbImage,
     },
     lazy_static::lazy_static,
-    legion::{
-        self, serialize::set_entity_serializer, serialize::Canon, storage::Component, EntityStore,
-        IntoQuery, Registry,
-    },
+    legion::{self, serialize::set_entity_serializer, serialize::Canon, EntityStore, IntoQuery},
     log,
     nalgebra::{linalg::QR, Isometry3, Point, Point3, Translation3, UnitQuaternion},
     nalgebra_glm as glm,
-    petgraph::{graph::WalkNeighbors, prelude::*},
+    petgraph::{
+        prelude::*,
+        stable_graph::{StableGraph, WalkNeighbors},
+    },
     rapier3d::{
         dynamics::{RigidBodyBuilder, RigidBodyType},
-        geometry::{ColliderBuilder, InteractionGroups},
+        geometry::{Collider, ColliderBuilder, ColliderHandle, InteractionGroups},
         prelude::{QueryFilter, Ray},
     },
     serde::{de::DeserializeSeed, Deserialize, Deserializer, Serialize, Serializer},
@@ -30,34 +34,12 @@ use std::{
     mem::replace,
     ops::{Index, IndexMut},
     path::Path,
-    sync::{Arc, RwLock},
 };
 
 lazy_static! {
-    pub static ref COMPONENT_REGISTRY: Arc<RwLock<Registry<String>>> = {
-        let mut registry = Registry::default();
-        registry.register::<Name>("name".to_string());
-        registry.register::<Transform>("transform".to_string());
-        registry.register::<Camera>("camera".to_string());
-        registry.register::<MeshRender>("mesh".to_string());
-        registry.register::<Skin>("skin".to_string());
-        registry.register::<Light>("light".to_string());
-        registry.register::<RigidBody>("rigid_body".to_string());
-        Arc::new(RwLock::new(registry))
-    };
     pub static ref ENTITY_SERIALIZER: Canon = Canon::default();
 }
 
-pub fn register_component<T: Component + Serialize + for<'de> Deserialize<'de>>(
-    key: &str,
-) -> Result<()> {
-    let mut registry = COMPONENT_REGISTRY
-        .write()
-        .expect("Failed to access component registry!");
-    registry.register::<T>(key.to_string());
-    Ok(())
-}
-
 pub type Ecs = legion::World;
 pub type Entity = legion::Entity;
 
@@ -74,10 +56,19 @@ pub struct World {
     pub hdr_textures: Vec<Texture>,
     pub geometry: Geometry,
     pub fonts: HashMap<String, SdfFont>,
+    #[serde(skip)]
+    pub generations: AssetGenerations,
+    #[serde(skip)]
+    pub(crate) node_cache: NodeCache,
 }
 
 impl World {
     pub const MAIN_CAMERA_NAME: &'static str = &"Main Camera";
+    const SCALE_EPSILON: f32 = 0.0001;
+    const TRANSLATION_EPSILON: f32 = 0.0001;
+    /// Compared against the distance between unit quaternions, which is about half the angle
+    /// between them. Unlike the angle, this distance stays accurate for tiny rotations.
+    const ROTATION_EPSILON: f32 = 0.0001;
 
     pub fn new() -> Result<World> {
         let mut world = World::default();
@@ -165,34 +156,47 @@ impl World {
         }
     }
 
+    /// Composes the entity's transform with those of its ancestors,
+    /// following parent components so no scenegraph search is needed
     pub fn entity_global_transform_matrix(&self, entity: Entity) -> Result<glm::Mat4> {
-        let mut transform = glm::Mat4::identity();
-        let mut found = false;
-        for graph in self.scene.graphs.iter() {
-            graph.walk(|node_index| {
-                if entity != graph[node_index] {
-                    return Ok(());
-                }
-                transform = self.global_transform(graph, node_index)?;
-                found = true;
-                Ok(())
-            })?;
-            if found {
-                break;
-            }
-        }
-        if !found {
-            // TODO: Maybe returning an error if the global transform of an entity that isn't in the scenegraph is better...
-            // Not found in the scenegraph, so the entity just have a local transform
-            transform = self
+        let mut transform = self
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<Transform>()?
+            .matrix();
+        let mut current = entity;
+        while let Some(parent) = self.parent_entity(current) {
+            let parent_transform = self
                 .ecs
-                .entry_ref(entity)?
+                .entry_ref(parent)?
                 .get_component::<Transform>()?
                 .matrix();
+            transform = parent_transform * transform;
+            current = parent;
         }
         Ok(transform)
     }
 
+    pub fn parent_entity(&self, entity: Entity) -> Option<Entity> {
+        let entry = self.ecs.entry_ref(entity).ok()?;
+        entry.get_component::<Parent>().ok().map(|parent| parent.0)
+    }
+
+    /// Returns the ancestors of an entity, starting with its parent
+    pub fn ancestors(&self, entity: Entity) -> Vec<Entity> {
+        let mut ancestors = Vec::new();
+        let mut current = entity;
+        while let Some(parent) = self.parent_entity(current) {
+            ancestors.push(parent);
+            current = parent;
+        }
+        ancestors
+    }
+
+    pub fn entity_depth(&self, entity: Entity) -> usize {
+        self.ancestors(entity).len()
+    }
+
     pub fn entity_global_transform(&self, entity: Entity) -> Result<Transform> {
         let transform_matrix = self.entity_global_transform_matrix(entity)?;
         Ok(Transform::from(transform_matrix))
@@ -227,6 +231,7 @@ impl World {
         self.animations.clear();
         self.materials.clear();
         self.geometry.clear();
+        self.generations = AssetGenerations::default();
         self.initialize()?;
         Ok(())
     }
@@ -400,20 +405,46 @@ impl World {
         let collider = ColliderBuilder::cylinder(half_height, radius)
             .collision_groups(collision_groups)
             .build();
+        self.attach_collider(entity, collider)?;
+        Ok(())
+    }
 
-        let rigid_body_handle = self
-            .ecs
-            .entry_ref(entity)?
-            .get_component::<RigidBody>()?
-            .handle;
+    /// Attaches a collider to the entity's rigid body and tags it with the entity
+    pub fn attach_collider(
+        &mut self,
+        entity: Entity,
+        mut collider: Collider,
+    ) -> Result<ColliderHandle> {
+        let (rigid_body_handle, scale) = {
+            let entry = self.ecs.entry_ref(entity)?;
+            let rigid_body = entry.get_component::<RigidBody>()?;
+            (rigid_body.handle, rigid_body.scale)
+        };
+
+        let base = ColliderBase {
+            shape: collider.shared_shape().clone(),
+            translation: collider
+                .position_wrt_parent()
+                .map(|position| position.translation.vector)
+                .unwrap_or_else(glm::Vec3::zeros),
+        };
 
-        self.physics.colliders.insert_with_parent(
+        collider.user_data = self.physics.entity_user_data(entity);
+        let handle = self.physics.colliders.insert_with_parent(
             collider,
             rigid_body_handle,
             &mut self.physics.bodies,
         );
+        self.physics.collider_bases.insert(handle, base);
+        self.physics.rescale_collider(handle, &scale);
 
-        Ok(())
+        self.ecs
+            .entry_mut(entity)?
+            .get_component_mut::<RigidBody>()?
+            .colliders
+            .push(handle);
+
+        Ok(handle)
     }
 
     pub fn add_trimesh_collider(
@@ -423,21 +454,14 @@ impl World {
     ) -> Result<()> {
         let entry = self.ecs.entry_ref(entity)?;
         let mesh = entry.get_component::<MeshRender>()?;
-        let transform = self.entity_global_transform(entity)?;
         let mesh = &self.geometry.meshes[&mesh.name];
 
-        // TODO: Add collider handles to component
-        let rigid_body_handle = self
-            .ecs
-            .entry_ref(entity)?
-            .get_component::<RigidBody>()?
-            .handle;
-
+        let mut colliders = Vec::new();
         for primitive in mesh.primitives.iter() {
             let vertices = self.geometry.vertices
                 [primitive.first_vertex..primitive.first_vertex + primitive.number_of_vertices]
                 .iter()
-                .map(|v| Point::from_slice((v.position.component_mul(&transform.scale)).as_slice()))
+                .map(|v| Point::from_slice(v.position.as_slice()))
                 .collect::<Vec<_>>();
 
             let indices = self.geometry.indices
@@ -455,30 +479,29 @@ impl World {
             let collider = ColliderBuilder::trimesh(vertices, indices)
                 .collision_groups(collision_groups)
                 .build();
-            self.physics.colliders.insert_with_parent(
-                collider,
-                rigid_body_handle,
-                &mut self.physics.bodies,
-            );
+            colliders.push(collider);
+        }
+        for collider in colliders.into_iter() {
+            self.attach_collider(entity, collider)?;
         }
         Ok(())
     }
 
     pub fn add_rigid_body(&mut self, entity: Entity, rigid_body_type: RigidBodyType) -> Result<()> {
+        let global_transform = self.entity_global_transform(entity)?;
         let handle = {
-            let isometry =
-                Transform::from(self.entity_global_transform_matrix(entity)?).as_isometry();
-
             // Insert a corresponding rigid body
             let rigid_body = RigidBodyBuilder::new(rigid_body_type)
-                .position(isometry)
+                .position(global_transform.as_isometry())
                 .build();
             self.physics.bodies.insert(rigid_body)
         };
+        let mut rigid_body = RigidBody::new(handle);
+        rigid_body.scale = global_transform.scale;
         self.ecs
             .entry(entity)
             .context("")?
-            .add_component(RigidBody::new(handle));
+            .add_component(rigid_body);
         Ok(())
     }
 
@@ -490,6 +513,168 @@ impl World {
         Ok(())
     }
 
+    /// Returns the entity's rigid body type and collider mass, if it has a rigid body
+    pub fn rigid_body_settings(&self, entity: Entity) -> Result<Option<RigidBodySettings>> {
+        let entry = self.ecs.entry_ref(entity)?;
+        let rigid_body = match entry.get_component::<RigidBody>() {
+            Ok(rigid_body) => rigid_body,
+            Err(_) => return Ok(None),
+        };
+        let body = self
+            .physics
+            .bodies
+            .get(rigid_body.handle)
+            .context("Failed to find the entity's rigid body!")?;
+        let mass = rigid_body
+            .colliders
+            .iter()
+            .filter_map(|handle| self.physics.colliders.get(*handle))
+            .map(|collider| collider.mass_properties().mass())
+            .sum();
+        Ok(Some(RigidBodySettings {
+            body_type: body.body_type(),
+            mass,
+        }))
+    }
+
+    /// Sets the entity's rigid body type, and sets the density of its colliders
+    /// so that their total mass matches the settings
+    pub fn set_rigid_body_settings(
+        &mut self,
+        entity: Entity,
+        settings: &RigidBodySettings,
+    ) -> Result<()> {
+        let (handle, colliders) = {
+            let entry = self.ecs.entry_ref(entity)?;
+            let rigid_body = entry
+                .get_component::<RigidBody>()
+                .context("Failed to set rigid body settings, the entity has no rigid body!")?;
+            (rigid_body.handle, rigid_body.colliders.clone())
+        };
+        let body = self
+            .physics
+            .bodies
+            .get_mut(handle)
+            .context("Failed to find the entity's rigid body!")?;
+        body.set_body_type(settings.body_type);
+        body.wake_up(true);
+
+        let volume = colliders
+            .iter()
+            .filter_map(|handle| self.physics.colliders.get(*handle))
+            .map(|collider| collider.shape().mass_properties(1.0).mass())
+            .sum::<f32>();
+        if volume > 0.0 {
+            let density = settings.mass.max(0.0) / volume;
+            for handle in colliders.iter() {
+                if let Some(collider) = self.physics.colliders.get_mut(*handle) {
+                    collider.set_density(density);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Connects the entity's rigid body to the rigid body of the joint's target entity
+    pub fn add_joint(&mut self, entity: Entity, mut joint: PhysicsJoint) -> Result<()> {
+        if self
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<PhysicsJoint>()
+            .is_ok()
+        {
+            self.remove_joint(entity)?;
+        }
+        let body = self
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<RigidBody>()?
+            .handle;
+        let target_body = self
+            .ecs
+            .entry_ref(joint.target)?
+            .get_component::<RigidBody>()
+            .context("The target of a joint must have a rigid body!")?
+            .handle;
+        joint.handle = self.physics.insert_joint(target_body, body, &joint);
+        if joint.handle.is_none() {
+            bail!("Failed to create joint! Multibody joints cannot form loops.");
+        }
+        self.ecs
+            .entry(entity)
+            .context("Failed to find entity!")?
+            .add_component(joint);
+        Ok(())
+    }
+
+    pub fn remove_joint(&mut self, entity: Entity) -> Result<()> {
+        let mut entry = self.ecs.entry(entity).context("Failed to find entity!")?;
+        let handle = entry.get_component::<PhysicsJoint>()?.handle;
+        entry.remove_component::<PhysicsJoint>();
+        if let Some(handle) = handle {
+            self.physics.remove_joint(handle);
+        }
+        Ok(())
+    }
+
+    /// Multibody joints are not serialized with the physics world,
+    /// so they are rebuilt from the joint components after loading
+    pub(crate) fn rebuild_multibody_joints(&mut self) -> Result<()> {
+        let mut query = <(Entity, &RigidBody, &PhysicsJoint)>::query();
+        let joints = query
+            .iter(&self.ecs)
+            .filter(|(_, _, joint)| joint.multibody)
+            .map(|(entity, rigid_body, joint)| (*entity, rigid_body.handle, joint.clone()))
+            .collect::<Vec<_>>();
+        for (entity, body, joint) in joints.into_iter() {
+            let target_body = self
+                .ecs
+                .entry_ref(joint.target)?
+                .get_component::<RigidBody>()?
+                .handle;
+            let handle = self.physics.insert_joint(target_body, body, &joint);
+            self.ecs
+                .entry_mut(entity)?
+                .get_component_mut::<PhysicsJoint>()?
+                .handle = handle;
+        }
+        Ok(())
+    }
+
+    /// Collider user data is not serialized with the physics world,
+    /// so the colliders of rigid bodies and ragdolls are tagged again after loading.
+    /// This also makes colliders from files written before user data was assigned pickable.
+    pub(crate) fn rebuild_collider_user_data(&mut self) -> Result<()> {
+        self.physics.clear_entity_user_data();
+
+        let mut query = <(Entity, &RigidBody)>::query();
+        let mut colliders = query
+            .iter(&self.ecs)
+            .flat_map(|(entity, rigid_body)| {
+                rigid_body
+                    .colliders
+                    .iter()
+                    .map(move |collider| (*entity, *collider))
+            })
+            .collect::<Vec<_>>();
+
+        let mut query = <&Ragdoll>::query();
+        colliders.extend(query.iter(&self.ecs).flat_map(|ragdoll| {
+            ragdoll
+                .bones
+                .iter()
+                .map(|bone| (bone.target, bone.collider))
+        }));
+
+        for (entity, handle) in colliders.into_iter() {
+            let user_data = self.physics.entity_user_data(entity);
+            if let Some(collider) = self.physics.colliders.get_mut(handle) {
+                collider.user_data = user_data;
+            }
+        }
+        Ok(())
+    }
+
     pub fn flatten_scenegraphs(&self) -> Vec<SceneGraphNode> {
         let mut offset = 0;
         self.scene
@@ -540,59 +725,58 @@ impl World {
         groups: InteractionGroups,
     ) -> Result<Option<Entity>> {
         let ray = self.mouse_ray(mouse_ray_configuration)?;
-
-        let hit = self.physics.query_pipeline.cast_ray(
-            &self.physics.bodies,
-            &self.physics.colliders,
-            &ray,
-            interact_distance,
-            true,
-            QueryFilter::from(groups),
-        );
-
-        let mut picked_entity = None;
-        if let Some((handle, _)) = hit {
-            let collider = &self.physics.colliders[handle];
-            let rigid_body_handle = collider
-                .parent()
-                .context("Failed to get a collider's parent!")?;
-            let mut query = <(Entity, &RigidBody)>::query();
-            for (entity, rigid_body) in query.iter(&self.ecs) {
-                if rigid_body.handle == rigid_body_handle {
-                    picked_entity = Some(*entity);
-                    break;
-                }
-            }
-        }
-
-        Ok(picked_entity)
+        let hit = self.cast_ray_nearest(&ray, interact_distance, QueryFilter::from(groups));
+        Ok(hit.and_then(|hit| hit.entity))
     }
 
     pub fn tick(&mut self, delta_time: f32) -> Result<()> {
         self.physics.update(delta_time);
-        self.sync_all_rigid_bodies();
+        self.sync_all_rigid_bodies()?;
+        self.sync_ragdolls()?;
         Ok(())
     }
 
     pub fn as_bytes(&self) -> Result<Vec<u8>> {
-        Ok(set_entity_serializer(&*ENTITY_SERIALIZER, || {
-            bincode::serialize(&self)
-        })?)
+        let payload = set_entity_serializer(&*ENTITY_SERIALIZER, || bincode::serialize(&self))?;
+        Ok(write_world_header(&payload))
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<World> {
-        Ok(set_entity_serializer(&*ENTITY_SERIALIZER, || {
-            bincode::deserialize(bytes)
-        })?)
+        let (version, payload) = read_world_header(bytes)?;
+        let payload = migrate_world_payload(version, payload)?;
+        let mut world: World =
+            set_entity_serializer(&*ENTITY_SERIALIZER, || bincode::deserialize(&payload))
+                .with_context(|| {
+                    format!(
+                        "Failed to deserialize world file version {}, \
+                         a component may be unregistered or its layout may have changed!",
+                        version
+                    )
+                })?;
+        world.rebuild_multibody_joints()?;
+        world.rebuild_collider_user_data()?;
+        world.sync_hierarchy()?;
+        Ok(world)
     }
 
+    /// Saves the world in the format matching the path's extension
     pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        match WorldFormat::from_path(path) {
+            WorldFormat::Binary => std::fs::write(path, &self.as_bytes()?)?,
+            WorldFormat::Ron => self.save_ron(path)?,
+        }
         log::info!("Saved world!");
-        Ok(std::fs::write(path, &self.as_bytes()?)?)
+        Ok(())
     }
 
+    /// Loads the world in the format matching the path's extension
     pub fn load(&mut self, path: impl AsRef<Path>) -> Result<()> {
-        let world = Self::from_bytes(&std::fs::read(path)?)?;
+        let path = path.as_ref();
+        let world = match WorldFormat::from_path(path) {
+            WorldFormat::Binary => Self::from_bytes(&std::fs::read(path)?)?,
+            WorldFormat::Ron => Self::from_ron(path)?,
+        };
         let _ = replace(self, world);
         Ok(())
     }
@@ -604,14 +788,25 @@ impl World {
 
     /// Sync the entity's physics rigid body with its transform
     pub fn sync_rigid_body_to_transform(&mut self, entity: Entity) -> Result<()> {
-        let entry = self.ecs.entry_ref(entity)?;
-        let rigid_body = entry.get_component::<RigidBody>()?;
-        let transform = entry.get_component::<Transform>()?;
-        if let Some(body) = self.physics.bodies.get_mut(rigid_body.handle) {
-            let mut position = body.position().clone();
-            position.translation.vector = transform.translation;
+        let global_transform = self.entity_global_transform(entity)?;
+        let rigid_body_handle = self
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<RigidBody>()?
+            .handle;
+        if let Some(body) = self.physics.bodies.get_mut(rigid_body_handle) {
+            let mut position = global_transform.as_isometry();
+            // Bodies with locked rotations keep their own orientation
+            if body.is_rotation_locked().iter().any(|locked| *locked) {
+                position.rotation = body.position().rotation;
+            }
             body.set_position(position, true);
         }
+        self.rescale_rigid_body(entity, &global_transform.scale)?;
+        self.ecs
+            .entry_mut(entity)?
+            .get_component_mut::<RigidBody>()?
+            .synced_position = Some(global_transform.as_isometry());
         Ok(())
     }
 
@@ -622,29 +817,144 @@ impl World {
             .entry_ref(entity)?
             .get_component::<RigidBody>()?
             .handle;
-        let mut entry = self.ecs.entry(entity).context("Failed to find entity!")?;
-        let transform = entry.get_component_mut::<Transform>()?;
-        if let Some(body) = self.physics.bodies.get(rigid_body_handle) {
-            let position = body.position();
-            transform.translation = position.translation.vector;
-            transform.rotation = *position.rotation.quaternion();
-        }
+        let position = match self.physics.bodies.get(rigid_body_handle) {
+            Some(body) => *body.position(),
+            None => return Ok(()),
+        };
+        self.set_transform_from_isometry(entity, &position)?;
         if let Some(body) = self.physics.bodies.get_mut(rigid_body_handle) {
             body.wake_up(true);
         }
         Ok(())
     }
 
-    /// Sync the render transforms with the physics rigid bodies
-    pub fn sync_all_rigid_bodies(&mut self) {
-        let mut query = <(&RigidBody, &mut Transform)>::query();
-        for (rigid_body, transform) in query.iter_mut(&mut self.ecs) {
-            if let Some(body) = self.physics.bodies.get(rigid_body.handle) {
-                let position = body.position();
-                transform.translation = position.translation.vector;
-                transform.rotation = *position.rotation.quaternion();
+    /// Moves the entity's transform to a global physics isometry, keeping its scale
+    fn set_transform_from_isometry(
+        &mut self,
+        entity: Entity,
+        isometry: &Isometry3<f32>,
+    ) -> Result<()> {
+        let local_transform = self.local_transform_from_isometry(entity, isometry)?;
+        let mut entry = self.ecs.entry_mut(entity)?;
+        let transform = entry.get_component_mut::<Transform>()?;
+        transform.translation = local_transform.translation;
+        transform.rotation = local_transform.rotation;
+        entry.get_component_mut::<RigidBody>()?.synced_position = Some(*isometry);
+        Ok(())
+    }
+
+    /// Whether two global positions are the same within the sync tolerances
+    fn isometries_match(first: &Isometry3<f32>, second: &Isometry3<f32>) -> bool {
+        let translation_distance =
+            glm::distance(&first.translation.vector, &second.translation.vector);
+        // A quaternion and its negation describe the same rotation
+        let (first, second) = (first.rotation.coords, second.rotation.coords);
+        let rotation_distance = (first - second).norm().min((first + second).norm());
+        translation_distance < Self::TRANSLATION_EPSILON
+            && rotation_distance < Self::ROTATION_EPSILON
+    }
+
+    /// Converts a global physics isometry into a transform local to the entity's parent
+    fn local_transform_from_isometry(
+        &self,
+        entity: Entity,
+        isometry: &Isometry3<f32>,
+    ) -> Result<Transform> {
+        let translation = isometry.translation.vector;
+        let rotation = *isometry.rotation.quaternion();
+        match self.parent_entity(entity) {
+            Some(parent) => {
+                let parent_matrix = self.entity_global_transform_matrix(parent)?;
+                let global_matrix =
+                    Transform::new(translation, rotation, glm::vec3(1.0, 1.0, 1.0)).matrix();
+                let local = Transform::from(glm::inverse(&parent_matrix) * global_matrix);
+                Ok(Transform::new(
+                    local.translation,
+                    glm::quat_normalize(&local.rotation),
+                    glm::vec3(1.0, 1.0, 1.0),
+                ))
+            }
+            None => Ok(Transform::new(
+                translation,
+                rotation,
+                glm::vec3(1.0, 1.0, 1.0),
+            )),
+        }
+    }
+
+    /// Rebuilds the entity's colliders if its global scale no longer matches them
+    fn rescale_rigid_body(&mut self, entity: Entity, scale: &glm::Vec3) -> Result<()> {
+        let (colliders, previous_scale) = {
+            let entry = self.ecs.entry_ref(entity)?;
+            let rigid_body = entry.get_component::<RigidBody>()?;
+            (rigid_body.colliders.clone(), rigid_body.scale)
+        };
+        if glm::distance(&previous_scale, scale) < Self::SCALE_EPSILON {
+            return Ok(());
+        }
+        for collider in colliders.into_iter() {
+            self.physics.rescale_collider(collider, scale);
+        }
+        self.ecs
+            .entry_mut(entity)?
+            .get_component_mut::<RigidBody>()?
+            .scale = *scale;
+        Ok(())
+    }
+
+    /// Sync the render transforms with the physics rigid bodies.
+    /// Dynamic and velocity based kinematic bodies drive their entity's transform,
+    /// unless the transform was edited since the last sync, in which case the body is moved to it.
+    /// Fixed and position based kinematic bodies follow their entity
+    /// so they move along with any non-physics parent nodes.
+    pub fn sync_all_rigid_bodies(&mut self) -> Result<()> {
+        let mut query = <(Entity, &RigidBody)>::query();
+        let rigid_bodies = query
+            .iter(&self.ecs)
+            .map(|(entity, rigid_body)| (*entity, rigid_body.handle, rigid_body.synced_position))
+            .collect::<Vec<_>>();
+
+        for (entity, handle, synced_position) in rigid_bodies.into_iter() {
+            let (body_type, position) = match self.physics.bodies.get(handle) {
+                Some(body) => (body.body_type(), *body.position()),
+                None => continue,
+            };
+
+            let global_transform = self.entity_global_transform(entity)?;
+            self.rescale_rigid_body(entity, &global_transform.scale)?;
+            let isometry = global_transform.as_isometry();
+
+            let simulated = matches!(
+                body_type,
+                RigidBodyType::Dynamic | RigidBodyType::KinematicVelocityBased
+            );
+            if simulated {
+                let edited = synced_position
+                    .map_or(false, |synced| !Self::isometries_match(&synced, &isometry));
+                if edited {
+                    self.sync_rigid_body_to_transform(entity)?;
+                } else {
+                    self.set_transform_from_isometry(entity, &position)?;
+                }
+                continue;
+            }
+
+            if !Self::isometries_match(&isometry, &position) {
+                if let Some(body) = self.physics.bodies.get_mut(handle) {
+                    match body_type {
+                        RigidBodyType::KinematicPositionBased => {
+                            body.set_next_kinematic_position(isometry)
+                        }
+                        _ => body.set_position(isometry, true),
+                    }
+                }
             }
+            self.ecs
+                .entry_mut(entity)?
+                .get_component_mut::<RigidBody>()?
+                .synced_position = Some(isometry);
         }
+        Ok(())
     }
 
     pub fn entity_model_matrix(
@@ -698,24 +1008,25 @@ pub struct MouseRayConfiguration {
     pub mouse_position: glm::Vec2,
 }
 
-fn serialize_ecs<S>(ecs: &Ecs, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_ecs<S>(ecs: &Ecs, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let registry = (&*COMPONENT_REGISTRY)
+    let components = COMPONENT_REGISTRY
         .read()
         .expect("Failed to get the component registry lock!");
-    ecs.as_serializable(legion::any(), &*registry, &*ENTITY_SERIALIZER)
+    ecs.as_serializable(legion::any(), &components.registry, &*ENTITY_SERIALIZER)
         .serialize(serializer)
 }
 
-fn deserialize_ecs<'de, D>(deserializer: D) -> Result<Ecs, D::Error>
+pub(crate) fn deserialize_ecs<'de, D>(deserializer: D) -> Result<Ecs, D::Error>
 where
     D: Deserializer<'de>,
 {
     (&*COMPONENT_REGISTRY)
         .read()
         .expect("Failed to get the component registry lock!")
+        .registry
         .as_deserialize(&*ENTITY_SERIALIZER)
         .deserialize(deserializer)
 }
@@ -751,7 +1062,9 @@ impl Scene {
         action: &mut impl FnMut(&SceneGraph, NodeIndex) -> Result<()>,
     ) -> Result<()> {
         for graph in self.graphs.iter() {
-            graph.recurse(NodeIndex::new(0), action)?;
+            for root in graph.roots() {
+                graph.recurse(root, action)?;
+            }
         }
         Ok(())
     }
@@ -882,7 +1195,7 @@ impl Default for LightKind {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub struct Camera {
     pub name: String,
@@ -906,14 +1219,14 @@ impl Camera {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub enum Projection {
     Perspective(PerspectiveCamera),
     Orthographic(OrthographicCamera),
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub struct PerspectiveCamera {
     pub aspect_ratio: Option<f32>,
@@ -938,7 +1251,7 @@ impl PerspectiveCamera {
     }
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub struct OrthographicCamera {
     pub x_mag: f32,
@@ -972,14 +1285,31 @@ impl OrthographicCamera {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A named viewpoint that a camera can return to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct CameraBookmark {
+    pub name: String,
+    pub translation: glm::Vec3,
+    pub rotation: glm::Quat,
+    /// The point the camera was looking at, so orbiting resumes around it
+    pub focus: glm::Vec3,
+    pub projection: Projection,
+}
+
+/// The viewpoints saved for a camera, which are stored with the scene alongside it
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "dragonglass_dependencies::serde")]
+pub struct CameraBookmarks(pub Vec<CameraBookmark>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub struct Skin {
     pub name: String,
     pub joints: Vec<Joint>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
 pub struct Joint {
     pub target: Entity,
@@ -1096,6 +1426,7 @@ impl Geometry {
     pub fn clear(&mut self) {
         self.vertices.clear();
         self.indices.clear();
+        self.meshes.clear();
     }
 }
 
@@ -1442,7 +1773,7 @@ impl Default for Filter {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "dragonglass_dependencies::serde")]
-pub struct SceneGraph(pub Graph<Entity, ()>);
+pub struct SceneGraph(pub StableGraph<Entity, ()>);
 
 impl Default for SceneGraph {
     fn default() -> Self {
@@ -1452,11 +1783,11 @@ impl Default for SceneGraph {
 
 impl SceneGraph {
     pub fn new() -> Self {
-        Self(Graph::<Entity, ()>::new())
+        Self(StableGraph::<Entity, ()>::new())
     }
 
     pub fn number_of_nodes(&self) -> usize {
-        self.0.raw_nodes().len()
+        self.0.node_count()
     }
 
     pub fn add_node(&mut self, node: Entity) -> NodeIndex {
@@ -1472,6 +1803,127 @@ impl SceneGraph {
         incoming_walker.next_node(&self.0)
     }
 
+    /// Returns the nodes without parents
+    pub fn roots(&self) -> Vec<NodeIndex> {
+        self.0
+            .node_indices()
+            .filter(|index| !self.has_parents(*index))
+            .collect()
+    }
+
+    /// Returns the children of a node in sibling order
+    pub fn children(&self, index: NodeIndex) -> Vec<NodeIndex> {
+        // Petgraph yields the most recently added edge first
+        let mut children = self
+            .0
+            .neighbors_directed(index, Outgoing)
+            .collect::<Vec<_>>();
+        children.reverse();
+        children
+    }
+
+    /// Returns the node and all of its descendants, parents before children
+    pub fn descendants(&self, index: NodeIndex) -> Vec<NodeIndex> {
+        let mut descendants = Vec::new();
+        let mut dfs = Dfs::new(&self.0, index);
+        while let Some(node_index) = dfs.next(&self.0) {
+            descendants.push(node_index);
+        }
+        descendants
+    }
+
+    pub fn is_descendant(&self, index: NodeIndex, ancestor: NodeIndex) -> bool {
+        let mut current = index;
+        while let Some(parent) = self.parent_of(current) {
+            if parent == ancestor {
+                return true;
+            }
+            current = parent;
+        }
+        false
+    }
+
+    /// Moves a node under a new parent, or makes it a root if no parent is given.
+    /// The node is appended after its new siblings.
+    pub fn set_parent(&mut self, index: NodeIndex, parent: Option<NodeIndex>) -> Result<()> {
+        if let Some(parent) = parent {
+            if parent == index || self.is_descendant(parent, index) {
+                bail!("Failed to set parent, a node cannot be parented to its own descendant!");
+            }
+        }
+        if let Some(edge) = self
+            .parent_of(index)
+            .and_then(|old_parent| self.0.find_edge(old_parent, index))
+        {
+            self.0.remove_edge(edge);
+        }
+        if let Some(parent) = parent {
+            self.add_edge(parent, index);
+        }
+        Ok(())
+    }
+
+    /// Moves a node to a new position among its siblings.
+    /// Roots are ordered by node index, so reordering a root rebuilds the graph
+    /// and the indices of all nodes may change.
+    pub fn set_sibling_index(&mut self, index: NodeIndex, sibling_index: usize) -> Result<()> {
+        let parent = match self.parent_of(index) {
+            Some(parent) => parent,
+            None => {
+                self.set_root_index(index, sibling_index);
+                return Ok(());
+            }
+        };
+        let mut children = self.children(parent);
+        children.retain(|child| *child != index);
+        children.insert(sibling_index.min(children.len()), index);
+        for child in children.iter() {
+            if let Some(edge) = self.0.find_edge(parent, *child) {
+                self.0.remove_edge(edge);
+            }
+        }
+        children
+            .into_iter()
+            .for_each(|child| self.add_edge(parent, child));
+        Ok(())
+    }
+
+    fn set_root_index(&mut self, index: NodeIndex, root_index: usize) {
+        let mut roots = self.roots();
+        roots.retain(|root| *root != index);
+        roots.insert(root_index.min(roots.len()), index);
+
+        let mut graph = StableGraph::new();
+        for root in roots.into_iter() {
+            self.copy_subtree(root, None, &mut graph);
+        }
+        self.0 = graph;
+    }
+
+    fn copy_subtree(
+        &self,
+        index: NodeIndex,
+        parent: Option<NodeIndex>,
+        graph: &mut StableGraph<Entity, ()>,
+    ) {
+        let copy = graph.add_node(self[index]);
+        if let Some(parent) = parent {
+            graph.add_edge(parent, copy, ());
+        }
+        for child in self.children(index) {
+            self.copy_subtree(child, Some(copy), graph);
+        }
+    }
+
+    /// Removes a node and its descendants, returning their entities.
+    /// The indices of all other nodes remain valid.
+    pub fn remove_subtree(&mut self, index: NodeIndex) -> Vec<Entity> {
+        self.descendants(index)
+            .into_iter()
+            .filter_map(|node_index| self.0.remove_node(node_index))
+            .collect()
+    }
+
     pub fn collect_nodes(&self) -> Result<Vec<SceneGraphNode>> {
         let mut nodes = Vec::new();
         let mut linear_offset = 0;
@@ -1570,4 +2022,159 @@ impl SdfFont {
         let texture = Texture::from_file(texture_path)?;
         Ok(Self { texture, font })
     }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::spawn_body;
+
+    fn body_position(world: &World, entity: Entity) -> Result<Isometry3<f32>> {
+        let handle = world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<RigidBody>()?
+            .handle;
+        Ok(*world
+            .physics
+            .bodies
+            .get(handle)
+            .context("Missing body!")?
+            .position())
+    }
+
+    fn set_translation(world: &mut World, entity: Entity, translation: glm::Vec3) -> Result<()> {
+        world
+            .ecs
+            .entry_mut(entity)?
+            .get_component_mut::<Transform>()?
+            .translation = translation;
+        Ok(())
+    }
+
+    #[test]
+    fn velocity_based_bodies_drive_their_transform() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_body(
+            &mut world,
+            "Body",
+            glm::Vec3::zeros(),
+            RigidBodyType::KinematicVelocityBased,
+        )?;
+        let handle = world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<RigidBody>()?
+            .handle;
+        world
+            .physics
+            .bodies
+            .get_mut(handle)
+            .context("Missing body!")?
+            .set_linvel(glm::vec3(1.0, 0.0, 0.0), true);
+
+        world.tick(0.5)?;
+        world.tick(0.5)?;
+
+        let translation = world.entity_global_transform(entity)?.translation;
+        assert!(glm::distance(&translation, &glm::vec3(1.0, 0.0, 0.0)) < 0.001);
+        assert!(
+            glm::distance(
+                &body_position(&world, entity)?.translation.vector,
+                &translation
+            ) < 0.001
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn edited_dynamic_bodies_move_to_their_transform() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_body(
+            &mut world,
+            "Body",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        world.sync_all_rigid_bodies()?;
+
+        set_translation(&mut world, entity, glm::vec3(5.0, 0.0, 0.0))?;
+        world.sync_all_rigid_bodies()?;
+
+        let position = body_position(&world, entity)?;
+        assert_eq!(position.translation.vector, glm::vec3(5.0, 0.0, 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn unedited_dynamic_bodies_keep_their_position() -> Result<()> {
+        let mut world = World::new()?;
+        let entity = spawn_body(
+            &mut world,
+            "Body",
+            glm::Vec3::zeros(),
+            RigidBodyType::Dynamic,
+        )?;
+        world.sync_all_rigid_bodies()?;
+
+        let handle = world
+            .ecs
+            .entry_ref(entity)?
+            .get_component::<RigidBody>()?
+            .handle;
+        let moved = Isometry3::translation(0.0, -2.0, 0.0);
+        world
+            .physics
+            .bodies
+            .get_mut(handle)
+            .context("Missing body!")?
+            .set_position(moved, true);
+        world.sync_all_rigid_bodies()?;
+
+        assert_eq!(body_position(&world, entity)?, moved);
+        let translation = world.entity_global_transform(entity)?.translation;
+        assert!(glm::distance(&translation, &glm::vec3(0.0, -2.0, 0.0)) < 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_bodies_follow_their_parent() -> Result<()> {
+        let mut world = World::new()?;
+        let parent = world
+            .ecs
+            .push((Transform::default(), Name("Parent".to_string())));
+        world.scene.default_scenegraph_mut()?.add_node(parent);
+        let entity = world.spawn_child(parent, "Body")?;
+        world.add_rigid_body(entity, RigidBodyType::Fixed)?;
+
+        set_translation(&mut world, parent, glm::vec3(0.0, 3.0, 0.0))?;
+        world.sync_all_rigid_bodies()?;
+
+        let position = body_position(&world, entity)?;
+        assert!(glm::distance(&position.translation.vector, &glm::vec3(0.0, 3.0, 0.0)) < 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn isometries_match_within_tolerance() {
+        let rotation = UnitQuaternion::from_euler_angles(0.3, 0.2, 0.1);
+        let isometry = Isometry3::from_parts(Translation3::new(1.0, 2.0, 3.0), rotation);
+        let negated = Isometry3::from_parts(
+            isometry.translation,
+            UnitQuaternion::new_unchecked(-rotation.into_inner()),
+        );
+        let rotated = Isometry3::from_parts(
+            isometry.translation,
+            rotation * UnitQuaternion::from_euler_angles(0.01, 0.0, 0.0),
+        );
+        let moved = Isometry3::from_parts(Translation3::new(1.0, 2.0, 3.01), rotation);
+
+        assert!(World::isometries_match(&isometry, &negated));
+        assert!(!World::isometries_match(&isometry, &rotated));
+        assert!(!World::isometries_match(&isometry, &moved));
+    }
 }